@@ -5,9 +5,10 @@ extern crate test;
 
 use test::Bencher;
 use piston_meta::*;
+use std::sync::Arc;
 
-#[bench]
-fn json(bencher: &mut Bencher) {
+/// Returns the text and rules of the JSON benchmarks.
+fn json_rules() -> (&'static str, Vec<(Arc<String>, Rule)>) {
     let text = r#"
     {
        "materials": {
@@ -59,13 +60,11 @@ fn json(bencher: &mut Bencher) {
         _ => {}
     }
 
-    bencher.iter(|| {
-        let _ = parse(&rules, text).unwrap();
-    });
+    (text, rules)
 }
 
-#[bench]
-fn ron(bencher: &mut Bencher) {
+/// Returns the text and rules of the RON benchmarks.
+fn ron_rules() -> (&'static str, Vec<(Arc<String>, Rule)>) {
     let text = r#"
     Scene(
         materials: {
@@ -117,7 +116,39 @@ fn ron(bencher: &mut Bencher) {
         _ => {}
     }
 
+    (text, rules)
+}
+
+#[bench]
+fn json(bencher: &mut Bencher) {
+    let (text, rules) = json_rules();
+    bencher.iter(|| {
+        let _ = parse(&rules, text).unwrap();
+    });
+}
+
+#[bench]
+fn json_program(bencher: &mut Bencher) {
+    let (text, rules) = json_rules();
+    let program = Program::new(&rules);
+    bencher.iter(|| {
+        let _ = program.parse(text).unwrap();
+    });
+}
+
+#[bench]
+fn ron(bencher: &mut Bencher) {
+    let (text, rules) = ron_rules();
     bencher.iter(|| {
         let _ = parse(&rules, text).unwrap();
     });
 }
+
+#[bench]
+fn ron_program(bencher: &mut Bencher) {
+    let (text, rules) = ron_rules();
+    let program = Program::new(&rules);
+    bencher.iter(|| {
+        let _ = program.parse(text).unwrap();
+    });
+}
@@ -0,0 +1,21 @@
+extern crate piston_meta;
+
+use piston_meta::parse;
+use piston_meta::bootstrap;
+use std::fs::File;
+use std::io::{ Read, Write };
+
+// Generates `src/bootstrap/rules.rs` from `assets/self-syntax.txt`.
+fn main() {
+    let mut source = String::new();
+    File::open("assets/self-syntax.txt").unwrap()
+        .read_to_string(&mut source).unwrap();
+    let rules = bootstrap::convert(
+        &parse(&bootstrap::rules(), &source).unwrap(),
+        &mut vec![]
+    ).unwrap();
+    let code = bootstrap::codegen(&rules, "");
+    File::create("src/bootstrap/rules.rs").unwrap()
+        .write_all(code.as_bytes()).unwrap();
+    println!("Generated `src/bootstrap/rules.rs`");
+}
@@ -0,0 +1,877 @@
+use std::sync::Arc;
+
+use super::syntax::write_node;
+use {
+    Comments,
+    DebugId,
+    Rule,
+};
+
+/// Generates a Rust module for parsing with rules.
+///
+/// The module has a function `rules` that returns the rules,
+/// and a function `parse` that parses text with the last rule.
+/// Each rule is parsed by a function of its own, which gives the same
+/// meta data and errors as parsing with the rules.
+/// This can be called from a build script,
+/// such that the meta language is not parsed at runtime.
+///
+/// The generated code uses this library by the path `krate`,
+/// for example `"piston_meta"`, or an empty path inside this library.
+pub fn codegen(rules: &[(Arc<String>, Rule)], krate: &str) -> String {
+    let mut gen = Generator {
+        names: vec![],
+        imports: vec!["MetaData", "ParseError", "Range", "Rule", "update_refs"],
+        support: vec!["parse_with"],
+        rule: 0,
+        count: 0,
+        queue: vec![],
+        comments: vec![],
+    };
+
+    let mut rules_fn = String::new();
+    for (i, r) in rules.iter().enumerate() {
+        rules_fn.push('\n');
+        let mut comment = String::new();
+        write_node(i + 1, &r.0, &r.1, &[], &mut comment);
+        if comment.contains('\n') {
+            rules_fn.push_str("    /*\n");
+            for line in comment.lines() {
+                rules_fn.push_str("    ");
+                rules_fn.push_str(line);
+                rules_fn.push('\n');
+            }
+            rules_fn.push_str("    */\n");
+        } else {
+            rules_fn.push_str("    // ");
+            rules_fn.push_str(&comment);
+            rules_fn.push('\n');
+        }
+        rules_fn.push_str(&format!("    rules.push(({}, ", arc(&r.0)));
+        gen.write_rule(&r.1, 1, &mut rules_fn);
+        rules_fn.push_str("));\n");
+    }
+    rules_fn.push('\n');
+    rules_fn.push_str("    update_refs(&mut rules);\n");
+    rules_fn.push_str("    rules\n");
+    rules_fn.push_str("}\n");
+
+    let mut start = String::new();
+    start.push_str("/// Returns the rules.\n");
+    start.push_str("pub fn rules() -> Vec<(Arc<String>, Rule)> {\n");
+    if gen.comments.len() > 0 {
+        gen.import("Comments");
+        start.push_str("    let comments = vec![\n");
+        for c in &gen.comments {
+            let line: Vec<String> = c.line.iter().map(|s| arc(s)).collect();
+            let block: Vec<String> = c.block.iter()
+                .map(|&(ref open, ref close)| format!("({}, {})", arc(open), arc(close)))
+                .collect();
+            start.push_str(&format!("        Arc::new(Comments {{\n\
+                \x20           line: vec![{}],\n\
+                \x20           block: vec![{}],\n\
+                \x20           nested: {},\n\
+                \x20       }}),\n", line.join(", "), block.join(", "), c.nested));
+        }
+        start.push_str("    ];\n");
+    }
+    start.push_str("    let mut rules = vec![];\n");
+    rules_fn.insert_str(0, &start);
+
+    let mut fns = String::new();
+    for (i, r) in rules.iter().enumerate() {
+        gen.rule = i;
+        gen.count = 0;
+        gen.queue.push((format!("rule_{}", i), format!("Parses {:?}.", r.0), &r.1));
+        while gen.queue.len() > 0 {
+            let (name, doc, rule) = gen.queue.remove(0);
+            fns.push('\n');
+            gen.write_fn(&name, &doc, rule, &mut fns);
+        }
+    }
+
+    let mut parse_fn = String::new();
+    parse_fn.push_str("/// Parses text with the last rule.\n");
+    parse_fn.push_str("pub fn parse(text: &str) -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {\n");
+    parse_fn.push_str("    parse_with_tokenizer(&mut Tokenizer::new(), text)\n");
+    parse_fn.push_str("}\n\n");
+    parse_fn.push_str("/// Parses text with the last rule, using a tokenizer to store the meta data.\n");
+    parse_fn.push_str("///\n");
+    parse_fn.push_str("/// This is used to set limits on the nesting of nodes\n");
+    parse_fn.push_str("/// and the number of rules parsed.\n");
+    parse_fn.push_str("pub fn parse_with_tokenizer(\n");
+    parse_fn.push_str("    tokenizer: &mut Tokenizer,\n");
+    parse_fn.push_str("    text: &str\n");
+    parse_fn.push_str(") -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {\n");
+    gen.import("Tokenizer");
+    if rules.len() == 0 {
+        parse_fn.push_str("    let _ = (tokenizer, text);\n");
+        parse_fn.push_str("    Err((Range::empty(0), ParseError::NoRules))\n");
+        gen.support.retain(|&s| s != "parse_with");
+    } else {
+        parse_fn.push_str("    let n = names();\n");
+        parse_fn.push_str(&format!("    parse_with(tokenizer, text, |t, s, c, o| rule_{}(t, s, c, o, &n))\n",
+            rules.len() - 1));
+    }
+    parse_fn.push_str("}\n");
+
+    let mut names_fn = String::new();
+    names_fn.push_str("/// Returns the strings used by the parse functions.\n");
+    names_fn.push_str("fn names() -> Vec<Arc<String>> {\n");
+    names_fn.push_str("    vec![\n");
+    for name in &gen.names {
+        names_fn.push_str(&format!("        {},\n", arc(name)));
+    }
+    names_fn.push_str("    ]\n");
+    names_fn.push_str("}\n");
+
+    let prefix = if krate.len() == 0 { String::new() } else { format!("{}::", krate) };
+    let mut out = String::new();
+    out.push_str("// Generated by `piston_meta::bootstrap::codegen`, do not edit.\n\n");
+    out.push_str("use std::sync::Arc;\n\n");
+    gen.support.sort();
+    write_use(&format!("{}support::", prefix), &gen.support, &mut out);
+    // Functions first, then types.
+    gen.imports.sort_by_key(|s| (s.chars().next().map(|c| c.is_uppercase()), *s));
+    write_use(&prefix, &gen.imports, &mut out);
+    out.push('\n');
+    out.push_str(&rules_fn);
+    out.push('\n');
+    out.push_str(&parse_fn);
+    if rules.len() > 0 {
+        out.push('\n');
+        out.push_str(&names_fn);
+        out.push_str(&fns);
+    }
+    out
+}
+
+/// Keeps track of what is needed by the generated code.
+struct Generator<'a> {
+    /// The strings used by the parse functions.
+    names: Vec<Arc<String>>,
+    /// The items to import from the library.
+    imports: Vec<&'static str>,
+    /// The functions to import from the `support` module.
+    support: Vec<&'static str>,
+    /// The index of the rule being generated.
+    rule: usize,
+    /// The number of functions generated for sub rules of the rule.
+    count: usize,
+    /// The functions to generate, with name and documentation.
+    queue: Vec<(String, String, &'a Rule)>,
+    /// The comments used by the rules.
+    comments: Vec<Arc<Comments>>,
+}
+
+impl<'a> Generator<'a> {
+    fn import(&mut self, name: &'static str) {
+        if !self.imports.contains(&name) { self.imports.push(name); }
+    }
+
+    fn support(&mut self, name: &'static str) {
+        if !self.support.contains(&name) { self.support.push(name); }
+    }
+
+    /// Returns the expression for a string used by the parse functions.
+    fn name(&mut self, val: &Arc<String>) -> String {
+        let i = match self.names.iter().position(|n| n == val) {
+            Some(i) => i,
+            None => {
+                self.names.push(val.clone());
+                self.names.len() - 1
+            }
+        };
+        format!("n[{}]", i)
+    }
+
+    /// Returns the expression for optional comments in the rules function.
+    fn comments(&mut self, val: &Option<Arc<Comments>>) -> String {
+        let val = match val {
+            &Some(ref val) => val,
+            &None => return "None".into()
+        };
+        let i = match self.comments.iter().position(|c| c == val) {
+            Some(i) => i,
+            None => {
+                self.comments.push(val.clone());
+                self.comments.len() - 1
+            }
+        };
+        format!("Some(comments[{}].clone())", i)
+    }
+
+    /// Returns the expression for an optional property.
+    fn property(&mut self, val: &Option<Arc<String>>) -> String {
+        match val {
+            &Some(ref val) => format!("Some(&{})", self.name(val)),
+            &None => "None".into()
+        }
+    }
+
+    /// Returns an expression that parses a rule.
+    /// Sub rules that read more than one thing get functions of their own.
+    fn expr(&mut self, rule: &'a Rule) -> String {
+        let kind = match rule {
+            &Rule::Whitespace(ref w) => {
+                self.support("whitespace");
+                let args = match w.comments {
+                    Some(ref c) => comments_args(c),
+                    None => "&[], &[], false".into()
+                };
+                return format!("whitespace({}, {}, {}, t, chars, offset)\
+                    .map(|range| (range, state.clone(), None))",
+                    w.optional, args, w.debug_id);
+            }
+            &Rule::Token(ref t) => {
+                self.support("token");
+                return format!("token(&{}, {}, {}, {}, {}, t, &state, chars, offset)",
+                    self.name(&t.text), t.inverted, t.case_insensitive,
+                    self.property(&t.property), t.debug_id);
+            }
+            &Rule::UntilAny(ref u) => {
+                self.support("until_any");
+                return format!("until_any({:?}, {}, {}, {}, t, &state, chars, offset)",
+                    u.any_characters, u.optional,
+                    self.property(&u.property), u.debug_id);
+            }
+            &Rule::UntilAnyOrWhitespace(ref u) => {
+                self.support("until_any_or_whitespace");
+                return format!("until_any_or_whitespace({:?}, {}, {}, {}, \
+                    t, &state, chars, offset)",
+                    u.any_characters, u.optional,
+                    self.property(&u.property), u.debug_id);
+            }
+            &Rule::Text(ref t) => {
+                self.support("text");
+                return format!("text({}, {}, {}, t, &state, chars, offset)",
+                    t.allow_empty, self.property(&t.property), t.debug_id);
+            }
+            &Rule::Number(ref n) => {
+                self.support("number");
+                self.import("NumberKind");
+                return format!("number(NumberKind::{:?}, {}, {}, {}, {}, {}, {}, \
+                    t, &state, chars, offset)",
+                    n.kind, n.allow_underscore, n.allow_radix, n.require_sign,
+                    n.allow_special, self.property(&n.property), n.debug_id);
+            }
+            &Rule::CharClass(ref c) => {
+                self.support("char_class");
+                return format!("char_class(&{:?}, {}, {}, {:?}, {}, {}, t, &state, chars, offset)",
+                    c.ranges, c.negated, c.min, c.max,
+                    self.property(&c.property), c.debug_id);
+            }
+            &Rule::Node(ref n) => {
+                return match n.index {
+                    Some(i) => {
+                        self.support("node");
+                        format!("node({}, {}, {}, t, &state, chars, offset, \
+                            &mut |t, s, c, o| rule_{}(t, s, c, o, n))",
+                            i, n.debug_id, self.property(&n.property), i)
+                    }
+                    None => {
+                        format!("Err((Range::empty(offset), ParseError::InvalidRule(\
+                            \"Node rule is not updated to reference\", {})))",
+                            n.debug_id)
+                    }
+                };
+            }
+            &Rule::Select(_) => "select",
+            &Rule::Sequence(_) => "sequence",
+            &Rule::SeparatedBy(_) => "separated by",
+            &Rule::Repeat(_) => "repeat",
+            &Rule::Lines(_) => "lines",
+            &Rule::Indent(_) => "indent",
+            &Rule::Optional(_) => "optional",
+            &Rule::Lookahead(_) => "lookahead",
+        };
+        self.count += 1;
+        let name = format!("rule_{}_{}", self.rule, self.count);
+        let doc = format!("Parses {} {} of `rule_{}`.", kind, rule.debug_id(), self.rule);
+        self.queue.push((name.clone(), doc, rule));
+        format!("{}(t, &state, chars, offset, n)", name)
+    }
+
+    /// Writes a function that parses a rule.
+    fn write_fn(&mut self, name: &str, doc: &str, rule: &'a Rule, out: &mut String) {
+        let mut body = String::new();
+        // Whether the rule reads from a start offset.
+        let mut sequential = true;
+        // Leaf rules and nodes count the step in the support function.
+        let step = match rule {
+            &Rule::Select(_) | &Rule::Sequence(_) | &Rule::SeparatedBy(_) |
+            &Rule::Repeat(_) | &Rule::Lines(_) | &Rule::Indent(_) |
+            &Rule::Optional(_) | &Rule::Lookahead(_) => true,
+            _ => false
+        };
+        match rule {
+            &Rule::Sequence(ref s) if s.args.len() > 0 => {
+                body.push_str(START);
+                for sub_rule in &s.args {
+                    let expr = self.expr(sub_rule);
+                    self.write_update(&expr, "return Err(ret_err(err, opt_error));",
+                        &mut body);
+                }
+                body.push_str(END);
+            }
+            &Rule::Sequence(_) => {
+                sequential = false;
+                body.push_str("    Ok((Range::empty(offset), state.clone(), None))\n");
+            }
+            &Rule::Select(ref s) if s.args.len() == 0 => {
+                sequential = false;
+                body.push_str(&format!("    Err((Range::new(offset, 0), ParseError::InvalidRule(\
+                    \"`Select` requires at least one sub rule\", {})))\n", s.debug_id));
+            }
+            &Rule::Select(ref s) => {
+                sequential = false;
+                body.push_str("    let mut opt_error: Option<(Range, ParseError)> = None;\n");
+                self.support("err_update");
+                for sub_rule in &s.args {
+                    let expr = self.expr(sub_rule);
+                    body.push_str(&format!("    match {} {{\n", expr));
+                    body.push_str("        Ok((range, state, err)) => {\n");
+                    body.push_str("            err_update(err, &mut opt_error);\n");
+                    body.push_str("            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));\n");
+                    body.push_str("        }\n");
+                    body.push_str("        Err(err) => { err_update(Some(err), &mut opt_error); }\n");
+                    body.push_str("    }\n");
+                }
+                body.push_str("    Err(opt_error.unwrap())\n");
+            }
+            &Rule::Optional(ref o) => {
+                sequential = false;
+                let expr = self.expr(&o.rule);
+                body.push_str(&format!("    match {} {{\n", expr));
+                body.push_str("        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),\n");
+                body.push_str("        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),\n");
+                body.push_str("    }\n");
+            }
+            &Rule::Indent(ref i) => {
+                sequential = false;
+                self.support("indent");
+                let space_len = match i.comments {
+                    None => {
+                        self.support("line_whitespace_len");
+                        "&|c| Ok(line_whitespace_len(c))".into()
+                    }
+                    Some(ref c) => {
+                        self.support("line_comments_len");
+                        format!("&|c| line_comments_len({}, c)", comments_args(c))
+                    }
+                };
+                let expr = self.expr(&i.rule);
+                body.push_str(&format!("    indent({}, {}, {}, t, &state, chars, offset,\n",
+                    i.tabs, i.debug_id, space_len));
+                body.push_str(&format!("        &mut |t, state, chars, offset| {})\n", expr));
+            }
+            &Rule::Lookahead(ref l) => {
+                sequential = false;
+                let expr = self.expr(&l.rule);
+                body.push_str(&format!("    match {} {{\n", expr));
+                if l.negate {
+                    body.push_str(&format!("        Ok((range, _, _)) => \
+                        Err((range, ParseError::UnexpectedMatch({}))),\n", l.debug_id));
+                    body.push_str("        Err(_) => Ok((Range::empty(offset), state.clone(), None)),\n");
+                } else {
+                    body.push_str("        Ok(_) => Ok((Range::empty(offset), state.clone(), None)),\n");
+                    body.push_str("        Err(err) => Err(err),\n");
+                }
+                body.push_str("    }\n");
+            }
+            &Rule::Repeat(ref r) => {
+                body.push_str(START);
+                if !r.optional { body.push_str("    let mut first = true;\n"); }
+                if r.count.is_some() { body.push_str("    let mut items = 0;\n"); }
+                body.push_str("    loop {\n");
+                // A maximum count stops the loop.
+                let bounded = r.count.and_then(|c| c.1).is_some();
+                if let Some((_, Some(max))) = r.count {
+                    body.push_str(&format!("        if items == {} {{ break; }}\n", max));
+                }
+                if !bounded { body.push_str("        let item_offset = offset;\n"); }
+                let expr = self.expr(&r.rule);
+                let fail = if r.optional {
+                    "err_update(Some(err), &mut opt_error);\n            break;"
+                } else {
+                    "if first { return Err(ret_err(err, opt_error)); }\n            \
+                    err_update(Some(err), &mut opt_error);\n            break;"
+                };
+                self.support("err_update");
+                let mut block = String::new();
+                self.write_update(&expr, fail, &mut block);
+                indent(&block, &mut body);
+                if !bounded { write_progress(r.debug_id, &mut body); }
+                if !r.optional { body.push_str("        first = false;\n"); }
+                if r.count.is_some() { body.push_str("        items += 1;\n"); }
+                body.push_str("    }\n");
+                if let Some(count) = r.count {
+                    self.support("check_count");
+                    write_count(count, r.debug_id, &mut body);
+                }
+                body.push_str(END);
+            }
+            &Rule::SeparatedBy(ref s) => {
+                body.push_str(START);
+                let uses_first = s.optional != s.allow_trail;
+                if uses_first { body.push_str("    let mut first = true;\n"); }
+                if s.count.is_some() { body.push_str("    let mut items = 0;\n"); }
+                body.push_str("    loop {\n");
+                // A maximum count stops the loop.
+                let bounded = s.count.and_then(|c| c.1).is_some();
+                if let Some((_, Some(max))) = s.count {
+                    body.push_str(&format!("        if items == {} {{ break; }}\n", max));
+                }
+                if !bounded { body.push_str("        let item_offset = offset;\n"); }
+                let expr = self.expr(&s.rule);
+                let fail = match (s.optional, s.allow_trail) {
+                    (false, false) => "return Err(ret_err(err, opt_error));",
+                    (false, true) => "if first { return Err(ret_err(err, opt_error)); }\n            \
+                        err_update(Some(err), &mut opt_error);\n            break;",
+                    (true, false) => "if !first { return Err(ret_err(err, opt_error)); }\n            \
+                        err_update(Some(err), &mut opt_error);\n            break;",
+                    (true, true) => "err_update(Some(err), &mut opt_error);\n            break;",
+                };
+                if s.optional || s.allow_trail { self.support("err_update"); }
+                let mut block = String::new();
+                self.write_update(&expr, fail, &mut block);
+                if s.count.is_some() { block.push_str("    items += 1;\n"); }
+                match s.count {
+                    Some((_, Some(max))) if !s.allow_trail => {
+                        block.push_str(&format!("    if items == {} {{ break; }}\n", max));
+                    }
+                    _ => {}
+                }
+                let expr = self.expr(&s.by);
+                self.support("err_update");
+                self.write_update(&expr,
+                    "err_update(Some(err), &mut opt_error);\n            break;", &mut block);
+                indent(&block, &mut body);
+                if !bounded { write_progress(s.debug_id, &mut body); }
+                if uses_first { body.push_str("        first = false;\n"); }
+                body.push_str("    }\n");
+                if let Some(count) = s.count {
+                    self.support("check_count");
+                    write_count(count, s.debug_id, &mut body);
+                }
+                body.push_str(END);
+            }
+            &Rule::Lines(ref l) => {
+                body.push_str(START);
+                body.push_str("    let mut new_lines = true;\n");
+                body.push_str("    loop {\n");
+                match l.comments {
+                    None => {
+                        self.support("line_whitespace_len");
+                        body.push_str("        let len = line_whitespace_len(chars);\n");
+                    }
+                    Some(ref c) => {
+                        self.support("line_comments_len");
+                        let args = comments_args(c);
+                        body.push_str(&format!("        let len = match line_comments_len({}, chars) {{\n",
+                            args));
+                        body.push_str("            Ok(len) => len,\n");
+                        body.push_str(&format!("            Err(i) => return Err(ret_err((\
+                            Range::new(offset + i, chars.len() - i), \
+                            ParseError::ExpectedCommentEnd({})), opt_error)),\n", l.debug_id));
+                        body.push_str("        };\n");
+                    }
+                }
+                body.push_str("        if len == chars.len() {\n");
+                body.push_str("            offset += len;\n");
+                body.push_str("            break;\n");
+                body.push_str("        } else if chars.as_bytes()[len] == b'\\n' {\n");
+                body.push_str("            chars = &chars[len + 1..];\n");
+                body.push_str("            offset += len + 1;\n");
+                body.push_str("            new_lines = true;\n");
+                body.push_str("        } else if new_lines {\n");
+                if l.comments.is_some() {
+                    // The rule starts after the comments.
+                    body.push_str("            chars = &chars[len..];\n");
+                    body.push_str("            offset += len;\n");
+                }
+                let expr = self.expr(&l.rule);
+                body.push_str(&format!("            state = match {} {{\n", expr));
+                body.push_str("                Err(err) => {\n");
+                body.push_str("                    err_update(Some(err), &mut opt_error);\n");
+                body.push_str("                    break;\n");
+                body.push_str("                }\n");
+                body.push_str("                Ok((range, state, err)) => {\n");
+                body.push_str("                    new_lines = ends_with_new_line(&chars[..range.next_offset() - offset]);\n");
+                body.push_str("                    update(range, err, &mut chars, &mut offset, &mut opt_error);\n");
+                body.push_str("                    state\n");
+                body.push_str("                }\n");
+                body.push_str("            };\n");
+                body.push_str("        } else {\n");
+                body.push_str(&format!("            return Err(ret_err((Range::empty(offset), \
+                    ParseError::ExpectedNewLine({})), opt_error));\n", l.debug_id));
+                body.push_str("        }\n");
+                body.push_str("    }\n");
+                body.push_str(END);
+                self.support("ends_with_new_line");
+                self.support("err_update");
+                self.support("update");
+                self.support("ret_err");
+            }
+            _ => {
+                sequential = false;
+                let expr = self.expr(rule);
+                body.push_str("    ");
+                body.push_str(&expr);
+                body.push('\n');
+            }
+        }
+        if step {
+            // Avoids `try!`, which is reserved in newer editions.
+            body.insert_str(0, &format!("    if let Err(err) = t.step({}, {}) {{ return Err(err); }}\n",
+                rule.debug_id(),
+                if sequential { "start_offset" } else { "offset" }));
+        }
+        if sequential {
+            self.support("update");
+            self.support("ret_err");
+        }
+        self.import("ParseResult");
+        self.import("Tokenizer");
+        self.import("TokenizerState");
+
+        let uses = |var: &str| body.contains(&format!("({}, ", var)) ||
+            body.contains(&format!(", {}, ", var)) ||
+            body.contains(&format!(", {})", var)) ||
+            body.contains(&format!("{}[", var));
+        out.push_str(&format!("/// {}\n", doc));
+        out.push_str(&format!("fn {}(\n", name));
+        out.push_str(if step || uses("t") { "    t: &mut Tokenizer,\n" } else { "    _: &mut Tokenizer,\n" });
+        out.push_str("    state: &TokenizerState,\n");
+        out.push_str(if sequential { "    mut chars: &str,\n" } else { "    chars: &str,\n" });
+        out.push_str(if sequential { "    start_offset: usize,\n" } else { "    offset: usize,\n" });
+        out.push_str(if uses("n") { "    n: &[Arc<String>]\n" } else { "    _: &[Arc<String>]\n" });
+        out.push_str(") -> ParseResult<TokenizerState> {\n");
+        out.push_str(&body);
+        out.push_str("}\n");
+    }
+
+    /// Writes code that parses a sub rule and updates the state,
+    /// or runs the code for failure.
+    fn write_update(&mut self, expr: &str, fail: &str, out: &mut String) {
+        out.push_str(&format!("    state = match {} {{\n", expr));
+        out.push_str("        Ok((range, state, err)) => {\n");
+        out.push_str("            update(range, err, &mut chars, &mut offset, &mut opt_error);\n");
+        out.push_str("            state\n");
+        out.push_str("        }\n");
+        out.push_str("        Err(err) => {\n");
+        out.push_str(&format!("            {}\n", fail));
+        out.push_str("        }\n");
+        out.push_str("    };\n");
+    }
+
+    /// Writes code that creates a rule.
+    fn write_rule(&mut self, rule: &Rule, level: usize, out: &mut String) {
+        let tab = "    ".repeat(level);
+        let field = |out: &mut String, name: &str, val: &str| {
+            out.push_str(&format!("{}    {}: {},\n", tab, name, val));
+        };
+        match rule {
+            &Rule::Whitespace(ref w) => {
+                self.import("Whitespace");
+                out.push_str("Rule::Whitespace(Whitespace {\n");
+                field(out, "debug_id", &w.debug_id.to_string());
+                field(out, "optional", &w.optional.to_string());
+                field(out, "comments", &self.comments(&w.comments));
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::Token(ref t) => {
+                self.import("Token");
+                out.push_str("Rule::Token(Token {\n");
+                field(out, "debug_id", &t.debug_id.to_string());
+                field(out, "text", &arc(&t.text));
+                field(out, "inverted", &t.inverted.to_string());
+                field(out, "case_insensitive", &t.case_insensitive.to_string());
+                field(out, "property", &opt_arc(&t.property));
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::UntilAny(ref u) => {
+                self.import("UntilAny");
+                out.push_str("Rule::UntilAny(UntilAny {\n");
+                field(out, "debug_id", &u.debug_id.to_string());
+                field(out, "any_characters", &arc(&u.any_characters));
+                field(out, "optional", &u.optional.to_string());
+                field(out, "property", &opt_arc(&u.property));
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::UntilAnyOrWhitespace(ref u) => {
+                self.import("UntilAnyOrWhitespace");
+                out.push_str("Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {\n");
+                field(out, "debug_id", &u.debug_id.to_string());
+                field(out, "any_characters", &arc(&u.any_characters));
+                field(out, "optional", &u.optional.to_string());
+                field(out, "property", &opt_arc(&u.property));
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::Text(ref t) => {
+                self.import("Text");
+                out.push_str("Rule::Text(Text {\n");
+                field(out, "debug_id", &t.debug_id.to_string());
+                field(out, "allow_empty", &t.allow_empty.to_string());
+                field(out, "property", &opt_arc(&t.property));
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::Number(ref n) => {
+                self.import("Number");
+                out.push_str("Rule::Number(Number {\n");
+                field(out, "debug_id", &n.debug_id.to_string());
+                field(out, "property", &opt_arc(&n.property));
+                field(out, "allow_underscore", &n.allow_underscore.to_string());
+                self.import("NumberKind");
+                field(out, "kind", &format!("NumberKind::{:?}", n.kind));
+                field(out, "allow_radix", &n.allow_radix.to_string());
+                field(out, "require_sign", &n.require_sign.to_string());
+                field(out, "allow_special", &n.allow_special.to_string());
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::CharClass(ref c) => {
+                self.import("CharClass");
+                out.push_str("Rule::CharClass(CharClass {\n");
+                field(out, "debug_id", &c.debug_id.to_string());
+                field(out, "ranges", &format!("vec!{:?}", c.ranges));
+                field(out, "negated", &c.negated.to_string());
+                field(out, "min", &c.min.to_string());
+                field(out, "max", &format!("{:?}", c.max));
+                field(out, "property", &opt_arc(&c.property));
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::Select(ref s) => {
+                self.import("Select");
+                out.push_str("Rule::Select(Select {\n");
+                field(out, "debug_id", &s.debug_id.to_string());
+                self.write_args(&s.args, level + 1, out);
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::Sequence(ref s) => {
+                self.import("Sequence");
+                out.push_str("Rule::Sequence(Sequence {\n");
+                field(out, "debug_id", &s.debug_id.to_string());
+                self.write_args(&s.args, level + 1, out);
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::SeparatedBy(ref s) => {
+                self.import("SeparatedBy");
+                out.push_str("Rule::SeparatedBy(Box::new(SeparatedBy {\n");
+                field(out, "debug_id", &s.debug_id.to_string());
+                field(out, "optional", &s.optional.to_string());
+                field(out, "allow_trail", &s.allow_trail.to_string());
+                field(out, "count", &format!("{:?}", s.count));
+                out.push_str(&format!("{}    by: ", tab));
+                self.write_rule(&s.by, level + 1, out);
+                out.push_str(",\n");
+                out.push_str(&format!("{}    rule: ", tab));
+                self.write_rule(&s.rule, level + 1, out);
+                out.push_str(",\n");
+                out.push_str(&format!("{}}}))", tab));
+            }
+            &Rule::Repeat(ref r) => {
+                self.import("Repeat");
+                out.push_str("Rule::Repeat(Box::new(Repeat {\n");
+                field(out, "debug_id", &r.debug_id.to_string());
+                field(out, "optional", &r.optional.to_string());
+                field(out, "count", &format!("{:?}", r.count));
+                field(out, "sync", &opt_arc(&r.sync));
+                out.push_str(&format!("{}    rule: ", tab));
+                self.write_rule(&r.rule, level + 1, out);
+                out.push_str(",\n");
+                out.push_str(&format!("{}}}))", tab));
+            }
+            &Rule::Lines(ref l) => {
+                self.import("Lines");
+                out.push_str("Rule::Lines(Box::new(Lines {\n");
+                field(out, "debug_id", &l.debug_id.to_string());
+                out.push_str(&format!("{}    rule: ", tab));
+                self.write_rule(&l.rule, level + 1, out);
+                out.push_str(",\n");
+                field(out, "comments", &self.comments(&l.comments));
+                out.push_str(&format!("{}}}))", tab));
+            }
+            &Rule::Indent(ref i) => {
+                self.import("Indent");
+                out.push_str("Rule::Indent(Box::new(Indent {\n");
+                field(out, "debug_id", &i.debug_id.to_string());
+                field(out, "tabs", &i.tabs.to_string());
+                out.push_str(&format!("{}    rule: ", tab));
+                self.write_rule(&i.rule, level + 1, out);
+                out.push_str(",\n");
+                field(out, "comments", &self.comments(&i.comments));
+                out.push_str(&format!("{}}}))", tab));
+            }
+            &Rule::Node(ref n) => {
+                self.import("Node");
+                out.push_str("Rule::Node(Node {\n");
+                field(out, "debug_id", &n.debug_id.to_string());
+                field(out, "name", &arc(&n.name));
+                field(out, "property", &opt_arc(&n.property));
+                field(out, "index", "None");
+                out.push_str(&format!("{}}})", tab));
+            }
+            &Rule::Optional(ref o) => {
+                self.import("Optional");
+                out.push_str("Rule::Optional(Box::new(Optional {\n");
+                field(out, "debug_id", &o.debug_id.to_string());
+                out.push_str(&format!("{}    rule: ", tab));
+                self.write_rule(&o.rule, level + 1, out);
+                out.push_str(",\n");
+                out.push_str(&format!("{}}}))", tab));
+            }
+            &Rule::Lookahead(ref l) => {
+                self.import("Lookahead");
+                out.push_str("Rule::Lookahead(Box::new(Lookahead {\n");
+                field(out, "debug_id", &l.debug_id.to_string());
+                field(out, "negate", &l.negate.to_string());
+                out.push_str(&format!("{}    rule: ", tab));
+                self.write_rule(&l.rule, level + 1, out);
+                out.push_str(",\n");
+                out.push_str(&format!("{}}}))", tab));
+            }
+        }
+    }
+
+    /// Writes code that creates the sub rules of a select or sequence.
+    fn write_args(&mut self, args: &[Rule], level: usize, out: &mut String) {
+        let tab = "    ".repeat(level);
+        out.push_str(&format!("{}args: vec![\n", tab));
+        for sub_rule in args {
+            out.push_str(&format!("{}    ", tab));
+            self.write_rule(sub_rule, level + 1, out);
+            out.push_str(",\n");
+        }
+        out.push_str(&format!("{}],\n", tab));
+    }
+}
+
+/// The start of a function that reads sub rules one after another.
+const START: &'static str = "    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+";
+
+/// The end of a function that reads sub rules one after another.
+const END: &'static str = "    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+";
+
+/// Writes code that stops a loop with an error when nothing was read.
+fn write_progress(debug_id: DebugId, out: &mut String) {
+    out.push_str("        if offset == item_offset {\n");
+    out.push_str(&format!("            return Err((Range::empty(offset), \
+        ParseError::InfiniteLoop({})));\n", debug_id));
+    out.push_str("        }\n");
+}
+
+/// Writes code that fails when the number of items is outside the count.
+fn write_count(count: (usize, Option<usize>), debug_id: DebugId, out: &mut String) {
+    out.push_str(&format!("    if let Err(err) = check_count({}, {:?}, items, \
+        Range::new(start_offset, offset - start_offset), {}) {{\n",
+        count.0, count.1, debug_id));
+    out.push_str("        return Err(ret_err(err, opt_error));\n");
+    out.push_str("    }\n");
+}
+
+/// Returns the arguments for comments used by the parse functions.
+fn comments_args(c: &Comments) -> String {
+    let line: Vec<String> = c.line.iter().map(|s| format!("{:?}", s)).collect();
+    let block: Vec<String> = c.block.iter()
+        .map(|&(ref open, ref close)| format!("({:?}, {:?})", open, close))
+        .collect();
+    format!("&[{}], &[{}], {}", line.join(", "), block.join(", "), c.nested)
+}
+
+/// Returns code that creates a shared string.
+fn arc(val: &str) -> String {
+    format!("Arc::new({:?}.into())", val)
+}
+
+/// Returns code that creates an optional shared string.
+fn opt_arc(val: &Option<Arc<String>>) -> String {
+    match val {
+        &Some(ref val) => format!("Some({})", arc(val)),
+        &None => "None".into()
+    }
+}
+
+/// Writes lines indented one more level.
+fn indent(text: &str, out: &mut String) {
+    for line in text.lines() {
+        out.push_str("    ");
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+/// Writes a `use` declaration for items of a path.
+fn write_use(path: &str, items: &[&str], out: &mut String) {
+    if items.len() == 0 { return; }
+    out.push_str(&format!("use {}{{\n", path));
+    for item in items {
+        out.push_str(&format!("    {},\n", item));
+    }
+    out.push_str("};\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::super::super::*;
+    use std::fs::File;
+    use std::io::Read;
+
+    fn self_syntax() -> String {
+        let mut source = String::new();
+        File::open("assets/self-syntax.txt").unwrap()
+            .read_to_string(&mut source).unwrap();
+        source
+    }
+
+    #[test]
+    fn bootstrap_rules() {
+        let rules = convert(&parse(&rules(), &self_syntax()).unwrap(), &mut vec![])
+            .unwrap();
+        assert_eq!(rules, super::super::rules());
+        let mut code = String::new();
+        File::open("src/bootstrap/rules.rs").unwrap()
+            .read_to_string(&mut code).unwrap();
+        // Run `cargo run --example codegen` to update.
+        assert!(codegen(&rules, "") == code, "`src/bootstrap/rules.rs` is outdated");
+        assert!(codegen(&rules, "piston_meta").contains("
+use piston_meta::support::{
+    ends_with_new_line,
+"));
+    }
+
+    #[test]
+    fn same_output() {
+        let source = self_syntax();
+        let rules = rules();
+        assert!(parse_syntax(&source).is_ok());
+        for end in (0..source.len()).filter(|i| i % 7 == 0) {
+            let text = &source[..end];
+            assert_eq!(parse_syntax(text), parse(&rules, text));
+        }
+    }
+
+    #[test]
+    fn limits() {
+        use super::super::rules::parse_with_tokenizer as parse_syntax_with_tokenizer;
+
+        let source = self_syntax();
+        let rules = rules();
+        for &(max_depth, fuel) in &[(None, None), (Some(3), None), (None, Some(2000))] {
+            let mut a = Tokenizer::new();
+            a.max_depth = max_depth;
+            a.fuel = fuel;
+            let mut b = Tokenizer::new();
+            b.max_depth = max_depth;
+            b.fuel = fuel;
+            let res = parse_syntax_with_tokenizer(&mut a, &source);
+            assert_eq!(res.is_ok(), max_depth.is_none() && fuel.is_none());
+            assert_eq!(res, parse_with_tokenizer(&mut b, &rules, &source));
+            assert_eq!(a.steps, b.steps);
+        }
+    }
+}
@@ -1,14 +1,20 @@
-use std::rc::Rc;
-use std::cell::Cell;
+use std::sync::Arc;
 use range::Range;
 
+use nullable::infinite_loop;
 use {
     update_refs,
+    CharClass,
+    Comments,
+    Indent,
     Lines,
+    Lookahead,
     MetaData,
     Optional,
     Node,
+    ParseError,
     Number,
+    NumberKind,
     Repeat,
     Rule,
     Sequence,
@@ -72,7 +78,7 @@ pub fn ignore(data: &[(Range, MetaData)], offset: usize)
 
 /// Reads string.
 pub fn meta_string(name: &str, data: &[(Range, MetaData)], offset: usize)
--> Result<(Range, Rc<String>), ()> {
+-> Result<(Range, Arc<String>), ()> {
     if data.len() == 0 { return Err(()); }
     match &data[0].1 {
         &MetaData::String(ref n, ref val) if &**n == name => {
@@ -106,13 +112,35 @@ pub fn meta_bool(name: &str, data: &[(Range, MetaData)], offset: usize)
     }
 }
 
+/// Converts a number to a count.
+fn count(val: f64) -> Result<usize, ()> {
+    if val < 0.0 || val.fract() != 0.0 { return Err(()); }
+    Ok(val as usize)
+}
+
 /// Converts meta data to rules.
+///
+/// Fails if a rule that can read nothing is repeated, since it would loop forever.
+/// Use `convert_with_error` to find out why it fails.
 pub fn convert(
+    data: &[(Range, MetaData)],
+    ignored: &mut Vec<Range>
+) -> Result<Vec<(Arc<String>, Rule)>, ()> {
+    convert_with_error(data, ignored).map_err(|_| ())
+}
+
+/// Converts meta data to rules, returning an error that tells why it fails.
+///
+/// Returns `ParseError::InfiniteLoop` with the debug id of a repeated rule
+/// that can read nothing, like `Grammar::new`,
+/// and `ParseError::InvalidMetaData` with the range of the first meta data
+/// that does not describe a rule.
+pub fn convert_with_error(
     mut data: &[(Range, MetaData)],
     ignored: &mut Vec<Range>
-) -> Result<Vec<(Rc<String>, Rule)>, ()> {
+) -> Result<Vec<(Arc<String>, Rule)>, (Range, ParseError)> {
     fn read_string(mut data: &[(Range, MetaData)], mut offset: usize)
-    -> Result<(Range, (Rc<String>, Rc<String>)), ()> {
+    -> Result<(Range, (Arc<String>, Arc<String>)), ()> {
         let start_offset = offset;
         let range = try!(start_node("string", data, offset));
         update(range, &mut data, &mut offset);
@@ -143,11 +171,40 @@ pub fn convert(
         Ok((Range::new(start_offset, offset - start_offset), (name, text)))
     }
 
+    fn read_comments(mut data: &[(Range, MetaData)], mut offset: usize)
+    -> Result<(Range, Comments), ()> {
+        let start_offset = offset;
+        let node = "comments";
+        let range = try!(start_node(node, data, offset));
+        update(range, &mut data, &mut offset);
+        let mut comments = Comments::default();
+        loop {
+            if let Ok(range) = end_node(node, data, offset) {
+                update(range, &mut data, &mut offset);
+                break;
+            } else if let Ok((range, val)) = meta_string("line", data, offset) {
+                update(range, &mut data, &mut offset);
+                comments.line.push(val);
+            } else if let Ok((range, open)) = meta_string("open", data, offset) {
+                update(range, &mut data, &mut offset);
+                let (range, close) = try!(meta_string("close", data, offset));
+                update(range, &mut data, &mut offset);
+                comments.block.push((open, close));
+            } else if let Ok((range, val)) = meta_bool("nested", data, offset) {
+                update(range, &mut data, &mut offset);
+                comments.nested = val;
+            } else {
+                return Err(())
+            }
+        }
+        Ok((Range::new(start_offset, offset - start_offset), comments))
+    }
+
     fn read_sequence(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -177,13 +234,13 @@ pub fn convert(
         })))
     }
 
-    fn find_string(val: &str, strings: &[(Rc<String>, Rc<String>)]) -> Option<Rc<String>> {
+    fn find_string(val: &str, strings: &[(Arc<String>, Arc<String>)]) -> Option<Arc<String>> {
         strings.iter().find(|&&(ref s, _)| &**s == val).map(|&(_, ref s)| s.clone())
     }
 
     fn read_set(property: &str, mut data: &[(Range, MetaData)], mut offset: usize,
-    strings: &[(Rc<String>, Rc<String>)])
-    -> Result<(Range, Rc<String>), ()> {
+    strings: &[(Arc<String>, Arc<String>)])
+    -> Result<(Range, Arc<String>), ()> {
         let start_offset = offset;
         let range = try!(start_node(property, data, offset));
         update(range, &mut data, &mut offset);
@@ -212,7 +269,7 @@ pub fn convert(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -261,7 +318,7 @@ pub fn convert(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -310,7 +367,7 @@ pub fn convert(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -321,6 +378,7 @@ pub fn convert(
         let mut text = None;
         let mut property = None;
         let mut inverted = None;
+        let mut case_insensitive = false;
         loop {
             if let Ok(range) = end_node(node, data, offset) {
                 update(range, &mut data, &mut offset);
@@ -334,6 +392,9 @@ pub fn convert(
             } else if let Ok((range, val)) = meta_bool("inverted", data, offset) {
                 update(range, &mut data, &mut offset);
                 inverted = Some(val);
+            } else if let Ok((range, val)) = meta_bool("case_insensitive", data, offset) {
+                update(range, &mut data, &mut offset);
+                case_insensitive = val;
             } else {
                 let range = ignore(data, offset);
                 update(range, &mut data, &mut offset);
@@ -349,6 +410,7 @@ pub fn convert(
                     debug_id: *debug_id,
                     text: text,
                     inverted: inverted,
+                    case_insensitive: case_insensitive,
                     property: property,
                 })))
             }
@@ -373,6 +435,7 @@ pub fn convert(
         Rule::Whitespace(Whitespace {
             debug_id: *debug_id,
             optional: optional,
+            comments: None,
         })))
     }
 
@@ -380,7 +443,7 @@ pub fn convert(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -415,11 +478,114 @@ pub fn convert(
         })))
     }
 
+    fn read_count(mut data: &[(Range, MetaData)], mut offset: usize)
+    -> Result<(Range, (usize, Option<usize>)), ()> {
+        let start_offset = offset;
+        let node = "count";
+        let range = try!(start_node(node, data, offset));
+        update(range, &mut data, &mut offset);
+        let mut min = None;
+        let mut to = false;
+        let mut max = None;
+        loop {
+            if let Ok(range) = end_node(node, data, offset) {
+                update(range, &mut data, &mut offset);
+                break;
+            } else if let Ok((range, val)) = meta_f64("min", data, offset) {
+                update(range, &mut data, &mut offset);
+                min = Some(try!(count(val)));
+            } else if let Ok((range, val)) = meta_bool("to", data, offset) {
+                update(range, &mut data, &mut offset);
+                to = val;
+            } else if let Ok((range, val)) = meta_f64("max", data, offset) {
+                update(range, &mut data, &mut offset);
+                max = Some(try!(count(val)));
+            } else {
+                return Err(());
+            }
+        }
+        let min = match min {
+            None => { return Err(()); }
+            Some(x) => x
+        };
+        // Without a comma the count is exact.
+        let max = if to { max } else { Some(min) };
+        if let Some(max) = max {
+            if max < min { return Err(()); }
+        }
+        Ok((Range::new(start_offset, offset - start_offset), (min, max)))
+    }
+
+    fn read_char_class(
+        debug_id: &mut usize,
+        mut data: &[(Range, MetaData)],
+        mut offset: usize,
+        strings: &[(Arc<String>, Arc<String>)],
+        ignored: &mut Vec<Range>
+    ) -> Result<(Range, Rule), ()> {
+        let start_offset = offset;
+        let node = "char_class";
+        let range = try!(start_node(node, data, offset));
+        update(range, &mut data, &mut offset);
+        let mut class = None;
+        let mut negated = false;
+        let mut count = None;
+        let mut optional = None;
+        let mut property = None;
+        loop {
+            if let Ok(range) = end_node(node, data, offset) {
+                update(range, &mut data, &mut offset);
+                break;
+            } else if let Ok((range, val)) = meta_bool("negated", data, offset) {
+                update(range, &mut data, &mut offset);
+                negated = val;
+            } else if let Ok((range, val)) = meta_string("class", data, offset) {
+                update(range, &mut data, &mut offset);
+                class = Some(val);
+            } else if let Ok((range, val)) = read_count(data, offset) {
+                update(range, &mut data, &mut offset);
+                count = Some(val);
+            } else if let Ok((range, val)) = meta_bool("optional", data, offset) {
+                update(range, &mut data, &mut offset);
+                optional = Some(val);
+            } else if let Ok((range, val)) = read_set("property", data, offset, strings) {
+                update(range, &mut data, &mut offset);
+                property = Some(val);
+            } else {
+                let range = ignore(data, offset);
+                update(range, &mut data, &mut offset);
+                ignored.push(range);
+            }
+        }
+        // Reads one character by default.
+        let (min, max) = match (count, optional) {
+            (Some(count), _) => count,
+            (None, Some(true)) => (0, None),
+            (None, Some(false)) => (1, None),
+            (None, None) => (1, Some(1)),
+        };
+        match class {
+            Some(class) => {
+                *debug_id += 1;
+                Ok((Range::new(start_offset, offset - start_offset),
+                Rule::CharClass(CharClass {
+                    debug_id: *debug_id,
+                    ranges: CharClass::ranges_from(&class),
+                    negated: negated,
+                    min: min,
+                    max: max,
+                    property: property,
+                })))
+            }
+            None => Err(())
+        }
+    }
+
     fn read_number(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -429,6 +595,10 @@ pub fn convert(
 
         let mut property = None;
         let mut underscore = None;
+        let mut kind = NumberKind::F64;
+        let mut radix = false;
+        let mut sign = false;
+        let mut special = false;
         loop {
             if let Ok(range) = end_node(node, data, offset) {
                 update(range, &mut data, &mut offset);
@@ -439,6 +609,21 @@ pub fn convert(
             } else if let Ok((range, val)) = meta_bool("underscore", data, offset) {
                 update(range, &mut data, &mut offset);
                 underscore = Some(val);
+            } else if let Ok((range, _)) = meta_bool("i64", data, offset) {
+                update(range, &mut data, &mut offset);
+                kind = NumberKind::I64;
+            } else if let Ok((range, _)) = meta_bool("u64", data, offset) {
+                update(range, &mut data, &mut offset);
+                kind = NumberKind::U64;
+            } else if let Ok((range, val)) = meta_bool("radix", data, offset) {
+                update(range, &mut data, &mut offset);
+                radix = val;
+            } else if let Ok((range, val)) = meta_bool("sign", data, offset) {
+                update(range, &mut data, &mut offset);
+                sign = val;
+            } else if let Ok((range, val)) = meta_bool("special", data, offset) {
+                update(range, &mut data, &mut offset);
+                special = val;
             } else {
                 let range = ignore(data, offset);
                 update(range, &mut data, &mut offset);
@@ -452,6 +637,10 @@ pub fn convert(
             debug_id: *debug_id,
             property: property,
             allow_underscore: underscore,
+            kind: kind,
+            allow_radix: radix,
+            require_sign: sign,
+            allow_special: special,
         })))
     }
 
@@ -459,7 +648,7 @@ pub fn convert(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -493,7 +682,7 @@ pub fn convert(
                     debug_id: *debug_id,
                     name: name,
                     property: property,
-                    index: Cell::new(None),
+                    index: None,
                 })))
             }
             None => Err(())
@@ -504,7 +693,7 @@ pub fn convert(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -539,7 +728,7 @@ pub fn convert(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -560,11 +749,42 @@ pub fn convert(
         }))))
     }
 
+    fn read_lookahead(
+        debug_id: &mut usize,
+        mut data: &[(Range, MetaData)],
+        mut offset: usize,
+        strings: &[(Arc<String>, Arc<String>)],
+        ignored: &mut Vec<Range>
+    ) -> Result<(Range, Rule), ()> {
+        let start_offset = offset;
+        let node = "lookahead";
+        let range = try!(start_node(node, data, offset));
+        update(range, &mut data, &mut offset);
+        let mut negate = false;
+        if let Ok((range, val)) = meta_bool("negate", data, offset) {
+            update(range, &mut data, &mut offset);
+            negate = val;
+        }
+        let (range, rule) = try!(read_rule(
+            debug_id, "rule", data, offset, strings, ignored
+        ));
+        update(range, &mut data, &mut offset);
+        let range = try!(end_node(node, data, offset));
+        update(range, &mut data, &mut offset);
+        *debug_id += 1;
+        Ok((Range::new(start_offset, offset - start_offset),
+        Rule::Lookahead(Box::new(Lookahead {
+            debug_id: *debug_id,
+            negate: negate,
+            rule: rule,
+        }))))
+    }
+
     fn read_separated_by(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -573,6 +793,7 @@ pub fn convert(
         update(range, &mut data, &mut offset);
         let mut optional = None;
         let mut allow_trail = None;
+        let mut count = None;
         let mut by = None;
         let mut rule = None;
         loop {
@@ -582,6 +803,9 @@ pub fn convert(
             } else if let Ok((range, val)) = meta_bool("optional", data, offset) {
                 update(range, &mut data, &mut offset);
                 optional = Some(val);
+            } else if let Ok((range, val)) = read_count(data, offset) {
+                update(range, &mut data, &mut offset);
+                count = Some(val);
             } else if let Ok((range, val)) = meta_bool("allow_trail", data, offset) {
                 update(range, &mut data, &mut offset);
                 allow_trail = Some(val);
@@ -601,7 +825,11 @@ pub fn convert(
                 ignored.push(range);
             }
         }
-        let optional = optional.unwrap_or(true);
+        // A count decides whether the rule can be read zero times.
+        let optional = match count {
+            Some((min, _)) => min == 0,
+            None => optional.unwrap_or(true),
+        };
         let allow_trail = allow_trail.unwrap_or(true);
         match (by, rule) {
             (Some(by), Some(rule)) => {
@@ -611,6 +839,7 @@ pub fn convert(
                     debug_id: *debug_id,
                     optional: optional,
                     allow_trail: allow_trail,
+                    count: count,
                     by: by,
                     rule: rule,
                 }))))
@@ -623,7 +852,7 @@ pub fn convert(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -640,6 +869,39 @@ pub fn convert(
         Rule::Lines(Box::new(Lines {
             debug_id: *debug_id,
             rule: rule,
+            comments: None,
+        }))))
+    }
+
+    fn read_indent(
+        debug_id: &mut usize,
+        mut data: &[(Range, MetaData)],
+        mut offset: usize,
+        strings: &[(Arc<String>, Arc<String>)],
+        ignored: &mut Vec<Range>
+    ) -> Result<(Range, Rule), ()> {
+        let start_offset = offset;
+        let node = "indent";
+        let range = try!(start_node(node, data, offset));
+        update(range, &mut data, &mut offset);
+        let mut tabs = false;
+        if let Ok((range, val)) = meta_bool("tabs", data, offset) {
+            update(range, &mut data, &mut offset);
+            tabs = val;
+        }
+        let (range, rule) = try!(read_rule(
+            debug_id, "rule", data, offset, strings, ignored
+        ));
+        update(range, &mut data, &mut offset);
+        let range = try!(end_node(node, data, offset));
+        update(range, &mut data, &mut offset);
+        *debug_id += 1;
+        Ok((Range::new(start_offset, offset - start_offset),
+        Rule::Indent(Box::new(Indent {
+            debug_id: *debug_id,
+            tabs: tabs,
+            rule: rule,
+            comments: None,
         }))))
     }
 
@@ -647,7 +909,7 @@ pub fn convert(
         debug_id: &mut usize,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -656,6 +918,8 @@ pub fn convert(
         update(range, &mut data, &mut offset);
         let mut rule = None;
         let mut optional = None;
+        let mut count = None;
+        let mut sync = None;
         loop {
             if let Ok(range) = end_node(node, data, offset) {
                 update(range, &mut data, &mut offset);
@@ -670,6 +934,15 @@ pub fn convert(
             ) {
                 update(range, &mut data, &mut offset);
                 optional = Some(val);
+            } else if let Ok((range, val)) = read_count(data, offset) {
+                update(range, &mut data, &mut offset);
+                optional = Some(val.0 == 0);
+                count = Some(val);
+            } else if let Ok((range, val)) = read_set(
+                "sync", data, offset, strings
+            ) {
+                update(range, &mut data, &mut offset);
+                sync = Some(val);
             } else {
                 let range = ignore(data, offset);
                 update(range, &mut data, &mut offset);
@@ -684,6 +957,8 @@ pub fn convert(
                     debug_id: *debug_id,
                     rule: rule,
                     optional: optional,
+                    count: count,
+                    sync: sync,
                 }))))
             }
             _ => Err(())
@@ -695,7 +970,7 @@ pub fn convert(
         property: &str,
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, Rule), ()> {
         let start_offset = offset;
@@ -718,6 +993,11 @@ pub fn convert(
         ) {
             update(range, &mut data, &mut offset);
             rule = Some(val);
+        } else if let Ok((range, val)) = read_char_class(
+            debug_id, data, offset, strings, ignored
+        ) {
+            update(range, &mut data, &mut offset);
+            rule = Some(val);
         } else if let Ok((range, val)) = read_token(
             debug_id, data, offset, strings, ignored
         ) {
@@ -753,6 +1033,11 @@ pub fn convert(
         ) {
             update(range, &mut data, &mut offset);
             rule = Some(val);
+        } else if let Ok((range, val)) = read_lookahead(
+            debug_id, data, offset, strings, ignored
+        ) {
+            update(range, &mut data, &mut offset);
+            rule = Some(val);
         } else if let Ok((range, val)) = read_separated_by(
             debug_id, data, offset, strings, ignored
         ) {
@@ -763,6 +1048,11 @@ pub fn convert(
         ) {
             update(range, &mut data, &mut offset);
             rule = Some(val);
+        } else if let Ok((range, val)) = read_indent(
+            debug_id, data, offset, strings, ignored
+        ) {
+            update(range, &mut data, &mut offset);
+            rule = Some(val);
         } else if let Ok((range, val)) = read_repeat(
             debug_id, data, offset, strings, ignored
         ) {
@@ -782,9 +1072,9 @@ pub fn convert(
     fn read_node(
         mut data: &[(Range, MetaData)],
         mut offset: usize,
-        strings: &[(Rc<String>, Rc<String>)],
+        strings: &[(Arc<String>, Arc<String>)],
         ignored: &mut Vec<Range>
-    ) -> Result<(Range, (Rc<String>, Rule)), ()> {
+    ) -> Result<(Range, (Arc<String>, Rule)), ()> {
         let start_offset = offset;
         let node = "node";
         let range = try!(start_node(node, data, offset));
@@ -822,8 +1112,15 @@ pub fn convert(
         }
     }
 
-    let mut strings: Vec<(Rc<String>, Rc<String>)> = vec![];
+    let mut strings: Vec<(Arc<String>, Arc<String>)> = vec![];
     let mut offset: usize = 0;
+    let comments = match read_comments(data, offset) {
+        Ok((range, val)) => {
+            update(range, &mut data, &mut offset);
+            Some(Arc::new(val))
+        }
+        Err(()) => None
+    };
     loop {
         if let Ok((range, val)) = read_string(data, offset) {
             strings.push(val);
@@ -838,11 +1135,17 @@ pub fn convert(
             update(range, &mut data, &mut offset);
             res.push(val);
         } else if offset < data.len() {
-            return Err(());
+            return Err((data[0].0, ParseError::InvalidMetaData));
         } else {
             break;
         }
     }
-    update_refs(&res);
+    update_refs(&mut res);
+    if let Some(ref comments) = comments {
+        for r in &mut res { r.1.set_comments(comments); }
+    }
+    if let Some(debug_id) = infinite_loop(&res) {
+        return Err((Range::empty(0), ParseError::InfiniteLoop(debug_id)));
+    }
     Ok(res)
 }
@@ -1,7 +1,11 @@
 //! Bootstrapping meta rules for changing documents at runtime
 
-pub use self::rules::rules;
-pub use self::convert::convert;
+pub use self::rules::{ rules, parse as parse_syntax };
+pub use self::convert::{ convert, convert_with_error };
+pub use self::syntax::syntax;
+pub use self::codegen::codegen;
 
 mod rules;
 mod convert;
+mod syntax;
+mod codegen;
@@ -1,747 +1,3800 @@
-use std::rc::Rc;
-use std::cell::Cell;
+// Generated by `piston_meta::bootstrap::codegen`, do not edit.
 
+use std::sync::Arc;
+
+use support::{
+    ends_with_new_line,
+    err_update,
+    line_comments_len,
+    node,
+    number,
+    parse_with,
+    ret_err,
+    text,
+    token,
+    until_any_or_whitespace,
+    update,
+    whitespace,
+};
 use {
     update_refs,
+    Comments,
     Lines,
+    Lookahead,
+    MetaData,
     Node,
     Number,
+    NumberKind,
     Optional,
+    ParseError,
+    ParseResult,
+    Range,
+    Repeat,
     Rule,
     Select,
     SeparatedBy,
     Sequence,
     Text,
     Token,
+    Tokenizer,
+    TokenizerState,
     UntilAnyOrWhitespace,
     Whitespace,
 };
 
-/// Returns rules for parsing meta rules.
-pub fn rules() -> Vec<(Rc<String>, Rule)> {
-    let opt: Rc<String> = Rc::new("optional".into());
-    let inv: Rc<String> = Rc::new("inverted".into());
-    let prop: Rc<String> = Rc::new("property".into());
-    let any: Rc<String> = Rc::new("any_characters".into());
-    let seps: Rc<String> = Rc::new("[]{}():.!?\"".into());
-
-    // 1 "string" [..seps!"name" ":" w? t?"text"]
-    let string_rule = Rule::Sequence(Sequence {
-        debug_id: 1000,
+/// Returns the rules.
+pub fn rules() -> Vec<(Arc<String>, Rule)> {
+    let comments = vec![
+        Arc::new(Comments {
+            line: vec![Arc::new("//".into())],
+            block: vec![],
+            nested: false,
+        }),
+    ];
+    let mut rules = vec![];
+
+    // 1 "string" [.."[]{}():.!?\""!"name" ":" w? t?"text"]
+    rules.push((Arc::new("string".into()), Rule::Sequence(Sequence {
+        debug_id: 1005,
         args: vec![
             Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {
                 debug_id: 1001,
-                any_characters: seps.clone(),
+                any_characters: Arc::new("[]{}():.!?\"".into()),
                 optional: false,
-                property: Some(Rc::new("name".into()))
+                property: Some(Arc::new("name".into())),
             }),
             Rule::Token(Token {
                 debug_id: 1002,
-                text: Rc::new(":".into()),
+                text: Arc::new(":".into()),
                 inverted: false,
-                property: None
+                case_insensitive: false,
+                property: None,
             }),
             Rule::Whitespace(Whitespace {
                 debug_id: 1003,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Text(Text {
                 debug_id: 1004,
                 allow_empty: true,
-                property: Some(Rc::new("text".into())),
-            })
-        ]
-    });
+                property: Some(Arc::new("text".into())),
+            }),
+        ],
+    })));
 
     // 2 "node" [$"id" w! t!"name" w! @"rule""rule"]
-    let node_rule = Rule::Sequence(Sequence {
-        debug_id: 2000,
+    rules.push((Arc::new("node".into()), Rule::Sequence(Sequence {
+        debug_id: 2006,
         args: vec![
             Rule::Number(Number {
                 debug_id: 2001,
+                property: Some(Arc::new("id".into())),
                 allow_underscore: false,
-                property: Some(Rc::new("id".into())),
+                kind: NumberKind::F64,
+                allow_radix: false,
+                require_sign: false,
+                allow_special: false,
             }),
             Rule::Whitespace(Whitespace {
                 debug_id: 2002,
                 optional: false,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Text(Text {
                 debug_id: 2003,
                 allow_empty: false,
-                property: Some(Rc::new("name".into())),
+                property: Some(Arc::new("name".into())),
             }),
             Rule::Whitespace(Whitespace {
                 debug_id: 2004,
                 optional: false,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Node(Node {
                 debug_id: 2005,
-                name: Rc::new("rule".into()),
-                property: Some(Rc::new("rule".into())),
-                index: Cell::new(None),
-            })
-        ]
-    });
-
-    // 3 "set" {t!"value" ..seps!"ref"}
-    let set_rule = Rule::Select(Select {
+                name: Arc::new("rule".into()),
+                property: Some(Arc::new("rule".into())),
+                index: None,
+            }),
+        ],
+    })));
+
+    // 3 "set" {t!"value" .."[]{}():.!?\""!"ref"}
+    rules.push((Arc::new("set".into()), Rule::Select(Select {
         debug_id: 3003,
         args: vec![
             Rule::Text(Text {
-                debug_id: 3004,
+                debug_id: 3001,
                 allow_empty: false,
-                property: Some(Rc::new("value".into())),
+                property: Some(Arc::new("value".into())),
             }),
             Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {
-                debug_id: 3005,
-                any_characters: seps.clone(),
+                debug_id: 3002,
+                any_characters: Arc::new("[]{}():.!?\"".into()),
                 optional: false,
-                property: Some(Rc::new("ref".into())),
-            })
-        ]
-    });
-
-    // 4 "opt" {"?"opt "!"!opt}
-    let opt_rule = Rule::Select(Select {
-        debug_id: 4000,
+                property: Some(Arc::new("ref".into())),
+            }),
+        ],
+    })));
+
+    // 4 "opt" {"?""optional" "!"!"optional"}
+    rules.push((Arc::new("opt".into()), Rule::Select(Select {
+        debug_id: 4003,
         args: vec![
             Rule::Token(Token {
                 debug_id: 4001,
-                text: Rc::new("?".into()),
+                text: Arc::new("?".into()),
                 inverted: false,
-                property: Some(opt.clone())
+                case_insensitive: false,
+                property: Some(Arc::new("optional".into())),
             }),
             Rule::Token(Token {
                 debug_id: 4002,
-                text: Rc::new("!".into()),
+                text: Arc::new("!".into()),
                 inverted: true,
-                property: Some(opt.clone())
+                case_insensitive: false,
+                property: Some(Arc::new("optional".into())),
             }),
-        ]
-    });
+        ],
+    })));
 
-    // 5 "number" ["$" ?"_""underscore" ?@"set"prop]
-    let number_rule = Rule::Sequence(Sequence {
-        debug_id: 5000,
+    /*
+    5 "number" [
+      "$"
+      ?"_""underscore"
+      ?[r!({"i""i64" "u""u64" "x""radix" "s""sign" "n""special"}) &"\""]
+      ?@"set""property"
+    ]
+    */
+    rules.push((Arc::new("number".into()), Rule::Sequence(Sequence {
+        debug_id: 5017,
         args: vec![
             Rule::Token(Token {
                 debug_id: 5001,
-                text: Rc::new("$".into()),
+                text: Arc::new("$".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Optional(Box::new(Optional {
-                debug_id: 5002,
+                debug_id: 5003,
                 rule: Rule::Token(Token {
-                    debug_id: 5003,
-                    text: Rc::new("_".into()),
+                    debug_id: 5002,
+                    text: Arc::new("_".into()),
                     inverted: false,
-                    property: Some(Rc::new("underscore".into()))
-                })
+                    case_insensitive: false,
+                    property: Some(Arc::new("underscore".into())),
+                }),
             })),
             Rule::Optional(Box::new(Optional {
-                debug_id: 5004,
+                debug_id: 5014,
+                rule: Rule::Sequence(Sequence {
+                    debug_id: 5013,
+                    args: vec![
+                        Rule::Repeat(Box::new(Repeat {
+                            debug_id: 5010,
+                            optional: false,
+                            count: None,
+                            sync: None,
+                            rule: Rule::Select(Select {
+                                debug_id: 5009,
+                                args: vec![
+                                    Rule::Token(Token {
+                                        debug_id: 5004,
+                                        text: Arc::new("i".into()),
+                                        inverted: false,
+                                        case_insensitive: false,
+                                        property: Some(Arc::new("i64".into())),
+                                    }),
+                                    Rule::Token(Token {
+                                        debug_id: 5005,
+                                        text: Arc::new("u".into()),
+                                        inverted: false,
+                                        case_insensitive: false,
+                                        property: Some(Arc::new("u64".into())),
+                                    }),
+                                    Rule::Token(Token {
+                                        debug_id: 5006,
+                                        text: Arc::new("x".into()),
+                                        inverted: false,
+                                        case_insensitive: false,
+                                        property: Some(Arc::new("radix".into())),
+                                    }),
+                                    Rule::Token(Token {
+                                        debug_id: 5007,
+                                        text: Arc::new("s".into()),
+                                        inverted: false,
+                                        case_insensitive: false,
+                                        property: Some(Arc::new("sign".into())),
+                                    }),
+                                    Rule::Token(Token {
+                                        debug_id: 5008,
+                                        text: Arc::new("n".into()),
+                                        inverted: false,
+                                        case_insensitive: false,
+                                        property: Some(Arc::new("special".into())),
+                                    }),
+                                ],
+                            }),
+                        })),
+                        Rule::Lookahead(Box::new(Lookahead {
+                            debug_id: 5012,
+                            negate: false,
+                            rule: Rule::Token(Token {
+                                debug_id: 5011,
+                                text: Arc::new("\"".into()),
+                                inverted: false,
+                                case_insensitive: false,
+                                property: None,
+                            }),
+                        })),
+                    ],
+                }),
+            })),
+            Rule::Optional(Box::new(Optional {
+                debug_id: 5016,
                 rule: Rule::Node(Node {
-                    debug_id: 5005,
-                    name: Rc::new("set".into()),
-                    property: Some(Rc::new("property".into())),
-                    index: Cell::new(None),
-                })
-            }))
-        ]
-    });
-
-    // 6 "text" ["t" {"?""allow_empty" "!"!"allow_empty"} ?@"set"prop]
-    let text_rule = Rule::Sequence(Sequence {
-        debug_id: 6000,
+                    debug_id: 5015,
+                    name: Arc::new("set".into()),
+                    property: Some(Arc::new("property".into())),
+                    index: None,
+                }),
+            })),
+        ],
+    })));
+
+    // 6 "text" ["t" {"?""allow_empty" "!"!"allow_empty"} ?@"set""property"]
+    rules.push((Arc::new("text".into()), Rule::Sequence(Sequence {
+        debug_id: 6007,
         args: vec![
             Rule::Token(Token {
                 debug_id: 6001,
-                text: Rc::new("t".into()),
+                text: Arc::new("t".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Select(Select {
-                debug_id: 6002,
+                debug_id: 6004,
                 args: vec![
                     Rule::Token(Token {
-                        debug_id: 6003,
-                        text: Rc::new("?".into()),
+                        debug_id: 6002,
+                        text: Arc::new("?".into()),
                         inverted: false,
-                        property: Some(Rc::new("allow_empty".into())),
+                        case_insensitive: false,
+                        property: Some(Arc::new("allow_empty".into())),
                     }),
                     Rule::Token(Token {
-                        debug_id: 6004,
-                        text: Rc::new("!".into()),
+                        debug_id: 6003,
+                        text: Arc::new("!".into()),
                         inverted: true,
-                        property: Some(Rc::new("allow_empty".into())),
-                    })
-                ]
+                        case_insensitive: false,
+                        property: Some(Arc::new("allow_empty".into())),
+                    }),
+                ],
             }),
             Rule::Optional(Box::new(Optional {
-                debug_id: 6005,
+                debug_id: 6006,
                 rule: Rule::Node(Node {
-                    debug_id: 6006,
-                    name: Rc::new("set".into()),
-                    property: Some(prop.clone()),
-                    index: Cell::new(None),
-                })
+                    debug_id: 6005,
+                    name: Arc::new("set".into()),
+                    property: Some(Arc::new("property".into())),
+                    index: None,
+                }),
             })),
-        ]
-    });
+        ],
+    })));
 
-    // 7 "reference" ["@" t!"name" ?@"set"prop]
-    let reference_rule = Rule::Sequence(Sequence {
-        debug_id: 7000,
+    // 7 "reference" ["@" t!"name" ?@"set""property"]
+    rules.push((Arc::new("reference".into()), Rule::Sequence(Sequence {
+        debug_id: 7005,
         args: vec![
             Rule::Token(Token {
                 debug_id: 7001,
-                text: Rc::new("@".into()),
+                text: Arc::new("@".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Text(Text {
                 debug_id: 7002,
                 allow_empty: false,
-                property: Some(Rc::new("name".into())),
+                property: Some(Arc::new("name".into())),
             }),
             Rule::Optional(Box::new(Optional {
-                debug_id: 7003,
+                debug_id: 7004,
                 rule: Rule::Node(Node {
-                    debug_id: 7004,
-                    name: Rc::new("set".into()),
-                    property: Some(prop.clone()),
-                    index: Cell::new(None)
-                })
-            }))
-        ]
-    });
+                    debug_id: 7003,
+                    name: Arc::new("set".into()),
+                    property: Some(Arc::new("property".into())),
+                    index: None,
+                }),
+            })),
+        ],
+    })));
 
     // 8 "sequence" ["[" w? s!.(w!) {@"rule""rule"} "]"]
-    let sequence_rule = Rule::Sequence(Sequence {
-        debug_id: 8000,
+    rules.push((Arc::new("sequence".into()), Rule::Sequence(Sequence {
+        debug_id: 8007,
         args: vec![
             Rule::Token(Token {
                 debug_id: 8001,
-                text: Rc::new("[".into()),
+                text: Arc::new("[".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Whitespace(Whitespace {
                 debug_id: 8002,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::SeparatedBy(Box::new(SeparatedBy {
-                debug_id: 8003,
+                debug_id: 8005,
                 optional: false,
                 allow_trail: true,
+                count: None,
                 by: Rule::Whitespace(Whitespace {
-                    debug_id: 8004,
+                    debug_id: 8003,
                     optional: false,
+                    comments: Some(comments[0].clone()),
                 }),
                 rule: Rule::Node(Node {
-                    debug_id: 8005,
-                    name: Rc::new("rule".into()),
-                    property: Some(Rc::new("rule".into())),
-                    index: Cell::new(None)
-                })
+                    debug_id: 8004,
+                    name: Arc::new("rule".into()),
+                    property: Some(Arc::new("rule".into())),
+                    index: None,
+                }),
             })),
             Rule::Token(Token {
                 debug_id: 8006,
-                text: Rc::new("]".into()),
+                text: Arc::new("]".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
-            })
-        ]
-    });
+            }),
+        ],
+    })));
 
     // 9 "select" ["{" w? s!.(w!) {@"rule""rule"} "}"]
-    let select_rule = Rule::Sequence(Sequence {
-        debug_id: 9000,
+    rules.push((Arc::new("select".into()), Rule::Sequence(Sequence {
+        debug_id: 9007,
         args: vec![
             Rule::Token(Token {
                 debug_id: 9001,
-                text: Rc::new("{".into()),
+                text: Arc::new("{".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Whitespace(Whitespace {
                 debug_id: 9002,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::SeparatedBy(Box::new(SeparatedBy {
-                debug_id: 9003,
+                debug_id: 9005,
                 optional: false,
                 allow_trail: true,
+                count: None,
                 by: Rule::Whitespace(Whitespace {
-                    debug_id: 9004,
+                    debug_id: 9003,
                     optional: false,
+                    comments: Some(comments[0].clone()),
                 }),
                 rule: Rule::Node(Node {
-                    debug_id: 9005,
-                    name: Rc::new("rule".into()),
-                    property: Some(Rc::new("rule".into())),
-                    index: Cell::new(None),
-                })
+                    debug_id: 9004,
+                    name: Arc::new("rule".into()),
+                    property: Some(Arc::new("rule".into())),
+                    index: None,
+                }),
             })),
             Rule::Token(Token {
                 debug_id: 9006,
-                text: Rc::new("}".into()),
+                text: Arc::new("}".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
-            })
-        ]
-    });
+            }),
+        ],
+    })));
 
-    // 10 "separated_by" ["s" @"opt" ?".""allow_trail"
-    //  "(" w? @"rule""by" w? ")" w? "{" w? @"rule""rule" w? "}"]
-    let separated_by_rule = Rule::Sequence(Sequence {
-        debug_id: 10000,
+    /*
+    10 "separated_by" [
+      "s"
+      {@"count""count" @"opt"}
+      ?".""allow_trail"
+      "("
+      w?
+      @"rule""by"
+      w?
+      ")"
+      w?
+      "{"
+      w?
+      @"rule""rule"
+      w?
+      "}"
+    ]
+    */
+    rules.push((Arc::new("separated_by".into()), Rule::Sequence(Sequence {
+        debug_id: 10018,
         args: vec![
             Rule::Token(Token {
                 debug_id: 10001,
-                text: Rc::new("s".into()),
+                text: Arc::new("s".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
-            Rule::Node(Node {
-                debug_id: 10002,
-                name: Rc::new("opt".into()),
-                property: None,
-                index: Cell::new(None),
+            Rule::Select(Select {
+                debug_id: 10004,
+                args: vec![
+                    Rule::Node(Node {
+                        debug_id: 10002,
+                        name: Arc::new("count".into()),
+                        property: Some(Arc::new("count".into())),
+                        index: None,
+                    }),
+                    Rule::Node(Node {
+                        debug_id: 10003,
+                        name: Arc::new("opt".into()),
+                        property: None,
+                        index: None,
+                    }),
+                ],
             }),
             Rule::Optional(Box::new(Optional {
-                debug_id: 10003,
+                debug_id: 10006,
                 rule: Rule::Token(Token {
-                    debug_id: 10004,
-                    text: Rc::new(".".into()),
+                    debug_id: 10005,
+                    text: Arc::new(".".into()),
                     inverted: false,
-                    property: Some(Rc::new("allow_trail".into())),
-                })
+                    case_insensitive: false,
+                    property: Some(Arc::new("allow_trail".into())),
+                }),
             })),
             Rule::Token(Token {
-                debug_id: 10004,
-                text: Rc::new("(".into()),
+                debug_id: 10007,
+                text: Arc::new("(".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Whitespace(Whitespace {
-                debug_id: 10005,
+                debug_id: 10008,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Node(Node {
-                debug_id: 10006,
-                name: Rc::new("rule".into()),
-                property: Some(Rc::new("by".into())),
-                index: Cell::new(None),
+                debug_id: 10009,
+                name: Arc::new("rule".into()),
+                property: Some(Arc::new("by".into())),
+                index: None,
             }),
             Rule::Whitespace(Whitespace {
-                debug_id: 10007,
+                debug_id: 10010,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Token(Token {
-                debug_id: 10008,
-                text: Rc::new(")".into()),
+                debug_id: 10011,
+                text: Arc::new(")".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Whitespace(Whitespace {
-                debug_id: 10009,
+                debug_id: 10012,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Token(Token {
-                debug_id: 10010,
-                text: Rc::new("{".into()),
+                debug_id: 10013,
+                text: Arc::new("{".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Whitespace(Whitespace {
-                debug_id: 10011,
+                debug_id: 10014,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Node(Node {
-                debug_id: 10012,
-                name: Rc::new("rule".into()),
-                property: Some(Rc::new("rule".into())),
-                index: Cell::new(None),
+                debug_id: 10015,
+                name: Arc::new("rule".into()),
+                property: Some(Arc::new("rule".into())),
+                index: None,
             }),
             Rule::Whitespace(Whitespace {
-                debug_id: 10013,
+                debug_id: 10016,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Token(Token {
-                debug_id: 10014,
-                text: Rc::new("}".into()),
+                debug_id: 10017,
+                text: Arc::new("}".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
-        ]
-    });
+        ],
+    })));
 
-    // 11 "token" [@"set""text" ?[?"!"inv @"set"prop]]
-    let token_rule = Rule::Sequence(Sequence {
-        debug_id: 11000,
+    /*
+    11 "token" [
+      ?["i""case_insensitive" &"\""]
+      @"set""text"
+      ?[?"!""inverted" @"set""property"]
+    ]
+    */
+    rules.push((Arc::new("token".into()), Rule::Sequence(Sequence {
+        debug_id: 11012,
         args: vec![
+            Rule::Optional(Box::new(Optional {
+                debug_id: 11005,
+                rule: Rule::Sequence(Sequence {
+                    debug_id: 11004,
+                    args: vec![
+                        Rule::Token(Token {
+                            debug_id: 11001,
+                            text: Arc::new("i".into()),
+                            inverted: false,
+                            case_insensitive: false,
+                            property: Some(Arc::new("case_insensitive".into())),
+                        }),
+                        Rule::Lookahead(Box::new(Lookahead {
+                            debug_id: 11003,
+                            negate: false,
+                            rule: Rule::Token(Token {
+                                debug_id: 11002,
+                                text: Arc::new("\"".into()),
+                                inverted: false,
+                                case_insensitive: false,
+                                property: None,
+                            }),
+                        })),
+                    ],
+                }),
+            })),
             Rule::Node(Node {
-                debug_id: 11001,
-                name: Rc::new("set".into()),
-                property: Some(Rc::new("text".into())),
-                index: Cell::new(None),
+                debug_id: 11006,
+                name: Arc::new("set".into()),
+                property: Some(Arc::new("text".into())),
+                index: None,
             }),
             Rule::Optional(Box::new(Optional {
-                debug_id: 11002,
+                debug_id: 11011,
                 rule: Rule::Sequence(Sequence {
-                    debug_id: 11003,
+                    debug_id: 11010,
                     args: vec![
                         Rule::Optional(Box::new(Optional {
-                            debug_id: 11006,
+                            debug_id: 11008,
                             rule: Rule::Token(Token {
                                 debug_id: 11007,
-                                text: Rc::new("!".into()),
+                                text: Arc::new("!".into()),
                                 inverted: false,
-                                property: Some(inv.clone()),
-                            })
+                                case_insensitive: false,
+                                property: Some(Arc::new("inverted".into())),
+                            }),
                         })),
                         Rule::Node(Node {
                             debug_id: 11009,
-                            name: Rc::new("set".into()),
-                            property: Some(prop.clone()),
-                            index: Cell::new(None),
-                        })
-                    ]
-                })
+                            name: Arc::new("set".into()),
+                            property: Some(Arc::new("property".into())),
+                            index: None,
+                        }),
+                    ],
+                }),
             })),
-        ]
-    });
+        ],
+    })));
 
     // 12 "optional" ["?" @"rule""rule"]
-    let optional_rule = Rule::Sequence(Sequence {
-        debug_id: 12001,
+    rules.push((Arc::new("optional".into()), Rule::Sequence(Sequence {
+        debug_id: 12003,
         args: vec![
             Rule::Token(Token {
-                debug_id: 12002,
-                text: Rc::new("?".into()),
+                debug_id: 12001,
+                text: Arc::new("?".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Node(Node {
-                debug_id: 12004,
-                name: Rc::new("rule".into()),
-                property: Some(Rc::new("rule".into())),
-                index: Cell::new(None),
-            })
-        ]
-    });
+                debug_id: 12002,
+                name: Arc::new("rule".into()),
+                property: Some(Arc::new("rule".into())),
+                index: None,
+            }),
+        ],
+    })));
 
     // 13 "whitespace" ["w" @"opt"]
-    let whitespace_rule = Rule::Sequence(Sequence {
-        debug_id: 13000,
+    rules.push((Arc::new("whitespace".into()), Rule::Sequence(Sequence {
+        debug_id: 13003,
         args: vec![
             Rule::Token(Token {
                 debug_id: 13001,
-                text: Rc::new("w".into()),
+                text: Arc::new("w".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Node(Node {
                 debug_id: 13002,
-                name: Rc::new("opt".into()),
+                name: Arc::new("opt".into()),
                 property: None,
-                index: Cell::new(None),
-            })
-        ]
-    });
+                index: None,
+            }),
+        ],
+    })));
 
-    // 14 "until_any_or_whitespace" [".." @"set"any @"opt" ?@"set"prop]
-    let until_any_or_whitespace_rule = Rule::Sequence(Sequence {
-        debug_id: 14001,
+    /*
+    14 "until_any_or_whitespace" [
+      ".."
+      @"set""any_characters"
+      @"opt"
+      ?@"set""property"
+    ]
+    */
+    rules.push((Arc::new("until_any_or_whitespace".into()), Rule::Sequence(Sequence {
+        debug_id: 14006,
         args: vec![
             Rule::Token(Token {
-                debug_id: 14002,
-                text: Rc::new("..".into()),
+                debug_id: 14001,
+                text: Arc::new("..".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Node(Node {
-                debug_id: 14003,
-                name: Rc::new("set".into()),
-                property: Some(any.clone()),
-                index: Cell::new(None),
+                debug_id: 14002,
+                name: Arc::new("set".into()),
+                property: Some(Arc::new("any_characters".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 14004,
-                name: Rc::new("opt".into()),
+                debug_id: 14003,
+                name: Arc::new("opt".into()),
                 property: None,
-                index: Cell::new(None),
+                index: None,
             }),
             Rule::Optional(Box::new(Optional {
                 debug_id: 14005,
                 rule: Rule::Node(Node {
-                    debug_id: 14006,
-                    name: Rc::new("set".into()),
-                    property: Some(prop.clone()),
-                    index: Cell::new(None),
-                })
-            }))
-        ]
-    });
-
-    // 15 "until_any" ["..." @"set"any @"opt" ?@"set"prop]
-    let until_any_rule = Rule::Sequence(Sequence {
-        debug_id: 15000,
+                    debug_id: 14004,
+                    name: Arc::new("set".into()),
+                    property: Some(Arc::new("property".into())),
+                    index: None,
+                }),
+            })),
+        ],
+    })));
+
+    // 15 "until_any" ["..." @"set""any_characters" @"opt" ?@"set""property"]
+    rules.push((Arc::new("until_any".into()), Rule::Sequence(Sequence {
+        debug_id: 15006,
         args: vec![
             Rule::Token(Token {
                 debug_id: 15001,
-                text: Rc::new("...".into()),
+                text: Arc::new("...".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Node(Node {
                 debug_id: 15002,
-                name: Rc::new("set".into()),
-                property: Some(any.clone()),
-                index: Cell::new(None),
+                name: Arc::new("set".into()),
+                property: Some(Arc::new("any_characters".into())),
+                index: None,
             }),
             Rule::Node(Node {
                 debug_id: 15003,
-                name: Rc::new("opt".into()),
+                name: Arc::new("opt".into()),
                 property: None,
-                index: Cell::new(None),
+                index: None,
             }),
             Rule::Optional(Box::new(Optional {
-                debug_id: 15004,
+                debug_id: 15005,
                 rule: Rule::Node(Node {
-                    debug_id: 15005,
-                    name: Rc::new("set".into()),
-                    property: Some(prop.clone()),
-                    index: Cell::new(None),
-                })
-            }))
-        ]
-    });
-
-    // 16 "repeat" ["r" @"opt" "(" @"rule""rule" ")"]
-    let repeat_rule = Rule::Sequence(Sequence {
-        debug_id: 16000,
+                    debug_id: 15004,
+                    name: Arc::new("set".into()),
+                    property: Some(Arc::new("property".into())),
+                    index: None,
+                }),
+            })),
+        ],
+    })));
+
+    /*
+    16 "repeat" [
+      "r"
+      {@"count""count" @"opt"}
+      "("
+      @"rule""rule"
+      ")"
+      ?["~" @"set""sync"]
+    ]
+    */
+    rules.push((Arc::new("repeat".into()), Rule::Sequence(Sequence {
+        debug_id: 16012,
         args: vec![
             Rule::Token(Token {
                 debug_id: 16001,
-                text: Rc::new("r".into()),
+                text: Arc::new("r".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
-            Rule::Node(Node {
-                debug_id: 16002,
-                name: Rc::new("opt".into()),
-                property: None,
-                index: Cell::new(None),
+            Rule::Select(Select {
+                debug_id: 16004,
+                args: vec![
+                    Rule::Node(Node {
+                        debug_id: 16002,
+                        name: Arc::new("count".into()),
+                        property: Some(Arc::new("count".into())),
+                        index: None,
+                    }),
+                    Rule::Node(Node {
+                        debug_id: 16003,
+                        name: Arc::new("opt".into()),
+                        property: None,
+                        index: None,
+                    }),
+                ],
             }),
             Rule::Token(Token {
-                debug_id: 16003,
-                text: Rc::new("(".into()),
+                debug_id: 16005,
+                text: Arc::new("(".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Node(Node {
-                debug_id: 16004,
-                name: Rc::new("rule".into()),
-                property: Some(Rc::new("rule".into())),
-                index: Cell::new(None),
+                debug_id: 16006,
+                name: Arc::new("rule".into()),
+                property: Some(Arc::new("rule".into())),
+                index: None,
             }),
             Rule::Token(Token {
-                debug_id: 16005,
-                text: Rc::new(")".into()),
+                debug_id: 16007,
+                text: Arc::new(")".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
-            })
-        ]
-    });
+            }),
+            Rule::Optional(Box::new(Optional {
+                debug_id: 16011,
+                rule: Rule::Sequence(Sequence {
+                    debug_id: 16010,
+                    args: vec![
+                        Rule::Token(Token {
+                            debug_id: 16008,
+                            text: Arc::new("~".into()),
+                            inverted: false,
+                            case_insensitive: false,
+                            property: None,
+                        }),
+                        Rule::Node(Node {
+                            debug_id: 16009,
+                            name: Arc::new("set".into()),
+                            property: Some(Arc::new("sync".into())),
+                            index: None,
+                        }),
+                    ],
+                }),
+            })),
+        ],
+    })));
 
     // 17 "lines" ["l(" w? @"rule""rule" w? ")"]
-    let lines_rule = Rule::Sequence(Sequence {
-        debug_id: 17000,
+    rules.push((Arc::new("lines".into()), Rule::Sequence(Sequence {
+        debug_id: 17006,
         args: vec![
             Rule::Token(Token {
                 debug_id: 17001,
-                text: Rc::new("l(".into()),
+                text: Arc::new("l(".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             Rule::Whitespace(Whitespace {
                 debug_id: 17002,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Node(Node {
                 debug_id: 17003,
-                name: Rc::new("rule".into()),
-                property: Some(Rc::new("rule".into())),
-                index: Cell::new(None),
+                name: Arc::new("rule".into()),
+                property: Some(Arc::new("rule".into())),
+                index: None,
             }),
             Rule::Whitespace(Whitespace {
                 debug_id: 17004,
                 optional: true,
+                comments: Some(comments[0].clone()),
             }),
             Rule::Token(Token {
                 debug_id: 17005,
-                text: Rc::new(")".into()),
+                text: Arc::new(")".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
-            })
-        ]
-    });
+            }),
+        ],
+    })));
+
+    // 18 "count" ["{" $"min" ?[",""to" ?$"max"] "}"]
+    rules.push((Arc::new("count".into()), Rule::Sequence(Sequence {
+        debug_id: 18009,
+        args: vec![
+            Rule::Token(Token {
+                debug_id: 18001,
+                text: Arc::new("{".into()),
+                inverted: false,
+                case_insensitive: false,
+                property: None,
+            }),
+            Rule::Number(Number {
+                debug_id: 18002,
+                property: Some(Arc::new("min".into())),
+                allow_underscore: false,
+                kind: NumberKind::F64,
+                allow_radix: false,
+                require_sign: false,
+                allow_special: false,
+            }),
+            Rule::Optional(Box::new(Optional {
+                debug_id: 18007,
+                rule: Rule::Sequence(Sequence {
+                    debug_id: 18006,
+                    args: vec![
+                        Rule::Token(Token {
+                            debug_id: 18003,
+                            text: Arc::new(",".into()),
+                            inverted: false,
+                            case_insensitive: false,
+                            property: Some(Arc::new("to".into())),
+                        }),
+                        Rule::Optional(Box::new(Optional {
+                            debug_id: 18005,
+                            rule: Rule::Number(Number {
+                                debug_id: 18004,
+                                property: Some(Arc::new("max".into())),
+                                allow_underscore: false,
+                                kind: NumberKind::F64,
+                                allow_radix: false,
+                                require_sign: false,
+                                allow_special: false,
+                            }),
+                        })),
+                    ],
+                }),
+            })),
+            Rule::Token(Token {
+                debug_id: 18008,
+                text: Arc::new("}".into()),
+                inverted: false,
+                case_insensitive: false,
+                property: None,
+            }),
+        ],
+    })));
 
     /*
-    18 "rule" {
+    19 "char_class" [
+      "c"
+      ?"!""negated"
+      t?"class"
+      ?{@"count""count" @"opt"}
+      ?@"set""property"
+    ]
+    */
+    rules.push((Arc::new("char_class".into()), Rule::Sequence(Sequence {
+        debug_id: 19011,
+        args: vec![
+            Rule::Token(Token {
+                debug_id: 19001,
+                text: Arc::new("c".into()),
+                inverted: false,
+                case_insensitive: false,
+                property: None,
+            }),
+            Rule::Optional(Box::new(Optional {
+                debug_id: 19003,
+                rule: Rule::Token(Token {
+                    debug_id: 19002,
+                    text: Arc::new("!".into()),
+                    inverted: false,
+                    case_insensitive: false,
+                    property: Some(Arc::new("negated".into())),
+                }),
+            })),
+            Rule::Text(Text {
+                debug_id: 19004,
+                allow_empty: true,
+                property: Some(Arc::new("class".into())),
+            }),
+            Rule::Optional(Box::new(Optional {
+                debug_id: 19008,
+                rule: Rule::Select(Select {
+                    debug_id: 19007,
+                    args: vec![
+                        Rule::Node(Node {
+                            debug_id: 19005,
+                            name: Arc::new("count".into()),
+                            property: Some(Arc::new("count".into())),
+                            index: None,
+                        }),
+                        Rule::Node(Node {
+                            debug_id: 19006,
+                            name: Arc::new("opt".into()),
+                            property: None,
+                            index: None,
+                        }),
+                    ],
+                }),
+            })),
+            Rule::Optional(Box::new(Optional {
+                debug_id: 19010,
+                rule: Rule::Node(Node {
+                    debug_id: 19009,
+                    name: Arc::new("set".into()),
+                    property: Some(Arc::new("property".into())),
+                    index: None,
+                }),
+            })),
+        ],
+    })));
+
+    // 20 "lookahead" [{"!""negate" "&"} @"rule""rule"]
+    rules.push((Arc::new("lookahead".into()), Rule::Sequence(Sequence {
+        debug_id: 20005,
+        args: vec![
+            Rule::Select(Select {
+                debug_id: 20003,
+                args: vec![
+                    Rule::Token(Token {
+                        debug_id: 20001,
+                        text: Arc::new("!".into()),
+                        inverted: false,
+                        case_insensitive: false,
+                        property: Some(Arc::new("negate".into())),
+                    }),
+                    Rule::Token(Token {
+                        debug_id: 20002,
+                        text: Arc::new("&".into()),
+                        inverted: false,
+                        case_insensitive: false,
+                        property: None,
+                    }),
+                ],
+            }),
+            Rule::Node(Node {
+                debug_id: 20004,
+                name: Arc::new("rule".into()),
+                property: Some(Arc::new("rule".into())),
+                index: None,
+            }),
+        ],
+    })));
+
+    // 21 "indent" ["i" ?"t""tabs" "(" w? @"rule""rule" w? ")"]
+    rules.push((Arc::new("indent".into()), Rule::Sequence(Sequence {
+        debug_id: 21009,
+        args: vec![
+            Rule::Token(Token {
+                debug_id: 21001,
+                text: Arc::new("i".into()),
+                inverted: false,
+                case_insensitive: false,
+                property: None,
+            }),
+            Rule::Optional(Box::new(Optional {
+                debug_id: 21003,
+                rule: Rule::Token(Token {
+                    debug_id: 21002,
+                    text: Arc::new("t".into()),
+                    inverted: false,
+                    case_insensitive: false,
+                    property: Some(Arc::new("tabs".into())),
+                }),
+            })),
+            Rule::Token(Token {
+                debug_id: 21004,
+                text: Arc::new("(".into()),
+                inverted: false,
+                case_insensitive: false,
+                property: None,
+            }),
+            Rule::Whitespace(Whitespace {
+                debug_id: 21005,
+                optional: true,
+                comments: Some(comments[0].clone()),
+            }),
+            Rule::Node(Node {
+                debug_id: 21006,
+                name: Arc::new("rule".into()),
+                property: Some(Arc::new("rule".into())),
+                index: None,
+            }),
+            Rule::Whitespace(Whitespace {
+                debug_id: 21007,
+                optional: true,
+                comments: Some(comments[0].clone()),
+            }),
+            Rule::Token(Token {
+                debug_id: 21008,
+                text: Arc::new(")".into()),
+                inverted: false,
+                case_insensitive: false,
+                property: None,
+            }),
+        ],
+    })));
+
+    /*
+    22 "rule" {
       @"whitespace""whitespace"
       @"until_any_or_whitespace""until_any_or_whitespace"
       @"until_any""until_any"
       @"lines""lines"
+      @"indent""indent"
       @"repeat""repeat"
       @"number""number"
       @"text""text"
+      @"char_class""char_class"
       @"reference""reference"
       @"sequence""sequence"
       @"select""select"
       @"separated_by""separated_by"
+      @"lookahead""lookahead"
       @"token""token"
       @"optional""optional"
     }
     */
-    let rule_rule = Rule::Select(Select {
-        debug_id: 18000,
+    rules.push((Arc::new("rule".into()), Rule::Select(Select {
+        debug_id: 22017,
         args: vec![
             Rule::Node(Node {
-                debug_id: 18009,
-                name: Rc::new("whitespace".into()),
-                property: Some(Rc::new("whitespace".into())),
-                index: Cell::new(None),
+                debug_id: 22001,
+                name: Arc::new("whitespace".into()),
+                property: Some(Arc::new("whitespace".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18009,
-                name: Rc::new("until_any_or_whitespace".into()),
-                property: Some(Rc::new("until_any_or_whitespace".into())),
-                index: Cell::new(None),
+                debug_id: 22002,
+                name: Arc::new("until_any_or_whitespace".into()),
+                property: Some(Arc::new("until_any_or_whitespace".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18010,
-                name: Rc::new("until_any".into()),
-                property: Some(Rc::new("until_any".into())),
-                index: Cell::new(None),
+                debug_id: 22003,
+                name: Arc::new("until_any".into()),
+                property: Some(Arc::new("until_any".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18012,
-                name: Rc::new("lines".into()),
-                property: Some(Rc::new("lines".into())),
-                index: Cell::new(None),
+                debug_id: 22004,
+                name: Arc::new("lines".into()),
+                property: Some(Arc::new("lines".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18011,
-                name: Rc::new("repeat".into()),
-                property: Some(Rc::new("repeat".into())),
-                index: Cell::new(None),
+                debug_id: 22005,
+                name: Arc::new("indent".into()),
+                property: Some(Arc::new("indent".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18001,
-                name: Rc::new("number".into()),
-                property: Some(Rc::new("number".into())),
-                index: Cell::new(None),
+                debug_id: 22006,
+                name: Arc::new("repeat".into()),
+                property: Some(Arc::new("repeat".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18002,
-                name: Rc::new("text".into()),
-                property: Some(Rc::new("text".into())),
-                index: Cell::new(None),
+                debug_id: 22007,
+                name: Arc::new("number".into()),
+                property: Some(Arc::new("number".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18003,
-                name: Rc::new("reference".into()),
-                property: Some(Rc::new("reference".into())),
-                index: Cell::new(None),
+                debug_id: 22008,
+                name: Arc::new("text".into()),
+                property: Some(Arc::new("text".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18004,
-                name: Rc::new("sequence".into()),
-                property: Some(Rc::new("sequence".into())),
-                index: Cell::new(None),
+                debug_id: 22009,
+                name: Arc::new("char_class".into()),
+                property: Some(Arc::new("char_class".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18005,
-                name: Rc::new("select".into()),
-                property: Some(Rc::new("select".into())),
-                index: Cell::new(None),
+                debug_id: 22010,
+                name: Arc::new("reference".into()),
+                property: Some(Arc::new("reference".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18006,
-                name: Rc::new("separated_by".into()),
-                property: Some(Rc::new("separated_by".into())),
-                index: Cell::new(None),
+                debug_id: 22011,
+                name: Arc::new("sequence".into()),
+                property: Some(Arc::new("sequence".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18007,
-                name: Rc::new("token".into()),
-                property: Some(Rc::new("token".into())),
-                index: Cell::new(None),
+                debug_id: 22012,
+                name: Arc::new("select".into()),
+                property: Some(Arc::new("select".into())),
+                index: None,
             }),
             Rule::Node(Node {
-                debug_id: 18008,
-                name: Rc::new("optional".into()),
-                property: Some(Rc::new("optional".into())),
-                index: Cell::new(None),
+                debug_id: 22013,
+                name: Arc::new("separated_by".into()),
+                property: Some(Arc::new("separated_by".into())),
+                index: None,
+            }),
+            Rule::Node(Node {
+                debug_id: 22014,
+                name: Arc::new("lookahead".into()),
+                property: Some(Arc::new("lookahead".into())),
+                index: None,
+            }),
+            Rule::Node(Node {
+                debug_id: 22015,
+                name: Arc::new("token".into()),
+                property: Some(Arc::new("token".into())),
+                index: None,
+            }),
+            Rule::Node(Node {
+                debug_id: 22016,
+                name: Arc::new("optional".into()),
+                property: Some(Arc::new("optional".into())),
+                index: None,
+            }),
+        ],
+    })));
+
+    /*
+    23 "comments" [
+      "comments"
+      w!
+      s!.(w!) {{t!"line" ["[" w? t!"open" w! t!"close" w? "]"] "nested""nested"}}
+    ]
+    */
+    rules.push((Arc::new("comments".into()), Rule::Sequence(Sequence {
+        debug_id: 23016,
+        args: vec![
+            Rule::Token(Token {
+                debug_id: 23001,
+                text: Arc::new("comments".into()),
+                inverted: false,
+                case_insensitive: false,
+                property: None,
+            }),
+            Rule::Whitespace(Whitespace {
+                debug_id: 23002,
+                optional: false,
+                comments: Some(comments[0].clone()),
             }),
-        ]
-    });
+            Rule::SeparatedBy(Box::new(SeparatedBy {
+                debug_id: 23015,
+                optional: false,
+                allow_trail: true,
+                count: None,
+                by: Rule::Whitespace(Whitespace {
+                    debug_id: 23003,
+                    optional: false,
+                    comments: Some(comments[0].clone()),
+                }),
+                rule: Rule::Select(Select {
+                    debug_id: 23014,
+                    args: vec![
+                        Rule::Text(Text {
+                            debug_id: 23004,
+                            allow_empty: false,
+                            property: Some(Arc::new("line".into())),
+                        }),
+                        Rule::Sequence(Sequence {
+                            debug_id: 23012,
+                            args: vec![
+                                Rule::Token(Token {
+                                    debug_id: 23005,
+                                    text: Arc::new("[".into()),
+                                    inverted: false,
+                                    case_insensitive: false,
+                                    property: None,
+                                }),
+                                Rule::Whitespace(Whitespace {
+                                    debug_id: 23006,
+                                    optional: true,
+                                    comments: Some(comments[0].clone()),
+                                }),
+                                Rule::Text(Text {
+                                    debug_id: 23007,
+                                    allow_empty: false,
+                                    property: Some(Arc::new("open".into())),
+                                }),
+                                Rule::Whitespace(Whitespace {
+                                    debug_id: 23008,
+                                    optional: false,
+                                    comments: Some(comments[0].clone()),
+                                }),
+                                Rule::Text(Text {
+                                    debug_id: 23009,
+                                    allow_empty: false,
+                                    property: Some(Arc::new("close".into())),
+                                }),
+                                Rule::Whitespace(Whitespace {
+                                    debug_id: 23010,
+                                    optional: true,
+                                    comments: Some(comments[0].clone()),
+                                }),
+                                Rule::Token(Token {
+                                    debug_id: 23011,
+                                    text: Arc::new("]".into()),
+                                    inverted: false,
+                                    case_insensitive: false,
+                                    property: None,
+                                }),
+                            ],
+                        }),
+                        Rule::Token(Token {
+                            debug_id: 23013,
+                            text: Arc::new("nested".into()),
+                            inverted: false,
+                            case_insensitive: false,
+                            property: Some(Arc::new("nested".into())),
+                        }),
+                    ],
+                }),
+            })),
+        ],
+    })));
 
-    // 19 "document" [l(@"string""string") l(@"node""node") w?]
-    let document_rule = Rule::Sequence(Sequence {
-        debug_id: 19000,
+    /*
+    24 "document" [
+      w?
+      ?@"comments""comments"
+      l(@"string""string")
+      l(@"node""node")
+      w?
+    ]
+    */
+    rules.push((Arc::new("document".into()), Rule::Sequence(Sequence {
+        debug_id: 24009,
         args: vec![
+            Rule::Whitespace(Whitespace {
+                debug_id: 24001,
+                optional: true,
+                comments: Some(comments[0].clone()),
+            }),
+            Rule::Optional(Box::new(Optional {
+                debug_id: 24003,
+                rule: Rule::Node(Node {
+                    debug_id: 24002,
+                    name: Arc::new("comments".into()),
+                    property: Some(Arc::new("comments".into())),
+                    index: None,
+                }),
+            })),
             Rule::Lines(Box::new(Lines {
-                debug_id: 19001,
+                debug_id: 24005,
                 rule: Rule::Node(Node {
-                    debug_id: 19002,
-                    name: Rc::new("string".into()),
-                    property: Some(Rc::new("string".into())),
-                    index: Cell::new(None),
-                })
+                    debug_id: 24004,
+                    name: Arc::new("string".into()),
+                    property: Some(Arc::new("string".into())),
+                    index: None,
+                }),
+                comments: Some(comments[0].clone()),
             })),
             Rule::Lines(Box::new(Lines {
-                debug_id: 19002,
+                debug_id: 24007,
                 rule: Rule::Node(Node {
-                    debug_id: 19003,
-                    name: Rc::new("node".into()),
-                    property: Some(Rc::new("node".into())),
-                    index: Cell::new(None),
-                })
+                    debug_id: 24006,
+                    name: Arc::new("node".into()),
+                    property: Some(Arc::new("node".into())),
+                    index: None,
+                }),
+                comments: Some(comments[0].clone()),
             })),
             Rule::Whitespace(Whitespace {
-                debug_id: 19004,
+                debug_id: 24008,
                 optional: true,
-            })
-        ]
-    });
-
-    let rules = vec![
-        (Rc::new("string".into()), string_rule),
-        (Rc::new("node".into()), node_rule),
-        (Rc::new("set".into()), set_rule),
-        (Rc::new("opt".into()), opt_rule),
-        (Rc::new("number".into()), number_rule),
-        (Rc::new("text".into()), text_rule),
-        (Rc::new("reference".into()), reference_rule),
-        (Rc::new("sequence".into()), sequence_rule),
-        (Rc::new("select".into()), select_rule),
-        (Rc::new("separated_by".into()), separated_by_rule),
-        (Rc::new("token".into()), token_rule),
-        (Rc::new("optional".into()), optional_rule),
-        (Rc::new("whitespace".into()), whitespace_rule),
-        (Rc::new("until_any_or_whitespace".into()), until_any_or_whitespace_rule),
-        (Rc::new("until_any".into()), until_any_rule),
-        (Rc::new("repeat".into()), repeat_rule),
-        (Rc::new("lines".into()), lines_rule),
-        (Rc::new("rule".into()), rule_rule),
-        (Rc::new("document".into()), document_rule),
-    ];
-    update_refs(&rules);
+                comments: Some(comments[0].clone()),
+            }),
+        ],
+    })));
+
+    update_refs(&mut rules);
     rules
 }
+
+/// Parses text with the last rule.
+pub fn parse(text: &str) -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
+    parse_with_tokenizer(&mut Tokenizer::new(), text)
+}
+
+/// Parses text with the last rule, using a tokenizer to store the meta data.
+///
+/// This is used to set limits on the nesting of nodes
+/// and the number of rules parsed.
+pub fn parse_with_tokenizer(
+    tokenizer: &mut Tokenizer,
+    text: &str
+) -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
+    let n = names();
+    parse_with(tokenizer, text, |t, s, c, o| rule_23(t, s, c, o, &n))
+}
+
+/// Returns the strings used by the parse functions.
+fn names() -> Vec<Arc<String>> {
+    vec![
+        Arc::new("name".into()),
+        Arc::new(":".into()),
+        Arc::new("text".into()),
+        Arc::new("id".into()),
+        Arc::new("rule".into()),
+        Arc::new("value".into()),
+        Arc::new("ref".into()),
+        Arc::new("?".into()),
+        Arc::new("optional".into()),
+        Arc::new("!".into()),
+        Arc::new("$".into()),
+        Arc::new("_".into()),
+        Arc::new("underscore".into()),
+        Arc::new("property".into()),
+        Arc::new("\"".into()),
+        Arc::new("i".into()),
+        Arc::new("i64".into()),
+        Arc::new("u".into()),
+        Arc::new("u64".into()),
+        Arc::new("x".into()),
+        Arc::new("radix".into()),
+        Arc::new("s".into()),
+        Arc::new("sign".into()),
+        Arc::new("n".into()),
+        Arc::new("special".into()),
+        Arc::new("t".into()),
+        Arc::new("allow_empty".into()),
+        Arc::new("@".into()),
+        Arc::new("[".into()),
+        Arc::new("]".into()),
+        Arc::new("{".into()),
+        Arc::new("}".into()),
+        Arc::new("(".into()),
+        Arc::new("by".into()),
+        Arc::new(")".into()),
+        Arc::new("count".into()),
+        Arc::new(".".into()),
+        Arc::new("allow_trail".into()),
+        Arc::new("case_insensitive".into()),
+        Arc::new("inverted".into()),
+        Arc::new("w".into()),
+        Arc::new("..".into()),
+        Arc::new("any_characters".into()),
+        Arc::new("...".into()),
+        Arc::new("r".into()),
+        Arc::new("~".into()),
+        Arc::new("sync".into()),
+        Arc::new("l(".into()),
+        Arc::new("min".into()),
+        Arc::new(",".into()),
+        Arc::new("to".into()),
+        Arc::new("max".into()),
+        Arc::new("c".into()),
+        Arc::new("class".into()),
+        Arc::new("negated".into()),
+        Arc::new("negate".into()),
+        Arc::new("&".into()),
+        Arc::new("tabs".into()),
+        Arc::new("whitespace".into()),
+        Arc::new("until_any_or_whitespace".into()),
+        Arc::new("until_any".into()),
+        Arc::new("lines".into()),
+        Arc::new("indent".into()),
+        Arc::new("repeat".into()),
+        Arc::new("number".into()),
+        Arc::new("char_class".into()),
+        Arc::new("reference".into()),
+        Arc::new("sequence".into()),
+        Arc::new("select".into()),
+        Arc::new("separated_by".into()),
+        Arc::new("lookahead".into()),
+        Arc::new("token".into()),
+        Arc::new("comments".into()),
+        Arc::new("line".into()),
+        Arc::new("nested".into()),
+        Arc::new("open".into()),
+        Arc::new("close".into()),
+        Arc::new("string".into()),
+        Arc::new("node".into()),
+    ]
+}
+
+/// Parses "string".
+fn rule_0(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(1005, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match until_any_or_whitespace("[]{}():.!?\"", false, Some(&n[0]), 1001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[1], false, false, None, 1002, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 1003, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match text(true, Some(&n[2]), 1004, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses "node".
+fn rule_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(2006, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match number(NumberKind::F64, false, false, false, false, Some(&n[3]), 2001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(false, &["//"], &[], false, 2002, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match text(false, Some(&n[0]), 2003, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(false, &["//"], &[], false, 2004, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(21, 2005, Some(&n[4]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses "set".
+fn rule_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(3003, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match text(false, Some(&n[5]), 3001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match until_any_or_whitespace("[]{}():.!?\"", false, Some(&n[6]), 3002, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses "opt".
+fn rule_3(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(4003, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match token(&n[7], false, false, Some(&n[8]), 4001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match token(&n[9], true, false, Some(&n[8]), 4002, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses "number".
+fn rule_4(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(5017, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[10], false, false, None, 5001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_4_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_4_2(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_4_3(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 5003 of `rule_4`.
+fn rule_4_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(5003, offset) { return Err(err); }
+    match token(&n[11], false, false, Some(&n[12]), 5002, t, &state, chars, offset) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses optional 5014 of `rule_4`.
+fn rule_4_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(5014, offset) { return Err(err); }
+    match rule_4_4(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses optional 5016 of `rule_4`.
+fn rule_4_3(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(5016, offset) { return Err(err); }
+    match node(2, 5015, Some(&n[13]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses sequence 5013 of `rule_4`.
+fn rule_4_4(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(5013, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match rule_4_5(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_4_6(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses repeat 5010 of `rule_4`.
+fn rule_4_5(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(5010, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    let mut first = true;
+    loop {
+        let item_offset = offset;
+        state = match rule_4_7(t, &state, chars, offset, n) {
+            Ok((range, state, err)) => {
+                update(range, err, &mut chars, &mut offset, &mut opt_error);
+                state
+            }
+            Err(err) => {
+                if first { return Err(ret_err(err, opt_error)); }
+                err_update(Some(err), &mut opt_error);
+                break;
+            }
+        };
+        if offset == item_offset {
+            return Err((Range::empty(offset), ParseError::InfiniteLoop(5010)));
+        }
+        first = false;
+    }
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses lookahead 5012 of `rule_4`.
+fn rule_4_6(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(5012, offset) { return Err(err); }
+    match token(&n[14], false, false, None, 5011, t, &state, chars, offset) {
+        Ok(_) => Ok((Range::empty(offset), state.clone(), None)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Parses select 5009 of `rule_4`.
+fn rule_4_7(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(5009, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match token(&n[15], false, false, Some(&n[16]), 5004, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match token(&n[17], false, false, Some(&n[18]), 5005, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match token(&n[19], false, false, Some(&n[20]), 5006, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match token(&n[21], false, false, Some(&n[22]), 5007, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match token(&n[23], false, false, Some(&n[24]), 5008, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses "text".
+fn rule_5(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(6007, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[25], false, false, None, 6001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_5_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_5_2(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses select 6004 of `rule_5`.
+fn rule_5_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(6004, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match token(&n[7], false, false, Some(&n[26]), 6002, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match token(&n[9], true, false, Some(&n[26]), 6003, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses optional 6006 of `rule_5`.
+fn rule_5_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(6006, offset) { return Err(err); }
+    match node(2, 6005, Some(&n[13]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses "reference".
+fn rule_6(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(7005, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[27], false, false, None, 7001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match text(false, Some(&n[0]), 7002, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_6_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 7004 of `rule_6`.
+fn rule_6_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(7004, offset) { return Err(err); }
+    match node(2, 7003, Some(&n[13]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses "sequence".
+fn rule_7(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(8007, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[28], false, false, None, 8001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 8002, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_7_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[29], false, false, None, 8006, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses separated by 8005 of `rule_7`.
+fn rule_7_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(8005, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    let mut first = true;
+    loop {
+        let item_offset = offset;
+        state = match node(21, 8004, Some(&n[4]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+            Ok((range, state, err)) => {
+                update(range, err, &mut chars, &mut offset, &mut opt_error);
+                state
+            }
+            Err(err) => {
+                if first { return Err(ret_err(err, opt_error)); }
+                err_update(Some(err), &mut opt_error);
+                break;
+            }
+        };
+        state = match whitespace(false, &["//"], &[], false, 8003, t, chars, offset).map(|range| (range, state.clone(), None)) {
+            Ok((range, state, err)) => {
+                update(range, err, &mut chars, &mut offset, &mut opt_error);
+                state
+            }
+            Err(err) => {
+                err_update(Some(err), &mut opt_error);
+                break;
+            }
+        };
+        if offset == item_offset {
+            return Err((Range::empty(offset), ParseError::InfiniteLoop(8005)));
+        }
+        first = false;
+    }
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses "select".
+fn rule_8(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(9007, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[30], false, false, None, 9001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 9002, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_8_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[31], false, false, None, 9006, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses separated by 9005 of `rule_8`.
+fn rule_8_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(9005, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    let mut first = true;
+    loop {
+        let item_offset = offset;
+        state = match node(21, 9004, Some(&n[4]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+            Ok((range, state, err)) => {
+                update(range, err, &mut chars, &mut offset, &mut opt_error);
+                state
+            }
+            Err(err) => {
+                if first { return Err(ret_err(err, opt_error)); }
+                err_update(Some(err), &mut opt_error);
+                break;
+            }
+        };
+        state = match whitespace(false, &["//"], &[], false, 9003, t, chars, offset).map(|range| (range, state.clone(), None)) {
+            Ok((range, state, err)) => {
+                update(range, err, &mut chars, &mut offset, &mut opt_error);
+                state
+            }
+            Err(err) => {
+                err_update(Some(err), &mut opt_error);
+                break;
+            }
+        };
+        if offset == item_offset {
+            return Err((Range::empty(offset), ParseError::InfiniteLoop(9005)));
+        }
+        first = false;
+    }
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses "separated_by".
+fn rule_9(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(10018, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[21], false, false, None, 10001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_9_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_9_2(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[32], false, false, None, 10007, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 10008, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(21, 10009, Some(&n[33]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 10010, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[34], false, false, None, 10011, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 10012, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[30], false, false, None, 10013, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 10014, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(21, 10015, Some(&n[4]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 10016, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[31], false, false, None, 10017, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses select 10004 of `rule_9`.
+fn rule_9_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(10004, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match node(17, 10002, Some(&n[35]), t, &state, chars, offset, &mut |t, s, c, o| rule_17(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(3, 10003, None, t, &state, chars, offset, &mut |t, s, c, o| rule_3(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses optional 10006 of `rule_9`.
+fn rule_9_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(10006, offset) { return Err(err); }
+    match token(&n[36], false, false, Some(&n[37]), 10005, t, &state, chars, offset) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses "token".
+fn rule_10(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(11012, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match rule_10_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(2, 11006, Some(&n[2]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_10_2(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 11005 of `rule_10`.
+fn rule_10_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(11005, offset) { return Err(err); }
+    match rule_10_3(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses optional 11011 of `rule_10`.
+fn rule_10_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(11011, offset) { return Err(err); }
+    match rule_10_4(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses sequence 11004 of `rule_10`.
+fn rule_10_3(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(11004, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[15], false, false, Some(&n[38]), 11001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_10_5(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses sequence 11010 of `rule_10`.
+fn rule_10_4(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(11010, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match rule_10_6(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(2, 11009, Some(&n[13]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses lookahead 11003 of `rule_10`.
+fn rule_10_5(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(11003, offset) { return Err(err); }
+    match token(&n[14], false, false, None, 11002, t, &state, chars, offset) {
+        Ok(_) => Ok((Range::empty(offset), state.clone(), None)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Parses optional 11008 of `rule_10`.
+fn rule_10_6(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(11008, offset) { return Err(err); }
+    match token(&n[9], false, false, Some(&n[39]), 11007, t, &state, chars, offset) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses "optional".
+fn rule_11(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(12003, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[7], false, false, None, 12001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(21, 12002, Some(&n[4]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses "whitespace".
+fn rule_12(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(13003, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[40], false, false, None, 13001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(3, 13002, None, t, &state, chars, offset, &mut |t, s, c, o| rule_3(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses "until_any_or_whitespace".
+fn rule_13(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(14006, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[41], false, false, None, 14001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(2, 14002, Some(&n[42]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(3, 14003, None, t, &state, chars, offset, &mut |t, s, c, o| rule_3(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_13_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 14005 of `rule_13`.
+fn rule_13_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(14005, offset) { return Err(err); }
+    match node(2, 14004, Some(&n[13]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses "until_any".
+fn rule_14(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(15006, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[43], false, false, None, 15001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(2, 15002, Some(&n[42]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(3, 15003, None, t, &state, chars, offset, &mut |t, s, c, o| rule_3(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_14_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 15005 of `rule_14`.
+fn rule_14_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(15005, offset) { return Err(err); }
+    match node(2, 15004, Some(&n[13]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses "repeat".
+fn rule_15(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(16012, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[44], false, false, None, 16001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_15_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[32], false, false, None, 16005, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(21, 16006, Some(&n[4]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[34], false, false, None, 16007, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_15_2(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses select 16004 of `rule_15`.
+fn rule_15_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(16004, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match node(17, 16002, Some(&n[35]), t, &state, chars, offset, &mut |t, s, c, o| rule_17(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(3, 16003, None, t, &state, chars, offset, &mut |t, s, c, o| rule_3(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses optional 16011 of `rule_15`.
+fn rule_15_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(16011, offset) { return Err(err); }
+    match rule_15_3(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses sequence 16010 of `rule_15`.
+fn rule_15_3(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(16010, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[45], false, false, None, 16008, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(2, 16009, Some(&n[46]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses "lines".
+fn rule_16(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(17006, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[47], false, false, None, 17001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 17002, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(21, 17003, Some(&n[4]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 17004, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[34], false, false, None, 17005, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses "count".
+fn rule_17(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(18009, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[30], false, false, None, 18001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match number(NumberKind::F64, false, false, false, false, Some(&n[48]), 18002, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_17_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[31], false, false, None, 18008, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 18007 of `rule_17`.
+fn rule_17_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(18007, offset) { return Err(err); }
+    match rule_17_2(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses sequence 18006 of `rule_17`.
+fn rule_17_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(18006, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[49], false, false, Some(&n[50]), 18003, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_17_3(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 18005 of `rule_17`.
+fn rule_17_3(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(18005, offset) { return Err(err); }
+    match number(NumberKind::F64, false, false, false, false, Some(&n[51]), 18004, t, &state, chars, offset) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses "char_class".
+fn rule_18(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(19011, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[52], false, false, None, 19001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_18_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match text(true, Some(&n[53]), 19004, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_18_2(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_18_3(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 19003 of `rule_18`.
+fn rule_18_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(19003, offset) { return Err(err); }
+    match token(&n[9], false, false, Some(&n[54]), 19002, t, &state, chars, offset) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses optional 19008 of `rule_18`.
+fn rule_18_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(19008, offset) { return Err(err); }
+    match rule_18_4(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses optional 19010 of `rule_18`.
+fn rule_18_3(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(19010, offset) { return Err(err); }
+    match node(2, 19009, Some(&n[13]), t, &state, chars, offset, &mut |t, s, c, o| rule_2(t, s, c, o, n)) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses select 19007 of `rule_18`.
+fn rule_18_4(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(19007, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match node(17, 19005, Some(&n[35]), t, &state, chars, offset, &mut |t, s, c, o| rule_17(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(3, 19006, None, t, &state, chars, offset, &mut |t, s, c, o| rule_3(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses "lookahead".
+fn rule_19(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(20005, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match rule_19_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(21, 20004, Some(&n[4]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses select 20003 of `rule_19`.
+fn rule_19_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(20003, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match token(&n[9], false, false, Some(&n[55]), 20001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match token(&n[56], false, false, None, 20002, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses "indent".
+fn rule_20(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(21009, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[15], false, false, None, 21001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_20_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[32], false, false, None, 21004, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 21005, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match node(21, 21006, Some(&n[4]), t, &state, chars, offset, &mut |t, s, c, o| rule_21(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 21007, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[34], false, false, None, 21008, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 21003 of `rule_20`.
+fn rule_20_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(21003, offset) { return Err(err); }
+    match token(&n[25], false, false, Some(&n[57]), 21002, t, &state, chars, offset) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses "rule".
+fn rule_21(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(22017, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match node(12, 22001, Some(&n[58]), t, &state, chars, offset, &mut |t, s, c, o| rule_12(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(13, 22002, Some(&n[59]), t, &state, chars, offset, &mut |t, s, c, o| rule_13(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(14, 22003, Some(&n[60]), t, &state, chars, offset, &mut |t, s, c, o| rule_14(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(16, 22004, Some(&n[61]), t, &state, chars, offset, &mut |t, s, c, o| rule_16(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(20, 22005, Some(&n[62]), t, &state, chars, offset, &mut |t, s, c, o| rule_20(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(15, 22006, Some(&n[63]), t, &state, chars, offset, &mut |t, s, c, o| rule_15(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(4, 22007, Some(&n[64]), t, &state, chars, offset, &mut |t, s, c, o| rule_4(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(5, 22008, Some(&n[2]), t, &state, chars, offset, &mut |t, s, c, o| rule_5(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(18, 22009, Some(&n[65]), t, &state, chars, offset, &mut |t, s, c, o| rule_18(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(6, 22010, Some(&n[66]), t, &state, chars, offset, &mut |t, s, c, o| rule_6(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(7, 22011, Some(&n[67]), t, &state, chars, offset, &mut |t, s, c, o| rule_7(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(8, 22012, Some(&n[68]), t, &state, chars, offset, &mut |t, s, c, o| rule_8(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(9, 22013, Some(&n[69]), t, &state, chars, offset, &mut |t, s, c, o| rule_9(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(19, 22014, Some(&n[70]), t, &state, chars, offset, &mut |t, s, c, o| rule_19(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(10, 22015, Some(&n[71]), t, &state, chars, offset, &mut |t, s, c, o| rule_10(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match node(11, 22016, Some(&n[8]), t, &state, chars, offset, &mut |t, s, c, o| rule_11(t, s, c, o, n)) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses "comments".
+fn rule_22(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(23016, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[72], false, false, None, 23001, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(false, &["//"], &[], false, 23002, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_22_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses separated by 23015 of `rule_22`.
+fn rule_22_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(23015, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    let mut first = true;
+    loop {
+        let item_offset = offset;
+        state = match rule_22_2(t, &state, chars, offset, n) {
+            Ok((range, state, err)) => {
+                update(range, err, &mut chars, &mut offset, &mut opt_error);
+                state
+            }
+            Err(err) => {
+                if first { return Err(ret_err(err, opt_error)); }
+                err_update(Some(err), &mut opt_error);
+                break;
+            }
+        };
+        state = match whitespace(false, &["//"], &[], false, 23003, t, chars, offset).map(|range| (range, state.clone(), None)) {
+            Ok((range, state, err)) => {
+                update(range, err, &mut chars, &mut offset, &mut opt_error);
+                state
+            }
+            Err(err) => {
+                err_update(Some(err), &mut opt_error);
+                break;
+            }
+        };
+        if offset == item_offset {
+            return Err((Range::empty(offset), ParseError::InfiniteLoop(23015)));
+        }
+        first = false;
+    }
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses select 23014 of `rule_22`.
+fn rule_22_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(23014, offset) { return Err(err); }
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    match text(false, Some(&n[73]), 23004, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match rule_22_3(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    match token(&n[74], false, false, Some(&n[74]), 23013, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            err_update(err, &mut opt_error);
+            return Ok((Range::new(offset, range.next_offset() - offset), state, opt_error));
+        }
+        Err(err) => { err_update(Some(err), &mut opt_error); }
+    }
+    Err(opt_error.unwrap())
+}
+
+/// Parses sequence 23012 of `rule_22`.
+fn rule_22_3(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(23012, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match token(&n[28], false, false, None, 23005, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 23006, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match text(false, Some(&n[75]), 23007, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(false, &["//"], &[], false, 23008, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match text(false, Some(&n[76]), 23009, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 23010, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match token(&n[29], false, false, None, 23011, t, &state, chars, offset) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses "document".
+fn rule_23(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(24009, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    state = match whitespace(true, &["//"], &[], false, 24001, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_23_1(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_23_2(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match rule_23_3(t, &state, chars, offset, n) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    state = match whitespace(true, &["//"], &[], false, 24008, t, chars, offset).map(|range| (range, state.clone(), None)) {
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+        Err(err) => {
+            return Err(ret_err(err, opt_error));
+        }
+    };
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses optional 24003 of `rule_23`.
+fn rule_23_1(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(24003, offset) { return Err(err); }
+    match node(22, 24002, Some(&n[72]), t, &state, chars, offset, &mut |t, s, c, o| rule_22(t, s, c, o, n)) {
+        Ok((range, state, err)) => Ok((Range::new(offset, range.next_offset() - offset), state, err)),
+        Err(err) => Ok((Range::new(offset, 0), state.clone(), Some(err))),
+    }
+}
+
+/// Parses lines 24005 of `rule_23`.
+fn rule_23_2(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(24005, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    let mut new_lines = true;
+    loop {
+        let len = match line_comments_len(&["//"], &[], false, chars) {
+            Ok(len) => len,
+            Err(i) => return Err(ret_err((Range::new(offset + i, chars.len() - i), ParseError::ExpectedCommentEnd(24005)), opt_error)),
+        };
+        if len == chars.len() {
+            offset += len;
+            break;
+        } else if chars.as_bytes()[len] == b'\n' {
+            chars = &chars[len + 1..];
+            offset += len + 1;
+            new_lines = true;
+        } else if new_lines {
+            chars = &chars[len..];
+            offset += len;
+            state = match node(0, 24004, Some(&n[77]), t, &state, chars, offset, &mut |t, s, c, o| rule_0(t, s, c, o, n)) {
+                Err(err) => {
+                    err_update(Some(err), &mut opt_error);
+                    break;
+                }
+                Ok((range, state, err)) => {
+                    new_lines = ends_with_new_line(&chars[..range.next_offset() - offset]);
+                    update(range, err, &mut chars, &mut offset, &mut opt_error);
+                    state
+                }
+            };
+        } else {
+            return Err(ret_err((Range::empty(offset), ParseError::ExpectedNewLine(24005)), opt_error));
+        }
+    }
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
+
+/// Parses lines 24007 of `rule_23`.
+fn rule_23_3(
+    t: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    n: &[Arc<String>]
+) -> ParseResult<TokenizerState> {
+    if let Err(err) = t.step(24007, start_offset) { return Err(err); }
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error: Option<(Range, ParseError)> = None;
+    let mut new_lines = true;
+    loop {
+        let len = match line_comments_len(&["//"], &[], false, chars) {
+            Ok(len) => len,
+            Err(i) => return Err(ret_err((Range::new(offset + i, chars.len() - i), ParseError::ExpectedCommentEnd(24007)), opt_error)),
+        };
+        if len == chars.len() {
+            offset += len;
+            break;
+        } else if chars.as_bytes()[len] == b'\n' {
+            chars = &chars[len + 1..];
+            offset += len + 1;
+            new_lines = true;
+        } else if new_lines {
+            chars = &chars[len..];
+            offset += len;
+            state = match node(1, 24006, Some(&n[78]), t, &state, chars, offset, &mut |t, s, c, o| rule_1(t, s, c, o, n)) {
+                Err(err) => {
+                    err_update(Some(err), &mut opt_error);
+                    break;
+                }
+                Ok((range, state, err)) => {
+                    new_lines = ends_with_new_line(&chars[..range.next_offset() - offset]);
+                    update(range, err, &mut chars, &mut offset, &mut opt_error);
+                    state
+                }
+            };
+        } else {
+            return Err(ret_err((Range::empty(offset), ParseError::ExpectedNewLine(24007)), opt_error));
+        }
+    }
+    Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+}
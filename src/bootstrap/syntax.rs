@@ -0,0 +1,538 @@
+use std::sync::Arc;
+
+use compose::write_string;
+use { Comments, NumberKind, Rule };
+
+/// Writes rules as meta language text.
+///
+/// Strings that are shared between rules are written as string definitions
+/// at the top, such that `bootstrap::convert` gives back the same rules.
+pub fn syntax(rules: &[(Arc<String>, Rule)]) -> String {
+    let mut shared: Vec<(Arc<String>, usize)> = vec![];
+    for r in rules {
+        strings(&r.1, &mut |s| {
+            for &mut (ref val, ref mut count) in &mut shared {
+                if Arc::ptr_eq(val, s) {
+                    *count += 1;
+                    return;
+                }
+            }
+            shared.push((s.clone(), 1));
+        });
+    }
+
+    let mut names: Vec<(Arc<String>, String)> = vec![];
+    let mut out = String::new();
+    if let Some(c) = rules.iter().filter_map(|r| comments(&r.1)).next() {
+        write_comments(c, &mut out);
+        out.push('\n');
+    }
+    for (val, count) in shared {
+        if count < 2 { continue; }
+        let name = string_name(&val, &names);
+        out.push_str(&name);
+        out.push_str(": ");
+        write_string(&val, &mut out);
+        out.push('\n');
+        names.push((val, name));
+    }
+    for (i, r) in rules.iter().enumerate() {
+        write_node(i + 1, &r.0, &r.1, &names, &mut out);
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes a named rule with its id.
+/// Puts each sub rule on a separate line if the rule is too long.
+pub fn write_node(
+    id: usize,
+    name: &str,
+    rule: &Rule,
+    names: &[(Arc<String>, String)],
+    out: &mut String
+) {
+    let start = out.len();
+    out.push_str(&format!("{} ", id));
+    write_string(name, out);
+    out.push(' ');
+    let line = out.len();
+    write_rule(rule, names, out);
+    if out.len() - start > MAX_LINE_LENGTH {
+        // Put each sub rule on a separate line.
+        let args = match rule {
+            &Rule::Select(ref s) => Some(("{", &s.args, "}")),
+            &Rule::Sequence(ref s) => Some(("[", &s.args, "]")),
+            _ => None
+        };
+        if let Some((open, args, close)) = args {
+            out.truncate(line);
+            out.push_str(open);
+            out.push('\n');
+            for sub_rule in args {
+                out.push_str("  ");
+                write_rule(sub_rule, names, out);
+                out.push('\n');
+            }
+            out.push_str(close);
+        }
+    }
+}
+
+/// The line length to break long rules into multiple lines.
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Picks a name for a string definition.
+fn string_name(val: &str, names: &[(Arc<String>, String)]) -> String {
+    let is_name = val.len() > 1 &&
+        val.chars().next().map(|c| c.is_alphabetic()).unwrap_or(false) &&
+        val.chars().all(|c| c.is_alphanumeric() || c == '_');
+    let taken = |name: &str| names.iter().any(|&(_, ref n)| n == name);
+    if is_name && !taken(val) {
+        return val.into();
+    }
+    let mut i = 1;
+    loop {
+        let name = format!("str{}", i);
+        if !taken(&name) { return name; }
+        i += 1;
+    }
+}
+
+/// Returns the first comments skipped by a rule.
+fn comments(rule: &Rule) -> Option<&Arc<Comments>> {
+    match rule {
+        &Rule::Whitespace(ref w) => w.comments.as_ref(),
+        &Rule::Token(_) => None,
+        &Rule::UntilAny(_) => None,
+        &Rule::UntilAnyOrWhitespace(_) => None,
+        &Rule::Text(_) => None,
+        &Rule::Number(_) => None,
+        &Rule::CharClass(_) => None,
+        &Rule::Select(ref s) => s.args.iter().filter_map(comments).next(),
+        &Rule::Sequence(ref s) => s.args.iter().filter_map(comments).next(),
+        &Rule::SeparatedBy(ref s) => comments(&s.rule).or_else(|| comments(&s.by)),
+        &Rule::Repeat(ref r) => comments(&r.rule),
+        &Rule::Lines(ref l) => l.comments.as_ref().or_else(|| comments(&l.rule)),
+        &Rule::Indent(ref i) => i.comments.as_ref().or_else(|| comments(&i.rule)),
+        &Rule::Node(_) => None,
+        &Rule::Optional(ref o) => comments(&o.rule),
+        &Rule::Lookahead(ref l) => comments(&l.rule),
+    }
+}
+
+/// Writes the comments declaration, for example `comments "//" ["/*" "*/"]`.
+fn write_comments(c: &Comments, out: &mut String) {
+    out.push_str("comments");
+    for prefix in &c.line {
+        out.push(' ');
+        write_string(prefix, out);
+    }
+    for &(ref open, ref close) in &c.block {
+        out.push_str(" [");
+        write_string(open, out);
+        out.push(' ');
+        write_string(close, out);
+        out.push(']');
+    }
+    if c.nested { out.push_str(" nested"); }
+}
+
+/// Calls function for each string that can refer to a string definition.
+fn strings<F>(rule: &Rule, f: &mut F) where F: FnMut(&Arc<String>) {
+    match rule {
+        &Rule::Whitespace(_) => {}
+        &Rule::Token(ref t) => {
+            // Text ignoring case is always written as a string.
+            if !t.case_insensitive { f(&t.text); }
+            if let Some(ref p) = t.property { f(p); }
+        }
+        &Rule::UntilAny(ref u) => {
+            f(&u.any_characters);
+            if let Some(ref p) = u.property { f(p); }
+        }
+        &Rule::UntilAnyOrWhitespace(ref u) => {
+            f(&u.any_characters);
+            if let Some(ref p) = u.property { f(p); }
+        }
+        &Rule::Text(ref t) => {
+            if let Some(ref p) = t.property { f(p); }
+        }
+        &Rule::Number(ref n) => {
+            if let Some(ref p) = n.property { f(p); }
+        }
+        &Rule::CharClass(ref c) => {
+            if let Some(ref p) = c.property { f(p); }
+        }
+        &Rule::Select(ref s) => {
+            for sub_rule in &s.args { strings(sub_rule, f); }
+        }
+        &Rule::Sequence(ref s) => {
+            for sub_rule in &s.args { strings(sub_rule, f); }
+        }
+        &Rule::SeparatedBy(ref s) => {
+            strings(&s.rule, f);
+            strings(&s.by, f);
+        }
+        &Rule::Repeat(ref r) => {
+            strings(&r.rule, f);
+            if let Some(ref s) = r.sync { f(s); }
+        }
+        &Rule::Lines(ref l) => { strings(&l.rule, f); }
+        &Rule::Indent(ref i) => { strings(&i.rule, f); }
+        &Rule::Node(ref n) => {
+            if let Some(ref p) = n.property { f(p); }
+        }
+        &Rule::Optional(ref o) => { strings(&o.rule, f); }
+        &Rule::Lookahead(ref l) => { strings(&l.rule, f); }
+    }
+}
+
+/// Writes a string definition reference or a quoted string.
+fn write_set(val: &Arc<String>, names: &[(Arc<String>, String)], out: &mut String) {
+    match names.iter().find(|&&(ref s, _)| Arc::ptr_eq(s, val)) {
+        Some(&(_, ref name)) => out.push_str(name),
+        None => write_string(val, out),
+    }
+}
+
+/// Writes whether a rule is optional.
+fn write_opt(optional: bool, out: &mut String) {
+    out.push(if optional { '?' } else { '!' });
+}
+
+/// Writes the minimum and maximum count, for example `{2,4}`.
+fn write_count(min: usize, max: Option<usize>, out: &mut String) {
+    match max {
+        Some(max) if max == min => out.push_str(&format!("{{{}}}", min)),
+        Some(max) => out.push_str(&format!("{{{},{}}}", min, max)),
+        None => out.push_str(&format!("{{{},}}", min)),
+    }
+}
+
+/// Writes the property, if any.
+fn write_property(
+    property: &Option<Arc<String>>,
+    names: &[(Arc<String>, String)],
+    out: &mut String
+) {
+    if let &Some(ref p) = property { write_set(p, names, out); }
+}
+
+/// Writes a rule.
+fn write_rule(rule: &Rule, names: &[(Arc<String>, String)], out: &mut String) {
+    match rule {
+        &Rule::Whitespace(ref w) => {
+            out.push('w');
+            write_opt(w.optional, out);
+        }
+        &Rule::Token(ref t) => {
+            if t.case_insensitive {
+                out.push('i');
+                write_string(&t.text, out);
+            } else {
+                write_set(&t.text, names, out);
+            }
+            if let Some(ref p) = t.property {
+                if t.inverted { out.push('!'); }
+                write_set(p, names, out);
+            }
+        }
+        &Rule::UntilAny(ref u) => {
+            out.push_str("...");
+            write_set(&u.any_characters, names, out);
+            write_opt(u.optional, out);
+            write_property(&u.property, names, out);
+        }
+        &Rule::UntilAnyOrWhitespace(ref u) => {
+            out.push_str("..");
+            write_set(&u.any_characters, names, out);
+            write_opt(u.optional, out);
+            write_property(&u.property, names, out);
+        }
+        &Rule::Text(ref t) => {
+            out.push('t');
+            write_opt(t.allow_empty, out);
+            write_property(&t.property, names, out);
+        }
+        &Rule::Number(ref n) => {
+            out.push('$');
+            if n.allow_underscore { out.push('_'); }
+            match n.kind {
+                NumberKind::F64 => {}
+                NumberKind::I64 => out.push('i'),
+                NumberKind::U64 => out.push('u'),
+            }
+            let start = out.len();
+            if n.allow_radix { out.push('x'); }
+            if n.require_sign { out.push('s'); }
+            if n.allow_special { out.push('n'); }
+            let flags = out.len() > start || n.kind != NumberKind::F64;
+            match n.property {
+                // Flags are only read when followed by a quoted property.
+                Some(ref p) if flags => write_string(p, out),
+                _ => write_property(&n.property, names, out),
+            }
+        }
+        &Rule::CharClass(ref c) => {
+            out.push('c');
+            if c.negated { out.push('!'); }
+            write_string(&c.class(), out);
+            match (c.min, c.max) {
+                (1, Some(1)) => {}
+                (0, None) => write_opt(true, out),
+                (1, None) => write_opt(false, out),
+                (min, max) => write_count(min, max, out),
+            }
+            write_property(&c.property, names, out);
+        }
+        &Rule::Select(ref s) => {
+            out.push('{');
+            for (i, sub_rule) in s.args.iter().enumerate() {
+                if i > 0 { out.push(' '); }
+                write_rule(sub_rule, names, out);
+            }
+            out.push('}');
+        }
+        &Rule::Sequence(ref s) => {
+            out.push('[');
+            for (i, sub_rule) in s.args.iter().enumerate() {
+                if i > 0 { out.push(' '); }
+                write_rule(sub_rule, names, out);
+            }
+            out.push(']');
+        }
+        &Rule::SeparatedBy(ref s) => {
+            out.push('s');
+            match s.count {
+                Some((min, max)) => write_count(min, max, out),
+                None => write_opt(s.optional, out),
+            }
+            if s.allow_trail { out.push('.'); }
+            out.push('(');
+            write_rule(&s.by, names, out);
+            out.push_str(") {");
+            write_rule(&s.rule, names, out);
+            out.push('}');
+        }
+        &Rule::Repeat(ref r) => {
+            out.push('r');
+            match r.count {
+                Some((min, max)) => write_count(min, max, out),
+                None => write_opt(r.optional, out),
+            }
+            out.push('(');
+            write_rule(&r.rule, names, out);
+            out.push(')');
+            if let Some(ref s) = r.sync {
+                out.push('~');
+                write_set(s, names, out);
+            }
+        }
+        &Rule::Lines(ref l) => {
+            out.push_str("l(");
+            write_rule(&l.rule, names, out);
+            out.push(')');
+        }
+        &Rule::Indent(ref i) => {
+            out.push_str(if i.tabs { "it(" } else { "i(" });
+            write_rule(&i.rule, names, out);
+            out.push(')');
+        }
+        &Rule::Node(ref n) => {
+            out.push('@');
+            write_string(&n.name, out);
+            write_property(&n.property, names, out);
+        }
+        &Rule::Optional(ref o) => {
+            out.push('?');
+            write_rule(&o.rule, names, out);
+        }
+        &Rule::Lookahead(ref l) => {
+            out.push(if l.negate { '!' } else { '&' });
+            write_rule(&l.rule, names, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::super::super::*;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn self_syntax() {
+        let mut source = String::new();
+        File::open("assets/self-syntax.txt").unwrap()
+            .read_to_string(&mut source).unwrap();
+        let rules1 = convert(&parse(&rules(), &source).unwrap(), &mut vec![])
+            .unwrap();
+        let text = syntax(&rules1);
+        let rules2 = convert(&parse(&rules(), &text).unwrap(), &mut vec![])
+            .unwrap();
+        assert_eq!(rules1, rules2);
+        assert_eq!(syntax(&rules2), text);
+        assert!(text.starts_with(r#"comments "//"
+str1: "[]{}():.!?\""
+optional: "optional"
+property: "property"
+any_characters: "any_characters"
+1 "string" [..str1!"name" ":" w? t?"text"]
+"#));
+        assert!(text.contains(r#"
+22 "rule" {
+  @"whitespace""whitespace"
+  @"until_any_or_whitespace""until_any_or_whitespace"
+"#));
+    }
+
+    #[test]
+    fn char_class() {
+        let source = r##"1 "ident" [c"a-zA-Z_" c"a-zA-Z0-9_"?]
+2 "hex" ["#" c"0-9a-fA-F"{2}"r" c"0-9a-fA-F"{2,}"g" c!"\n"{0,3}"b"]
+3 "document" s!.(",") {{@"ident""ident" @"hex""hex" c"+-"!"sign"}}
+"##;
+        let rules1 = convert(&parse(&rules(), source).unwrap(), &mut vec![])
+            .unwrap();
+        let text = syntax(&rules1);
+        assert_eq!(text, source);
+        assert!(parse(&rules1, "foo_1,-+,#00ff;x").is_ok());
+        assert!(parse(&rules1, "1foo").is_err());
+        let data = parse(&rules1, "#00ff0").unwrap();
+        assert_eq!(data[2].1, MetaData::String(Arc::new("g".into()), Arc::new("ff0".into())));
+        assert!(parse(&rules1, "#00f").is_err());
+    }
+
+    #[test]
+    fn lookahead() {
+        let source = r#"1 "keyword" ["if" !c"a-zA-Z0-9_"]
+2 "comment" ["/*" r?([!"*/" c!""]) "*/"]
+3 "document" s!.(w!) {{@"keyword""if" [&"i" ..","!"name"] @"comment""comment"}}
+"#;
+        let rules1 = convert(&parse(&rules(), source).unwrap(), &mut vec![])
+            .unwrap();
+        assert_eq!(syntax(&rules1), source);
+        let data = parse(&rules1, "if ifx /* a * b */").unwrap();
+        assert_eq!(data.len(), 5);
+        assert_eq!(data[1].1, MetaData::EndNode(Arc::new("if".into())));
+        assert_eq!(data[2].1, MetaData::String(Arc::new("name".into()), Arc::new("ifx".into())));
+        assert!(parse(&rules1, "x").is_err());
+    }
+
+    #[test]
+    fn number() {
+        let source = r#"1 "int" $i"count"
+2 "offset" $_uxsn"offset"
+3 "document" s!.(",") {{$_ux"size" @"int""int" $n"val"}}
+"#;
+        let rules1 = convert(&parse(&rules(), source).unwrap(), &mut vec![])
+            .unwrap();
+        assert_eq!(syntax(&rules1), source);
+        let data = parse(&rules1, "-3,1.5,18446744073709551615,0x1_F,-inf").unwrap();
+        assert_eq!(data[1].1, MetaData::I64(Arc::new("count".into()), -3));
+        assert_eq!(data[3].1, MetaData::F64(Arc::new("val".into()), 1.5));
+        assert_eq!(data[4].1, MetaData::U64(Arc::new("size".into()), ::std::u64::MAX));
+        assert_eq!(data[5].1, MetaData::U64(Arc::new("size".into()), 31));
+        assert_eq!(data[6].1, MetaData::F64(Arc::new("val".into()), ::std::f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn number_reference() {
+        // Flags are only read when followed by a quoted property.
+        let source = r#"six: "six"
+1 "document" [$six "," $i"six" "," $six]
+"#;
+        let rules1 = convert(&parse(&rules(), source).unwrap(), &mut vec![])
+            .unwrap();
+        assert_eq!(syntax(&rules1), source);
+        let data = parse(&rules1, "1.5,2,-3").unwrap();
+        assert_eq!(data[0].1, MetaData::F64(Arc::new("six".into()), 1.5));
+        assert_eq!(data[1].1, MetaData::I64(Arc::new("six".into()), 2));
+    }
+
+    #[test]
+    fn comments() {
+        let source = r##"comments "#" ["(*" "*)"] nested
+1 "item" [t!"name" w? ":" w? $"value"]
+2 "document" l(@"item""item")
+"##;
+        let rules1 = convert(&parse(&rules(), source).unwrap(), &mut vec![])
+            .unwrap();
+        assert_eq!(syntax(&rules1), source);
+        let text = "# items\n\"a\": (* one (* 1 *) *) 1 # end\n  (* b *) \"b\" : 2";
+        let data = parse(&rules1, text).unwrap();
+        assert_eq!(data.len(), 8);
+        assert_eq!(data[6].1, MetaData::F64(Arc::new("value".into()), 2.0));
+        assert_eq!(parse(&rules1, "\"a\": (* 1"),
+            Err((Range::new(5, 4), ParseError::ExpectedCommentEnd(1004))));
+        assert_eq!(Program::new(&rules1).parse(text), Ok(data));
+    }
+
+    #[test]
+    fn indent() {
+        let source = r#"1 "block" [t!"name" ":" i(@"item""item")]
+2 "item" {@"block""block" t!"name"}
+3 "document" l(@"block""block")
+"#;
+        let rules1 = convert(&parse(&rules(), source).unwrap(), &mut vec![])
+            .unwrap();
+        assert_eq!(syntax(&rules1), source);
+        let text = "\"a\":\n  \"b\":\n    \"c\"\n\n  \"d\"\n\"e\":\n  \"f\"";
+        let data = parse(&rules1, text).unwrap();
+        assert_eq!(data.len(), 20);
+        assert_eq!(data[8].1, MetaData::EndNode(Arc::new("block".into())));
+        assert_eq!(data[13].0, Range::new(0, 26));
+        assert_eq!(data[11].1, MetaData::String(Arc::new("name".into()), Arc::new("d".into())));
+        assert_eq!(Program::new(&rules1).parse(text), Ok(data.clone()));
+        assert_eq!(compose(&rules1, &data).unwrap(),
+            "\"a\":\n    \"b\":\n        \"c\"\n    \"d\"\n\"e\":\n    \"f\"\n");
+        assert_eq!(parse(&rules1, "\"a\":\n    \"b\"\n  \"c\""),
+            Err((Range::new(13, 2), ParseError::InconsistentDedent(1004))));
+        assert_eq!(parse(&rules1, "\"a\":\n\t\"b\""),
+            Err((Range::new(5, 1), ParseError::MixedIndentation(1004))));
+    }
+
+    #[test]
+    fn count() {
+        let source = r##"1 "vec3" ["(" w? s{3}.([w? "," w?]) {$"x"} w? ")"]
+2 "rgba" ["#" r{2,4}(c"0-9a-f"{2}"c")]
+3 "document" s!.([w? ";" w?]) {{@"vec3""vec3" @"rgba""rgba"}}
+"##;
+        let rules1 = convert(&parse(&rules(), source).unwrap(), &mut vec![])
+            .unwrap();
+        assert_eq!(syntax(&rules1), source);
+        let text = "(1, 2, 3); #ff00ff; #00ff00ff";
+        let data = parse(&rules1, text).unwrap();
+        assert_eq!(data.len(), 16);
+        assert_eq!(Program::new(&rules1).parse(text), Ok(data.clone()));
+        assert_eq!(compose(&rules1, &data).unwrap(), "(1,2,3);#ff00ff;#00ff00ff");
+        // Reading stops at the maximum count.
+        assert_eq!(parse(&rules1, "(1, 2, 3, 4)"),
+            Err((Range::empty(10), ParseError::ExpectedToken(Arc::new(")".into()), 1010))));
+        assert_eq!(parse(&rules1, "#ff00ff00ff"),
+            Err((Range::empty(9), ParseError::ExpectedEnd)));
+        assert_eq!(parse(&rules1, "#ff"),
+            Err((Range::new(1, 2), ParseError::ExpectedCount(2, Some(4), 1, 2003))));
+        assert_eq!(Program::new(&rules1).parse("#ff"), parse(&rules1, "#ff"));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let source = r#"1 "select" [i"select" w! i"distinct""distinct" w! t!"column"]
+2 "document" l(@"select""select")
+"#;
+        let rules1 = convert(&parse(&rules(), source).unwrap(), &mut vec![])
+            .unwrap();
+        assert_eq!(syntax(&rules1), source);
+        let text = "SELECT Distinct \"a\"\nselect DISTINCT \"b\"";
+        let data = parse(&rules1, text).unwrap();
+        assert_eq!(data.len(), 8);
+        assert_eq!(data[1], (Range::new(7, 8),
+            MetaData::Bool(Arc::new("distinct".into()), true)));
+        assert_eq!(Program::new(&rules1).parse(text), Ok(data.clone()));
+        assert_eq!(compose(&rules1, &data).unwrap(),
+            "select distinct \"a\"\nselect distinct \"b\"\n");
+    }
+}
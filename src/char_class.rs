@@ -0,0 +1,222 @@
+use range::Range;
+use std::sync::Arc;
+
+use {
+    compose,
+    DebugId,
+    MetaData,
+    ParseError,
+    ParseResult,
+    Tokenizer,
+    TokenizerState,
+};
+
+/// Stores information about reading characters in a class,
+/// for example letters and digits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CharClass {
+    /// The ranges of characters, including both ends.
+    pub ranges: Vec<(char, char)>,
+    /// Whether to read characters outside the ranges instead.
+    pub negated: bool,
+    /// The minimum number of characters.
+    pub min: usize,
+    /// The maximum number of characters, if any.
+    pub max: Option<usize>,
+    /// The property to store read text.
+    pub property: Option<Arc<String>>,
+    /// A debug id to track down the rule generating an error.
+    pub debug_id: DebugId,
+}
+
+impl CharClass {
+    /// Reads ranges from text, for example `a-zA-Z_`.
+    /// A `-` at the start or end is read as a character.
+    pub fn ranges_from(text: &str) -> Vec<(char, char)> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut ranges = vec![];
+        let mut i = 0;
+        while i < chars.len() {
+            if i + 2 < chars.len() && chars[i + 1] == '-' {
+                ranges.push((chars[i], chars[i + 2]));
+                i += 3;
+            } else {
+                ranges.push((chars[i], chars[i]));
+                i += 1;
+            }
+        }
+        ranges
+    }
+
+    /// Returns the ranges as text, such that `ranges_from` reads them back.
+    pub fn class(&self) -> String {
+        let mut text = String::new();
+        let mut dash = false;
+        for &(a, b) in &self.ranges {
+            if a == b {
+                if a == '-' { dash = true; } else { text.push(a); }
+            } else {
+                text.push(a);
+                text.push('-');
+                text.push(b);
+            }
+        }
+        if dash { text.push('-'); }
+        text
+    }
+
+    /// Returns `true` if the character is in the class.
+    pub fn contains(&self, c: char) -> bool {
+        contains(&self.ranges, self.negated, c)
+    }
+
+    /// Parses characters in the class.
+    pub fn parse(
+        &self,
+        tokenizer: &mut Tokenizer,
+        state: &TokenizerState,
+        chars: &str,
+        offset: usize
+    ) -> ParseResult<TokenizerState> {
+        parse(&self.ranges, self.negated, self.min, self.max,
+            self.property.as_ref(), self.debug_id, tokenizer, state, chars, offset)
+    }
+
+    /// Composes the characters in the class.
+    /// Fails if the text has characters outside the class
+    /// or the wrong number of characters.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        data: &[(Range, MetaData)],
+        offset: usize
+    ) -> Result<Range, ()> {
+        match self.property {
+            Some(ref property) => {
+                let (range, val) = try!(compose::meta_string(
+                    property, data, offset));
+                let count = val.chars().count();
+                if count < self.min { return Err(()); }
+                if let Some(max) = self.max { if count > max { return Err(()); } }
+                if !val.chars().all(|c| self.contains(c)) { return Err(()); }
+                out.push_str(&val);
+                Ok(range)
+            }
+            None => {
+                // There is no data to tell what the text is.
+                if self.min > 0 { return Err(()); }
+                Ok(Range::empty(offset))
+            }
+        }
+    }
+}
+
+/// Returns `true` if the character is in the ranges, or outside if negated.
+#[inline(always)]
+fn contains(ranges: &[(char, char)], negated: bool, c: char) -> bool {
+    ranges.iter().any(|&(a, b)| a <= c && c <= b) != negated
+}
+
+/// Parses characters in ranges.
+/// This is used by generated code to avoid creating the ranges each time.
+pub fn parse(
+    ranges: &[(char, char)],
+    negated: bool,
+    min: usize,
+    max: Option<usize>,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    let mut count = 0;
+    let mut len = 0;
+    for c in chars.chars() {
+        if Some(count) == max || !contains(ranges, negated, c) { break; }
+        count += 1;
+        len += c.len_utf8();
+    }
+    let range = Range::new(offset, len);
+    if count < min {
+        return Err((Range::empty(offset + len),
+            ParseError::ExpectedCharClass(debug_id)));
+    }
+    if let Some(property) = property {
+        let text = chars[..len].to_owned();
+        Ok((range, tokenizer.data(
+            MetaData::String(property.clone(), Arc::new(text)),
+            state,
+            range
+        ), None))
+    } else {
+        Ok((range, state.clone(), None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use range::Range;
+    use std::sync::Arc;
+
+    fn ident() -> CharClass {
+        CharClass {
+            ranges: CharClass::ranges_from("a-zA-Z_"),
+            negated: false,
+            min: 1,
+            max: None,
+            property: Some(Arc::new("name".into())),
+            debug_id: 0,
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(CharClass::ranges_from("a-z_-"),
+            vec![('a', 'z'), ('_', '_'), ('-', '-')]);
+        assert_eq!(CharClass::ranges_from("-0-9"), vec![('-', '-'), ('0', '9')]);
+        let class = CharClass { ranges: CharClass::ranges_from("-a-c"), ..ident() };
+        assert_eq!(class.class(), "a-c-");
+    }
+
+    #[test]
+    fn successful() {
+        let text = "foo_Bar2";
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let res = ident().parse(&mut tokenizer, &s, text, 0);
+        assert_eq!(res, Ok((Range::new(0, 7), TokenizerState(1), None)));
+        assert_eq!(&tokenizer.tokens[0].1,
+            &MetaData::String(Arc::new("name".into()), Arc::new("foo_Bar".into())));
+    }
+
+    #[test]
+    fn required() {
+        let text = "2foo";
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let res = ident().parse(&mut tokenizer, &s, text, 0);
+        assert_eq!(res, Err((Range::empty(0), ParseError::ExpectedCharClass(0))));
+    }
+
+    #[test]
+    fn negated_bounded() {
+        let text = "ab\u{e6}d*/";
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let class = CharClass {
+            ranges: CharClass::ranges_from("*"),
+            negated: true,
+            min: 2,
+            max: Some(3),
+            property: None,
+            debug_id: 0,
+        };
+        let res = class.parse(&mut tokenizer, &s, text, 0);
+        assert_eq!(res, Ok((Range::new(0, 4), TokenizerState(0), None)));
+        let res = class.parse(&mut tokenizer, &s, &text[4..], 4);
+        assert_eq!(res, Err((Range::empty(5), ParseError::ExpectedCharClass(0))));
+    }
+}
@@ -0,0 +1,135 @@
+use read;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Stores information about comments, which are skipped as whitespace.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Comments {
+    /// The prefixes of comments that end at a new line, for example `//`.
+    pub line: Vec<Arc<String>>,
+    /// The tokens that start and end block comments, for example `/*` and `*/`.
+    pub block: Vec<(Arc<String>, Arc<String>)>,
+    /// Whether block comments can contain other block comments.
+    pub nested: bool,
+}
+
+impl Comments {
+    /// Returns the number of bytes of whitespace and comments at the start.
+    /// Stops at a new line outside block comments if `new_lines` is `false`.
+    /// Returns `Err(i)` if a block comment starting at byte `i` does not end.
+    pub fn whitespace_len(&self, chars: &str, new_lines: bool) -> Result<usize, usize> {
+        whitespace_len(&self.line, &self.block, self.nested, chars, new_lines)
+    }
+
+    /// Returns the length of the longest token that starts a comment.
+    pub fn start_len(&self) -> usize {
+        self.line.iter().chain(self.block.iter().map(|b| &b.0))
+            .map(|s| s.len()).max().unwrap_or(0)
+    }
+}
+
+/// Returns the number of bytes of whitespace and comments at the start.
+/// This is used by generated code to avoid creating the comments each time.
+pub fn whitespace_len<S>(
+    line: &[S],
+    block: &[(S, S)],
+    nested: bool,
+    chars: &str,
+    new_lines: bool
+) -> Result<usize, usize>
+    where S: Deref, S::Target: AsRef<str>
+{
+    let mut i = 0;
+    loop {
+        i += if new_lines {
+                read::whitespace_len(&chars[i..])
+            } else {
+                read::line_whitespace_len(&chars[i..])
+            };
+        let rest = &chars[i..];
+        let starts_with = |s: &S| !text(s).is_empty() && rest.starts_with(text(s));
+        if line.iter().any(|prefix| starts_with(prefix)) {
+            // Stops before the new line, such that `Lines` can read it.
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(&(ref open, ref close)) = block.iter()
+            .find(|b| starts_with(&b.0) && !text(&b.1).is_empty()) {
+            match block_len(text(open), text(close), nested, rest) {
+                None => return Err(i),
+                Some(len) => i += len,
+            }
+        } else {
+            return Ok(i);
+        }
+    }
+}
+
+/// Returns the text of a comment token.
+fn text<S>(s: &S) -> &str
+    where S: Deref, S::Target: AsRef<str>
+{
+    (**s).as_ref()
+}
+
+/// Returns the number of bytes of a block comment at the start,
+/// or `None` if it does not end.
+fn block_len(open: &str, close: &str, nested: bool, chars: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = 0;
+    while let Some(c) = chars[i..].chars().next() {
+        let rest = &chars[i..];
+        if depth > 0 && rest.starts_with(close) {
+            depth -= 1;
+            i += close.len();
+            if depth == 0 { return Some(i); }
+        } else if (depth == 0 || nested) && rest.starts_with(open) {
+            depth += 1;
+            i += open.len();
+        } else {
+            i += c.len_utf8();
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use std::sync::Arc;
+
+    fn comments(nested: bool) -> Comments {
+        Comments {
+            line: vec![Arc::new("//".into()), Arc::new("#".into())],
+            block: vec![(Arc::new("/*".into()), Arc::new("*/".into()))],
+            nested: nested,
+        }
+    }
+
+    #[test]
+    fn line() {
+        let text = "  // a\n # b\nc";
+        assert_eq!(comments(false).whitespace_len(text, true), Ok(12));
+        assert_eq!(comments(false).whitespace_len(text, false), Ok(6));
+        assert_eq!(comments(false).whitespace_len("c // d", true), Ok(0));
+    }
+
+    #[test]
+    fn block() {
+        let text = "/* a /* b */ c */ d";
+        assert_eq!(comments(false).whitespace_len(text, true), Ok(13));
+        assert_eq!(comments(true).whitespace_len(text, true), Ok(18));
+        assert_eq!(comments(false).whitespace_len("/*\n*/ d", false), Ok(6));
+        assert_eq!(comments(true).whitespace_len(" /* /* */", true), Err(1));
+    }
+
+    #[test]
+    fn whitespace() {
+        let w = Whitespace {
+            debug_id: 0,
+            optional: false,
+            comments: Some(Arc::new(comments(false))),
+        };
+        assert_eq!(w.parse("/* a */b", 0), Ok(Range::new(0, 7)));
+        assert_eq!(w.parse("b", 0), Err((Range::new(0, 0), ParseError::ExpectedWhitespace(0))));
+        assert_eq!(w.parse(" /* a", 2), Err((Range::new(3, 4), ParseError::ExpectedCommentEnd(0))));
+    }
+}
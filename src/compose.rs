@@ -0,0 +1,245 @@
+use range::Range;
+use std::sync::Arc;
+
+use {
+    MetaData,
+    Rule,
+};
+
+/// Composes text from meta data with rules.
+///
+/// This is the inverse of `parse`.
+/// The composed text parses back to the same meta data,
+/// but whitespace and formatting might differ from the original document.
+pub fn compose(
+    rules: &[(Arc<String>, Rule)],
+    data: &[(Range, MetaData)]
+) -> Result<String, ()> {
+    let n = match rules.len() {
+        0 => { return Err(()); }
+        x => x
+    };
+    let mut out = String::new();
+    let range = try!(rules[n - 1].1.compose(&mut out, data, 0, rules));
+    // Fail if not all meta data was used.
+    if range.next_offset() < data.len() {
+        Err(())
+    } else {
+        Ok(out)
+    }
+}
+
+/// Updates the composer state.
+/// Used by rules that have multiple sub rules.
+#[inline(always)]
+pub fn update(range: Range, data: &mut &[(Range, MetaData)], offset: &mut usize) {
+    let next_offset = range.next_offset();
+    *data = &data[next_offset - *offset..];
+    *offset = next_offset;
+}
+
+/// Inserts a space where optional whitespace was composed,
+/// when the text on both sides would otherwise be read as one item,
+/// for example two numbers.
+pub fn separate(out: &mut String, at: usize) {
+    let merges = |c: char| c.is_alphanumeric() || c == '_' || c == '.' || c == '"';
+    match (out[..at].chars().next_back(), out[at..].chars().next()) {
+        (Some(a), Some(b)) if merges(a) && merges(b) => out.insert(at, ' '),
+        _ => {}
+    }
+}
+
+/// Reads start node.
+pub fn start_node(name: &str, data: &[(Range, MetaData)], offset: usize)
+-> Result<Range, ()> {
+    match data.first() {
+        Some(&(_, MetaData::StartNode(ref n))) if &**n == name => {
+            Ok(Range::new(offset, 1))
+        }
+        _ => Err(())
+    }
+}
+
+/// Reads end node.
+pub fn end_node(name: &str, data: &[(Range, MetaData)], offset: usize)
+-> Result<Range, ()> {
+    match data.first() {
+        Some(&(_, MetaData::EndNode(ref n))) if &**n == name => {
+            Ok(Range::new(offset, 1))
+        }
+        _ => Err(())
+    }
+}
+
+/// Reads string.
+pub fn meta_string(name: &str, data: &[(Range, MetaData)], offset: usize)
+-> Result<(Range, Arc<String>), ()> {
+    match data.first() {
+        Some(&(_, MetaData::String(ref n, ref val))) if &**n == name => {
+            Ok((Range::new(offset, 1), val.clone()))
+        }
+        _ => Err(())
+    }
+}
+
+/// Reads f64.
+pub fn meta_f64(name: &str, data: &[(Range, MetaData)], offset: usize)
+-> Result<(Range, f64), ()> {
+    match data.first() {
+        Some(&(_, MetaData::F64(ref n, val))) if &**n == name => {
+            Ok((Range::new(offset, 1), val))
+        }
+        _ => Err(())
+    }
+}
+
+/// Reads i64.
+pub fn meta_i64(name: &str, data: &[(Range, MetaData)], offset: usize)
+-> Result<(Range, i64), ()> {
+    match data.first() {
+        Some(&(_, MetaData::I64(ref n, val))) if &**n == name => {
+            Ok((Range::new(offset, 1), val))
+        }
+        _ => Err(())
+    }
+}
+
+/// Reads u64.
+pub fn meta_u64(name: &str, data: &[(Range, MetaData)], offset: usize)
+-> Result<(Range, u64), ()> {
+    match data.first() {
+        Some(&(_, MetaData::U64(ref n, val))) if &**n == name => {
+            Ok((Range::new(offset, 1), val))
+        }
+        _ => Err(())
+    }
+}
+
+/// Reads bool.
+pub fn meta_bool(name: &str, data: &[(Range, MetaData)], offset: usize)
+-> Result<(Range, bool), ()> {
+    match data.first() {
+        Some(&(_, MetaData::Bool(ref n, val))) if &**n == name => {
+            Ok((Range::new(offset, 1), val))
+        }
+        _ => Err(())
+    }
+}
+
+/// Writes text as a quoted string with character escapes.
+pub fn write_string(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{0008}' => out.push_str("\\b"),
+            '\u{000c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                out.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use range::Range;
+    use std::sync::Arc;
+
+    #[test]
+    fn round_trip() {
+        let text = r#"
+1 "material" [t!"name" w? ":" w? "(" w? "reflectivity" w? ":" w? $"reflectivity" ?"," w? ")"]
+2 "materials" ["materials" w? ":" w? "{" w? s?.(["," w?]){@"material""material"} w? "}"]
+3 "document" [w? "(" w? s?(["," w?]){@"materials""materials"} w? ")" w?]
+        "#;
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), text).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let doc = r#"
+        (
+            materials: {
+                "metal": (reflectivity: 1.0),
+                "plastic \"soft\"": (reflectivity: 0.5,),
+            },
+        )
+        "#;
+        let data = parse(&rules, doc).unwrap();
+        let composed = compose(&rules, &data).unwrap();
+        assert_eq!(&composed, r#"(materials:{"metal":(reflectivity:1,),"plastic \"soft\"":(reflectivity:0.5,)})"#);
+        let data2 = parse(&rules, &composed).unwrap();
+        let meta1: Vec<&MetaData> = data.iter().map(|x| &x.1).collect();
+        let meta2: Vec<&MetaData> = data2.iter().map(|x| &x.1).collect();
+        assert_eq!(meta1, meta2);
+    }
+
+    #[test]
+    fn self_syntax() {
+        use std::fs::File;
+        use std::io::Read;
+
+        let mut source = String::new();
+        File::open("assets/self-syntax.txt").unwrap()
+            .read_to_string(&mut source).unwrap();
+        let rules = bootstrap::rules();
+        let data = parse(&rules, &source).unwrap();
+        let composed = compose(&rules, &data).unwrap();
+        let data2 = parse(&rules, &composed).unwrap();
+        let meta1: Vec<&MetaData> = data.iter().map(|x| &x.1).collect();
+        let meta2: Vec<&MetaData> = data2.iter().map(|x| &x.1).collect();
+        assert_eq!(meta1, meta2);
+    }
+
+    #[test]
+    fn select_data() {
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), r#"1 "document" {"null" $"num" t!"str"}"#).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        for text in &["null", "2", "\"a\""] {
+            let data = parse(&rules, text).unwrap();
+            assert_eq!(&compose(&rules, &data).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn separate() {
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), r#"
+1 "document" [$"a" w? $"b" w? .." "!"c" w? s?.(w?){$"d"}]
+            "#).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let data = parse(&rules, "1 2 foo 3 4").unwrap();
+        let composed = compose(&rules, &data).unwrap();
+        assert_eq!(&composed, "1 2 foo 3 4");
+        assert_eq!(parse(&rules, &composed), Ok(data));
+    }
+
+    #[test]
+    fn unused_data() {
+        let foo: Arc<String> = Arc::new("foo".into());
+        let rule = Rule::Token(Token {
+            debug_id: 0,
+            text: Arc::new("foo".into()),
+            inverted: false,
+            case_insensitive: false,
+            property: Some(foo.clone()),
+        });
+        let rules = [(Arc::new("".into()), rule)];
+        let data = vec![
+            (Range::empty(0), MetaData::Bool(foo.clone(), true)),
+            (Range::empty(0), MetaData::Bool(foo.clone(), true)),
+        ];
+        assert_eq!(compose(&rules, &data[..1]), Ok("foo".into()));
+        assert_eq!(compose(&rules, &data), Err(()));
+    }
+}
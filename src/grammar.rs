@@ -0,0 +1,117 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use nullable::infinite_loop;
+use {
+    update_refs,
+    ParseError,
+    Rule,
+};
+
+/// Stores rules that can not be changed, with all references resolved.
+///
+/// This is `Send + Sync`, such that it can be shared between threads
+/// or stored in a static.
+/// Derefs to the rules, such that it can be passed to `parse`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grammar {
+    rules: Vec<(Arc<String>, Rule)>,
+}
+
+impl Grammar {
+    /// Creates a new grammar, resolving the references between rules.
+    /// Returns an error if a rule refers to a name that does not exist,
+    /// or if a rule that can read nothing is repeated.
+    pub fn new(mut rules: Vec<(Arc<String>, Rule)>) -> Result<Grammar, ParseError> {
+        update_refs(&mut rules);
+        for r in &rules {
+            if let Some(name) = unresolved(&r.1) {
+                return Err(ParseError::UnknownRule(name));
+            }
+        }
+        if let Some(debug_id) = infinite_loop(&rules) {
+            return Err(ParseError::InfiniteLoop(debug_id));
+        }
+        Ok(Grammar { rules: rules })
+    }
+
+    /// Returns the rules.
+    pub fn rules(&self) -> &[(Arc<String>, Rule)] {
+        &self.rules
+    }
+}
+
+impl Deref for Grammar {
+    type Target = [(Arc<String>, Rule)];
+
+    fn deref(&self) -> &[(Arc<String>, Rule)] {
+        &self.rules
+    }
+}
+
+/// Returns the name of the first reference that is not resolved.
+fn unresolved(rule: &Rule) -> Option<Arc<String>> {
+    match rule {
+        &Rule::Node(ref n) => {
+            if n.index.is_none() { Some(n.name.clone()) } else { None }
+        }
+        &Rule::Whitespace(_) => None,
+        &Rule::Token(_) => None,
+        &Rule::UntilAny(_) => None,
+        &Rule::UntilAnyOrWhitespace(_) => None,
+        &Rule::Text(_) => None,
+        &Rule::Number(_) => None,
+        &Rule::CharClass(_) => None,
+        &Rule::Select(ref s) => s.args.iter().filter_map(unresolved).next(),
+        &Rule::Sequence(ref s) => s.args.iter().filter_map(unresolved).next(),
+        &Rule::SeparatedBy(ref s) => unresolved(&s.rule).or_else(|| unresolved(&s.by)),
+        &Rule::Repeat(ref r) => unresolved(&r.rule),
+        &Rule::Lines(ref l) => unresolved(&l.rule),
+        &Rule::Indent(ref i) => unresolved(&i.rule),
+        &Rule::Optional(ref o) => unresolved(&o.rule),
+        &Rule::Lookahead(ref l) => unresolved(&l.rule),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn is_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn threads() {
+        is_send_sync::<Grammar>();
+
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), r#"1 "rule" ["say" w! t?"foo"]"#).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let grammar = Arc::new(Grammar::new(rules).unwrap());
+        let handles: Vec<_> = (0..4).map(|i| {
+            let grammar = grammar.clone();
+            thread::spawn(move || {
+                let text = format!("say \"{}\"", i);
+                parse(&grammar, &text).unwrap().len()
+            })
+        }).collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 1);
+        }
+    }
+
+    #[test]
+    fn unknown_rule() {
+        let rules = vec![(Arc::new("a".into()), Rule::Node(Node {
+            name: Arc::new("b".into()),
+            property: None,
+            debug_id: 0,
+            index: None,
+        }))];
+        assert_eq!(Grammar::new(rules),
+            Err(ParseError::UnknownRule(Arc::new("b".into()))));
+        assert!(Grammar::new(bootstrap::rules()).is_ok());
+    }
+}
@@ -0,0 +1,309 @@
+use range::Range;
+use read;
+use std::sync::Arc;
+
+use {
+    compose,
+    Comments,
+    ends_with_new_line,
+    ret_err,
+    update,
+    DebugId,
+    MetaData,
+    ParseError,
+    ParseResult,
+    Rule,
+    Tokenizer,
+    TokenizerState,
+};
+
+/// Stores information about an indented block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Indent {
+    /// The rule to read each line of the block.
+    pub rule: Rule,
+    /// Whether lines are indented with tabs instead of spaces.
+    pub tabs: bool,
+    /// A debug id to track down the rule generating an error.
+    pub debug_id: DebugId,
+    /// The comments to skip between lines, if any.
+    pub comments: Option<Arc<Comments>>,
+}
+
+impl Indent {
+    /// Parses rule on the following lines,
+    /// which must be indented deeper than the current block.
+    /// Ignores lines that only contain whitespace characters.
+    pub fn parse(
+        &self,
+        tokenizer: &mut Tokenizer,
+        state: &TokenizerState,
+        chars: &str,
+        offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> ParseResult<TokenizerState> {
+        let space_len = |chars: &str| match self.comments {
+            None => Ok(read::line_whitespace_len(chars)),
+            Some(ref comments) => comments.whitespace_len(chars, false),
+        };
+        parse_indent(self.tabs, self.debug_id, &space_len,
+            tokenizer, state, chars, offset,
+            &mut |tokenizer, state, chars, offset| {
+                self.rule.parse(tokenizer, state, chars, offset, refs)
+            })
+    }
+
+    /// Composes rule on separate lines,
+    /// indented one level deeper than the current line.
+    /// Stops when the rule does not match or uses no meta data.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        mut data: &[(Range, MetaData)],
+        start_offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> Result<Range, ()> {
+        let mut indent = {
+            let line = &out[out.rfind('\n').map(|i| i + 1).unwrap_or(0)..];
+            let n = line.bytes().take_while(|&b| b == b' ' || b == b'\t').count();
+            line[..n].to_string()
+        };
+        indent.push_str(if self.tabs { "\t" } else { "    " });
+        let mut offset = start_offset;
+        loop {
+            let len = out.len();
+            out.push('\n');
+            out.push_str(&indent);
+            match self.rule.compose(out, data, offset, refs) {
+                Ok(range) if range.length > 0 => {
+                    compose::update(range, &mut data, &mut offset);
+                }
+                _ => {
+                    out.truncate(len);
+                    break;
+                }
+            }
+        }
+        if offset == start_offset { return Err(()); }
+        Ok(Range::new(start_offset, offset - start_offset))
+    }
+}
+
+/// Parses an indented block, using a function to parse each line.
+/// The block must be indented deeper than the line before it.
+/// The indentation of the block is kept in the tokenizer while parsing,
+/// such that blocks can be nested.
+pub fn parse_indent<F, G>(
+    tabs: bool,
+    debug_id: DebugId,
+    space_len: &G,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    f: &mut F
+) -> ParseResult<TokenizerState>
+    where F: FnMut(&mut Tokenizer, &TokenizerState, &str, usize)
+        -> ParseResult<TokenizerState>,
+          G: Fn(&str) -> Result<usize, usize>
+{
+    let parent = tokenizer.indent;
+    let res = parse_lines(tokenizer.block_indent(offset), tabs, debug_id, space_len,
+        tokenizer, state, chars, offset, f);
+    tokenizer.indent = parent;
+    res
+}
+
+fn parse_lines<F, G>(
+    parent: usize,
+    tabs: bool,
+    debug_id: DebugId,
+    space_len: &G,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    f: &mut F
+) -> ParseResult<TokenizerState>
+    where F: FnMut(&mut Tokenizer, &TokenizerState, &str, usize)
+        -> ParseResult<TokenizerState>,
+          G: Fn(&str) -> Result<usize, usize>
+{
+    let mut offset = start_offset;
+    let mut state = state.clone();
+    let mut opt_error = None;
+    // The indentation of the block, which is set by the first line.
+    let mut block = None;
+    // The end of the last line read by the rule.
+    let mut end = start_offset;
+    let mut new_line = false;
+    loop {
+        // The block starts on the next line.
+        if !new_line {
+            let len = match space_len(chars) {
+                Ok(len) => len,
+                Err(i) => {
+                    tokenizer.examine(offset + chars.len() + 1);
+                    return Err(ret_err((Range::new(offset + i, chars.len() - i),
+                        ParseError::ExpectedCommentEnd(debug_id)), opt_error));
+                }
+            };
+            tokenizer.examine(offset + len + 1);
+            if len == chars.len() { break; }
+            if chars.as_bytes()[len] != b'\n' {
+                return Err(ret_err((Range::empty(offset + len),
+                    ParseError::ExpectedNewLine(debug_id)), opt_error));
+            }
+            chars = &chars[len + 1..];
+            offset += len + 1;
+        }
+        new_line = false;
+        let n = chars.bytes().take_while(|&b| b == b' ' || b == b'\t').count();
+        let len = match space_len(&chars[n..]) {
+            Ok(len) => n + len,
+            Err(i) => {
+                tokenizer.examine(offset + chars.len() + 1);
+                return Err(ret_err((Range::new(offset + n + i, chars.len() - n - i),
+                    ParseError::ExpectedCommentEnd(debug_id)), opt_error));
+            }
+        };
+        tokenizer.examine(offset + len + 1);
+        if len == chars.len() { break; }
+        if chars.as_bytes()[len] == b'\n' {
+            // Ignores lines that only contain whitespace.
+            chars = &chars[len..];
+            offset += len;
+            continue;
+        }
+        let wrong = if tabs { b' ' } else { b'\t' };
+        if chars.as_bytes()[..n].contains(&wrong) {
+            return Err(ret_err((Range::new(offset, n),
+                ParseError::MixedIndentation(debug_id)), opt_error));
+        }
+        match block {
+            None if n <= parent => {
+                return Err(ret_err((Range::new(offset, n),
+                    ParseError::ExpectedIndent(debug_id)), opt_error));
+            }
+            None => block = Some(n),
+            Some(_) if n <= parent => break,
+            Some(b) if n > b => {
+                return Err(ret_err((Range::new(offset, n),
+                    ParseError::UnexpectedIndent(debug_id)), opt_error));
+            }
+            Some(b) if n < b => {
+                return Err(ret_err((Range::new(offset, n),
+                    ParseError::InconsistentDedent(debug_id)), opt_error));
+            }
+            Some(_) => {}
+        }
+        chars = &chars[n..];
+        offset += n;
+        tokenizer.indent = n;
+        state = match f(tokenizer, &state, chars, offset) {
+            Err(err) => return Err(ret_err(err, opt_error)),
+            Ok((range, state, err)) => {
+                // Find whether a new line occured at the end.
+                // If it did, the next line starts right away.
+                new_line = ends_with_new_line(&chars[..range.next_offset() - offset]);
+                update(range, err, &mut chars, &mut offset, &mut opt_error);
+                end = offset;
+                state
+            }
+        };
+    }
+    if block.is_none() {
+        return Err(ret_err((Range::empty(offset),
+            ParseError::ExpectedIndent(debug_id)), opt_error));
+    }
+    Ok((Range::new(start_offset, end - start_offset), state, opt_error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use range::Range;
+    use std::sync::Arc;
+
+    fn indent(tabs: bool) -> Indent {
+        Indent {
+            debug_id: 0,
+            tabs: tabs,
+            comments: None,
+            rule: Rule::Token(Token {
+                debug_id: 1,
+                text: Arc::new("x".into()),
+                inverted: false,
+                case_insensitive: false,
+                property: Some(Arc::new("x".into())),
+            }),
+        }
+    }
+
+    #[test]
+    fn block() {
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let text = "\n  x\n\n  x\ny";
+        let res = indent(false).parse(&mut tokenizer, &s, text, 0, &[]);
+        assert_eq!(res, Ok((Range::new(0, 9), TokenizerState(2), None)));
+        assert_eq!(tokenizer.indent, 0);
+    }
+
+    #[test]
+    fn fail() {
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let res = indent(false).parse(&mut tokenizer, &s, " x", 0, &[]);
+        assert_eq!(res, Err((Range::empty(1), ParseError::ExpectedNewLine(0))));
+        let res = indent(false).parse(&mut tokenizer, &s, "\nx", 0, &[]);
+        assert_eq!(res, Err((Range::empty(1), ParseError::ExpectedIndent(0))));
+        let res = indent(false).parse(&mut tokenizer, &s, "\n  x\n    x", 0, &[]);
+        assert_eq!(res, Err((Range::new(5, 4), ParseError::UnexpectedIndent(0))));
+        let res = indent(false).parse(&mut tokenizer, &s, "\n    x\n  x", 0, &[]);
+        assert_eq!(res, Err((Range::new(7, 2), ParseError::InconsistentDedent(0))));
+        let res = indent(false).parse(&mut tokenizer, &s, "\n\tx", 0, &[]);
+        assert_eq!(res, Err((Range::new(1, 1), ParseError::MixedIndentation(0))));
+        let res = indent(true).parse(&mut tokenizer, &s, "\n\tx", 0, &[]);
+        assert_eq!(res, Ok((Range::new(0, 3), TokenizerState(1), None)));
+    }
+
+    #[test]
+    fn nested() {
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.indent = 2;
+        let s = TokenizerState::new();
+        let res = indent(false).parse(&mut tokenizer, &s, "\n  x", 0, &[]);
+        assert_eq!(res, Err((Range::new(1, 2), ParseError::ExpectedIndent(0))));
+        let res = indent(false).parse(&mut tokenizer, &s, "\n    x\n  y", 0, &[]);
+        assert_eq!(res, Ok((Range::new(0, 6), TokenizerState(1), None)));
+        assert_eq!(tokenizer.indent, 2);
+    }
+
+    #[test]
+    fn header() {
+        // The block must be deeper than the line before it.
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let text = "    x\n  x";
+        tokenizer.read_lines(text);
+        let res = indent(false).parse(&mut tokenizer, &s, &text[5..], 5, &[]);
+        assert_eq!(res, Err((Range::new(6, 2), ParseError::ExpectedIndent(0))));
+        let text = "    x\n      x";
+        tokenizer.read_lines(text);
+        let res = indent(false).parse(&mut tokenizer, &s, &text[5..], 5, &[]);
+        assert_eq!(res, Ok((Range::new(5, 8), TokenizerState(1), None)));
+        assert_eq!(tokenizer.indent, 0);
+
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), r#"
+1 "item" ["x" ?i(@"item""item")]
+2 "document" [w? @"item""item" w?]
+            "#).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        assert!(parse(&rules, "  x\n    x\n      x").is_ok());
+        assert!(parse(&rules, "  x\n    x\n   x").is_err());
+        assert!(parse(&rules, "    x\n  x").is_err());
+    }
+}
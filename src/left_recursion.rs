@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use MemoEntry;
+
+/// Keeps track of named rules in progress,
+/// such that left recursive rules can be parsed by growing a seed.
+///
+/// When a rule refers to itself at the same offset,
+/// the inner reference fails the first time.
+/// If the rule succeeds, the result is used as a seed and the rule is
+/// parsed again, where the inner reference replays the seed.
+/// This is repeated as long as the rule reads further.
+#[derive(Clone, Debug)]
+pub struct LeftRecursion {
+    heads: HashMap<(usize, usize), Head>,
+    growing: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Head {
+    seed: Option<MemoEntry>,
+    detected: bool,
+}
+
+impl LeftRecursion {
+    /// Creates a new left recursion tracker.
+    pub fn new() -> LeftRecursion {
+        LeftRecursion {
+            heads: HashMap::new(),
+            growing: 0,
+        }
+    }
+
+    /// Returns `true` if any left recursive rule is in progress.
+    /// Results depending on a seed must not be memoized.
+    pub fn is_growing(&self) -> bool {
+        self.growing > 0
+    }
+
+    /// Starts parsing rule at offset.
+    ///
+    /// Returns `Err` with the current seed if the rule is already in progress
+    /// at the same offset, which means it is left recursive.
+    pub fn enter(&mut self, index: usize, offset: usize)
+    -> Result<(), Option<MemoEntry>> {
+        if let Some(head) = self.heads.get_mut(&(index, offset)) {
+            if !head.detected {
+                head.detected = true;
+                self.growing += 1;
+            }
+            return Err(head.seed.clone());
+        }
+        self.heads.insert((index, offset), Head { seed: None, detected: false });
+        Ok(())
+    }
+
+    /// Stops parsing rule at offset.
+    /// Returns `true` if left recursion was detected.
+    pub fn exit(&mut self, index: usize, offset: usize) -> bool {
+        self.heads.remove(&(index, offset)).map(|head| head.detected)
+            .unwrap_or(false)
+    }
+
+    /// Starts growing rule at offset using a seed.
+    pub fn grow(&mut self, index: usize, offset: usize, seed: MemoEntry) {
+        self.heads.insert((index, offset), Head {
+            seed: Some(seed),
+            detected: true
+        });
+    }
+
+    /// Finishes growing a left recursive rule.
+    pub fn finish(&mut self) {
+        self.growing -= 1;
+    }
+}
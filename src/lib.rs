@@ -6,38 +6,57 @@ extern crate read_token;
 extern crate range;
 
 pub use whitespace::Whitespace;
+pub use comments::Comments;
 pub use parse_error_handler::{ ParseErrorHandler, ParseStdErr };
 pub use parse_error::ParseError;
 pub use token::Token;
 pub use select::Select;
 pub use node::Node;
 pub use optional::Optional;
+pub use lookahead::Lookahead;
 pub use sequence::Sequence;
 pub use separated_by::SeparatedBy;
 pub use repeat::Repeat;
 pub use until_any::UntilAny;
 pub use until_any_or_whitespace::UntilAnyOrWhitespace;
 pub use text::Text;
-pub use number::Number;
+pub use number::{ Number, NumberKind };
+pub use char_class::CharClass;
 pub use lines::Lines;
+pub use indent::Indent;
 pub use rule::Rule;
 pub use tokenizer::{ Tokenizer, TokenizerState };
+pub use memo::{ Memo, MemoEntry };
+pub use left_recursion::LeftRecursion;
+pub use compose::compose;
+pub use source_map::{ ColumnUnit, Position, SourceMap };
+pub use session::Session;
+pub use stream::{ parse_stream, Stream, StreamError };
+pub use range::Range;
+pub use grammar::Grammar;
+pub use program::{ Instruction, Program };
+pub use tracer::{ Tracer, TraceWriter };
+pub use profiler::{ Profiler, ProfileStats };
 
 /// The type of debug id used to track down errors in rules.
 pub type DebugId = usize;
 
-use std::rc::Rc;
-use range::Range;
+use std::cmp::min;
+use std::mem;
+use std::sync::Arc;
 
 pub mod bootstrap;
+pub mod support;
 
 mod parse_error;
 mod parse_error_handler;
 mod token;
 mod whitespace;
+mod comments;
 mod select;
 mod node;
 mod optional;
+mod lookahead;
 mod sequence;
 mod separated_by;
 mod repeat;
@@ -45,27 +64,161 @@ mod until_any;
 mod until_any_or_whitespace;
 mod text;
 mod number;
+mod char_class;
 mod lines;
+mod indent;
 mod rule;
 mod tokenizer;
+mod memo;
+mod left_recursion;
+mod compose;
+mod source_map;
+mod read;
+mod session;
+mod stream;
+mod grammar;
+mod program;
+mod tracer;
+mod profiler;
+mod nullable;
 
 /// Parses text with rules.
+///
+/// Ranges are byte offsets into the text,
+/// such that they can be used to slice it.
 pub fn parse(
-    rules: &[(Rc<String>, Rule)],
+    rules: &[(Arc<String>, Rule)],
     text: &str
 ) -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
-    let chars: Vec<char> = text.chars().collect();
+    parse_with_tokenizer(&mut Tokenizer::new(), rules, text)
+}
+
+/// Parses text with rules, using a tokenizer to store the meta data.
+///
+/// This is used to enable features of the tokenizer, for example memoization,
+/// or limits on the nesting of nodes and the number of rules parsed.
+/// Clears the memo table before parsing, such that the statistics
+/// are available afterwards.
+pub fn parse_with_tokenizer(
+    tokenizer: &mut Tokenizer,
+    rules: &[(Arc<String>, Rule)],
+    text: &str
+) -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
+    if let Some(ref mut memo) = tokenizer.memo { memo.clear(); }
+    parse_with_memo(tokenizer, rules, text)
+}
+
+/// Parses text with rules, notifying a tracer about each rule.
+///
+/// This is used to find out why rules do not parse as expected.
+/// For example, `TraceWriter` shows where alternatives were tried and rejected.
+pub fn parse_with_tracer(
+    rules: &[(Arc<String>, Rule)],
+    text: &str,
+    tracer: &mut dyn Tracer
+) -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
     let mut tokenizer = Tokenizer::new();
-    let s = TokenizerState::new();
+    tokenizer.tracer = Some(tracer);
+    parse_with_memo(&mut tokenizer, rules, text)
+}
+
+/// Parses text with rules, reusing the results in the memo table.
+fn parse_with_memo(
+    tokenizer: &mut Tokenizer,
+    rules: &[(Arc<String>, Rule)],
+    text: &str
+) -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
     let n = match rules.len() {
         0 => { return Err((Range::empty(0), ParseError::NoRules)); }
         x => x
     };
-    let res = rules[n - 1].1.parse(&mut tokenizer, &s, &chars, 0, rules);
+    parse_rule(tokenizer, rules, n - 1, text, 0, true)
+        .map(|(_, data)| data)
+}
+
+/// Parses text with the rule of the given name instead of the last rule.
+pub fn parse_with_start(
+    rules: &[(Arc<String>, Rule)],
+    name: &str,
+    text: &str
+) -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
+    let index = try!(find_rule(rules, name));
+    parse_rule(&mut Tokenizer::new(), rules, index, text, 0, true)
+        .map(|(_, data)| data)
+}
+
+/// Parses the start of text from an offset with the rule of the given name.
+///
+/// Returns the number of bytes read and the meta data.
+/// The rest of the text is ignored.
+/// Returns `ParseError::InvalidOffset` if the offset is not
+/// at a character boundary of the text, at the offset clamped to the text.
+pub fn parse_at(
+    rules: &[(Arc<String>, Rule)],
+    name: &str,
+    text: &str,
+    offset: usize
+) -> Result<(usize, Vec<(Range, MetaData)>), (Range, ParseError)> {
+    let index = try!(find_rule(rules, name));
+    if offset > text.len() || !text.is_char_boundary(offset) {
+        let at = min(offset, text.len());
+        return Err((Range::empty(at), ParseError::InvalidOffset(offset)));
+    }
+    parse_rule(&mut Tokenizer::new(), rules, index, text, offset, false)
+}
+
+/// Finds the index of a rule by name.
+fn find_rule(rules: &[(Arc<String>, Rule)], name: &str)
+-> Result<usize, (Range, ParseError)> {
+    match rules.iter().position(|r| &**r.0 == name) {
+        Some(index) => Ok(index),
+        None => Err((Range::empty(0), ParseError::UnknownRule(Arc::new(name.into()))))
+    }
+}
+
+/// Parses text from an offset with a rule,
+/// returning the number of bytes read and the meta data.
+fn parse_rule(
+    tokenizer: &mut Tokenizer,
+    rules: &[(Arc<String>, Rule)],
+    index: usize,
+    text: &str,
+    offset: usize,
+    to_end: bool
+) -> Result<(usize, Vec<(Range, MetaData)>), (Range, ParseError)> {
+    parse_fn(tokenizer, text, offset, to_end,
+        &mut |tokenizer, state, chars, offset| {
+            rules[index].1.parse(tokenizer, state, chars, offset, rules)
+        })
+}
+
+/// Parses text from an offset with a function,
+/// returning the number of bytes read and the meta data.
+fn parse_fn<F>(
+    tokenizer: &mut Tokenizer,
+    text: &str,
+    offset: usize,
+    to_end: bool,
+    f: &mut F
+) -> Result<(usize, Vec<(Range, MetaData)>), (Range, ParseError)>
+    where F: FnMut(&mut Tokenizer, &TokenizerState, &str, usize)
+        -> ParseResult<TokenizerState>
+{
+    tokenizer.tokens.clear();
+    tokenizer.examined = offset;
+    tokenizer.depth = 0;
+    tokenizer.steps = 0;
+    tokenizer.limit = None;
+    tokenizer.indent = 0;
+    tokenizer.read_lines(text);
+    let s = TokenizerState::new();
+    let res = f(tokenizer, &s, &text[offset..], offset);
+    // A limit stops parsing, even if the error was ignored.
+    if let Some(err) = tokenizer.limit.take() { return Err(err); }
     match res {
         Ok((range, s, opt_error)) => {
             // Report error if did not reach the end of text.
-            if range.next_offset() < text.len() {
+            if to_end && range.next_offset() < text.len() {
                 Err(ret_err(
                     (Range::empty(range.next_offset()),
                         ParseError::ExpectedEnd),
@@ -73,7 +226,7 @@ pub fn parse(
                 ))
             } else {
                 tokenizer.tokens.truncate(s.0);
-                Ok(tokenizer.tokens)
+                Ok((range.length, mem::replace(&mut tokenizer.tokens, vec![])))
             }
         }
         Err((err_range, err)) => {
@@ -82,10 +235,102 @@ pub fn parse(
     }
 }
 
+/// Parses text with rules, including the line and column of each item.
+///
+/// To look up the end position of a range, use a `SourceMap`.
+pub fn parse_with_positions(
+    rules: &[(Arc<String>, Rule)],
+    text: &str,
+    unit: ColumnUnit
+) -> Result<Vec<(Range, Position, MetaData)>, (Range, Position, ParseError)> {
+    let source_map = SourceMap::new(text);
+    match parse(rules, text) {
+        Ok(data) => Ok(data.into_iter().map(|(range, data)| {
+            (range, source_map.position(range.offset, unit), data)
+        }).collect()),
+        Err((range, err)) => {
+            Err((range, source_map.position(range.offset, unit), err))
+        }
+    }
+}
+
+/// Parses text with rules, recovering from errors to report all of them.
+///
+/// When a rule inside `Lines`, `SeparatedBy` or `Repeat` fails,
+/// the text is skipped to the next line, the next separator on the same line
+/// or past the sync token of `Repeat`, and parsing continues.
+/// The skipped text is marked with `MetaData::Error`.
+///
+/// Returns the meta data and the errors in the order they occur.
+/// The meta data is empty if an error could not be recovered from.
+pub fn parse_with_recovery(
+    rules: &[(Arc<String>, Rule)],
+    text: &str
+) -> (Vec<(Range, MetaData)>, Vec<(Range, ParseError)>) {
+    let mut tokenizer = Tokenizer::with_memo();
+    tokenizer.recoverable = Some(vec![]);
+    loop {
+        // Parse again until there are no errors, where the deepest error
+        // of each attempt is recovered from in the next.
+        // Results of named rules are reused when they did not reach the error.
+        match parse_with_memo(&mut tokenizer, rules, text) {
+            Ok(data) => {
+                let errors = data.iter().filter_map(|&(range, ref data)| {
+                    match data {
+                        &MetaData::Error(ref err) => Some((range, err.clone())),
+                        _ => None
+                    }
+                }).collect();
+                return (data, errors);
+            }
+            Err(err) => {
+                let deeper = tokenizer.recover.last()
+                    .map(|last| last.0.next_offset() < err.0.next_offset())
+                    .unwrap_or(true);
+                let next = recovery_error(&mut tokenizer, &err);
+                if !deeper || next.is_none() {
+                    // Give up when the same error occurs again,
+                    // or when no rule can recover from it.
+                    let mut errors = mem::replace(&mut tokenizer.recover, vec![]);
+                    if !errors.contains(&err) { errors.push(err); }
+                    return (vec![], errors);
+                }
+                let next = next.unwrap();
+                if let Some(ref mut memo) = tokenizer.memo {
+                    memo.invalidate(next.0.offset);
+                }
+                tokenizer.recover.push(next);
+            }
+        }
+    }
+}
+
+/// Picks the error to recover from when parsing again,
+/// among the errors that rules could recover from at the same depth
+/// as the reported error.
+///
+/// The reported error might be another one, for example when a rule
+/// after a repetition fails at the same position.
+/// Picks the error that was recorded first, such that the innermost rule
+/// recovers and skips as little text as possible.
+fn recovery_error(tokenizer: &mut Tokenizer, err: &(Range, ParseError))
+-> Option<(Range, ParseError)> {
+    let recoverable = match tokenizer.recoverable {
+        Some(ref mut recoverable) => recoverable,
+        None => return None
+    };
+    let end = err.0.next_offset();
+    let next = recoverable.iter().find(|r| r.0.next_offset() == end).cloned();
+    // Only deeper errors can be recovered from later.
+    recoverable.retain(|r| r.0.next_offset() > end);
+    next
+}
+
 /// Updates the references such that they point to each other.
-pub fn update_refs(rules: &[(Rc<String>, Rule)]) {
+pub fn update_refs(rules: &mut [(Arc<String>, Rule)]) {
+    let names: Vec<Arc<String>> = rules.iter().map(|r| r.0.clone()).collect();
     for r in rules {
-        r.1.update_refs(rules);
+        r.1.update_refs(&names);
     }
 }
 
@@ -99,15 +344,21 @@ pub type ParseResult<S> = Result<(Range, S, Option<(Range, ParseError)>),
 #[derive(PartialEq, Clone, Debug)]
 pub enum MetaData {
     /// Starts node.
-    StartNode(Rc<String>),
+    StartNode(Arc<String>),
     /// Ends node.
-    EndNode(Rc<String>),
+    EndNode(Arc<String>),
     /// Sets bool property.
-    Bool(Rc<String>, bool),
+    Bool(Arc<String>, bool),
     /// Sets f64 property.
-    F64(Rc<String>, f64),
+    F64(Arc<String>, f64),
+    /// Sets i64 property.
+    I64(Arc<String>, i64),
+    /// Sets u64 property.
+    U64(Arc<String>, u64),
     /// Sets string property.
-    String(Rc<String>, Rc<String>),
+    String(Arc<String>, Arc<String>),
+    /// Marks text skipped when recovering from an error.
+    Error(ParseError),
 }
 
 /// Updates the parser state.
@@ -116,7 +367,7 @@ pub enum MetaData {
 fn update<'a>(
     range: range::Range,
     err: Option<(Range, ParseError)>,
-    chars: &mut &'a [char],
+    chars: &mut &'a str,
     offset: &mut usize,
     opt_error: &mut Option<(Range, ParseError)>
 ) {
@@ -126,6 +377,58 @@ fn update<'a>(
     err_update(err, opt_error);
 }
 
+/// Returns the offset of the next new line character from an offset,
+/// or the end of text.
+fn line_end(chars: &str, offset: usize, from: usize) -> usize {
+    let from = from - offset;
+    offset + chars[from..].find('\n').map(|i| from + i).unwrap_or(chars.len())
+}
+
+/// Returns an error if fewer items were read than the minimum count, if any.
+/// Reading stops at the maximum count, so there are never more items.
+fn check_count(
+    count: Option<(usize, Option<usize>)>,
+    n: usize,
+    range: Range,
+    debug_id: DebugId
+) -> Result<(), (Range, ParseError)> {
+    match count {
+        Some((min, max)) if n < min => {
+            Err((range, ParseError::ExpectedCount(min, max, n, debug_id)))
+        }
+        _ => Ok(())
+    }
+}
+
+/// Returns `true` if text ends with a new line,
+/// ignoring other whitespace after it.
+fn ends_with_new_line(text: &str) -> bool {
+    text.trim_end_matches(|c: char| c != '\n' && c.is_whitespace())
+        .ends_with('\n')
+}
+
+/// Finds the first offset on the same line where a rule reads something,
+/// starting at an offset.
+fn find_on_line(
+    rule: &Rule,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    from: usize,
+    refs: &[(Arc<String>, Rule)]
+) -> Option<usize> {
+    let end = line_end(chars, offset, from) - offset;
+    for (i, _) in chars[from - offset..end].char_indices() {
+        let i = from - offset + i;
+        match rule.parse(tokenizer, state, &chars[i..], offset + i, refs) {
+            Ok((range, _, _)) if range.length > 0 => return Some(offset + i),
+            _ => {}
+        }
+    }
+    None
+}
+
 /// Picks deepest error, overwriting with the newest one if they are
 /// equally deep.
 #[inline(always)]
@@ -170,4 +473,162 @@ mod tests{
         assert_eq!(parse(&[], ""),
             Err((Range::empty(0), ParseError::NoRules)));
     }
+
+    #[test]
+    fn byte_offsets() {
+        let rules = r#"1 "rule" [..","!"name" "," w? t!"text"]"#;
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let text = "bl\u{e5}b\u{e6}r, \"\u{1f600}\"";
+        let data = parse(&rules, text).unwrap();
+        assert_eq!(&text[data[0].0.offset..data[0].0.next_offset()],
+            "bl\u{e5}b\u{e6}r");
+        assert_eq!(data[1].0, Range::new(10, 6));
+        assert_eq!(&text[data[1].0.offset..data[1].0.next_offset()],
+            "\"\u{1f600}\"");
+    }
+
+    #[test]
+    fn start_rule() {
+        let rules = meta_rules(r#"
+1 "material" [t!"name" w? ":" w? $"reflectivity"]
+2 "entity" ["entity" w! t!"name"]
+3 "document" [@"entity""entity" w! @"material""material"]
+        "#);
+        let text = r#"entity "ship" "steel": 0.5"#;
+        assert_eq!(parse_with_start(&rules, "document", text), parse(&rules, text));
+        let data = parse_with_start(&rules, "material", r#""steel": 0.5"#).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(parse_with_start(&rules, "entity", text),
+            Err((Range::empty(13), ParseError::ExpectedEnd)));
+        assert_eq!(parse_with_start(&rules, "ship", text),
+            Err((Range::empty(0), ParseError::UnknownRule(Arc::new("ship".into())))));
+
+        let (n, data) = parse_at(&rules, "entity", text, 0).unwrap();
+        assert_eq!(&text[..n], r#"entity "ship""#);
+        assert_eq!(data.len(), 1);
+        let (n, data) = parse_at(&rules, "material", text, 14).unwrap();
+        assert_eq!(n, 12);
+        assert_eq!(data[0].0, Range::new(14, 7));
+        assert_eq!(parse_at(&rules, "material", text, 13),
+            Err((Range::empty(13), ParseError::ExpectedText(1001))));
+        assert_eq!(parse_at(&rules, "material", text, 100),
+            Err((Range::empty(text.len()), ParseError::InvalidOffset(100))));
+        assert_eq!(parse_at(&rules, "document", "\u{e9}1", 1),
+            Err((Range::empty(1), ParseError::InvalidOffset(1))));
+    }
+
+    fn meta_rules(rules: &str) -> Vec<(Arc<String>, Rule)> {
+        bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap()
+    }
+
+    #[test]
+    fn recovery() {
+        let rules = meta_rules(r#"
+1 "item" [t!"name" w? ":" w? {$"value" ["[" w? s!([w? "," w?]) {$"value"} w? "]"]}]
+2 "document" l(@"item""item")
+        "#);
+        let text = "\"a\": 1\n\"b\": x\n\"c\": [1, y, 3]\n\"d\": 4";
+        let (data, errors) = parse_with_recovery(&rules, text);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].0, Range::new(7, 6));
+        assert_eq!(errors[1].0, Range::new(23, 1));
+        assert_eq!(&text[23..24], "y");
+        let values: Vec<f64> = data.iter().filter_map(|d| match d.1 {
+            MetaData::F64(_, val) => Some(val),
+            _ => None
+        }).collect();
+        assert_eq!(values, vec![1.0, 1.0, 3.0, 4.0]);
+        let n = data.iter().filter(|d| match d.1 {
+            MetaData::Error(_) => true,
+            _ => false
+        }).count();
+        assert_eq!(n, 2);
+
+        // Without errors, the result is the same as `parse`.
+        let text = "\"a\": 1\n\"c\": [1, 3]";
+        assert_eq!(parse_with_recovery(&rules, text),
+            (parse(&rules, text).unwrap(), vec![]));
+    }
+
+    #[test]
+    fn recovery_sync() {
+        let rules = meta_rules(r#"
+1 "document" r?([w? t!"name" w? "=" w? $"value" w? ";"])~";"
+        "#);
+        let text = r#""a" = 1; "b" = ; "c" = x y; "d" = 4;"#;
+        let (data, errors) = parse_with_recovery(&rules, text);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].0, Range::new(8, 8));
+        assert_eq!(errors[1].0, Range::new(16, 11));
+        assert_eq!(data.len(), 6);
+    }
+
+    #[test]
+    fn recovery_bounded() {
+        // The item reads nothing at the end, which stops at the maximum count.
+        let rules = meta_rules(r#"
+1 "document" r{0,3}({[t!"name" "=" $"value" ";"] !"\""})~";"
+        "#);
+        let text = r#""a"=1;"b"=x;"#;
+        let (data, errors) = parse_with_recovery(&rules, text);
+        assert_eq!(errors, vec![(Range::new(6, 6), ParseError::ExpectedNumber(1003))]);
+        assert_eq!(data.len(), 3);
+        // Without a maximum count, the item would loop forever.
+        let rules = parse(&bootstrap::rules(), r#"1 "document" r?({"x" !"\""})"#).unwrap();
+        assert!(bootstrap::convert(&rules, &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn recovery_fails() {
+        let rules = meta_rules(r#"1 "document" [t!"name" w! $"value"]"#);
+        let (data, errors) = parse_with_recovery(&rules, r#""a" x"#);
+        assert_eq!(data, vec![]);
+        assert_eq!(errors, vec![(Range::new(4, 0), ParseError::ExpectedNumber(1003))]);
+    }
+
+    #[test]
+    fn depth_limit() {
+        let rules = meta_rules(r#"1 "list" ["(" ?@"list" ")"]"#);
+        let debug_id = match rules[0].1 {
+            Rule::Sequence(ref s) => match s.args[1] {
+                Rule::Optional(ref o) => o.rule.debug_id(),
+                _ => unreachable!()
+            },
+            _ => unreachable!()
+        };
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.max_depth = Some(3);
+        assert!(parse_with_tokenizer(&mut tokenizer, &rules, "((()))").is_ok());
+        // The error is reported even if the node is optional.
+        assert_eq!(parse_with_tokenizer(&mut tokenizer, &rules, "(((())))"),
+            Err((Range::empty(4), ParseError::DepthLimit(debug_id))));
+
+        // Fails instead of running out of stack.
+        tokenizer.max_depth = Some(100);
+        let text = format!("{}{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert_eq!(parse_with_tokenizer(&mut tokenizer, &rules, &text),
+            Err((Range::empty(101), ParseError::DepthLimit(debug_id))));
+    }
+
+    #[test]
+    fn fuel() {
+        let rules = meta_rules(r#"1 "rule" r?(["a" w?])"#);
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.fuel = Some(12);
+        assert!(parse_with_tokenizer(&mut tokenizer, &rules, "a a a").is_ok());
+        assert_eq!(tokenizer.steps, 12);
+        let res = parse_with_tokenizer(&mut tokenizer, &rules, "a a a a a");
+        match res {
+            Err((range, ParseError::OutOfFuel(_))) => assert_eq!(range, Range::empty(7)),
+            _ => panic!("Expected out of fuel, found {:?}", res)
+        }
+        tokenizer.fuel = None;
+        assert!(parse_with_tokenizer(&mut tokenizer, &rules, "a a a a a").is_ok());
+    }
 }
@@ -1,11 +1,18 @@
 use range::Range;
-use std::rc::Rc;
+use read;
+use std::cmp::max;
+use std::sync::Arc;
 
 use {
+    compose,
+    Comments,
+    ends_with_new_line,
+    line_end,
     ret_err,
     err_update,
     update,
     DebugId,
+    MetaData,
     ParseError,
     ParseResult,
     Rule,
@@ -21,6 +28,8 @@ pub struct Lines {
     pub rule: Rule,
     /// A debug id to track down the rule generating an error.
     pub debug_id: DebugId,
+    /// The comments to skip between lines, if any.
+    pub comments: Option<Arc<Comments>>,
 }
 
 impl Lines {
@@ -30,46 +39,55 @@ impl Lines {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        mut chars: &[char],
+        mut chars: &str,
         start_offset: usize,
-        refs: &[(Rc<String>, Rule)]
+        refs: &[(Arc<String>, Rule)]
     ) -> ParseResult<TokenizerState> {
         let mut offset = start_offset;
         let mut state = state.clone();
         let mut opt_error = None;
         let mut new_lines = true;
         loop {
-            let len = chars.iter()
-                .take_while(|&c| *c != '\n' && c.is_whitespace())
-                .count();
+            let len = match self.space_len(chars) {
+                Ok(len) => len,
+                Err(i) => {
+                    tokenizer.examine(offset + chars.len() + 1);
+                    return Err(ret_err((Range::new(offset + i, chars.len() - i),
+                        ParseError::ExpectedCommentEnd(self.debug_id)), opt_error));
+                }
+            };
+            tokenizer.examine(offset + len + 1);
             if len == chars.len() {
                 offset += len;
                 break;
-            } else if chars[len] == '\n' {
+            } else if chars.as_bytes()[len] == b'\n' {
                 chars = &chars[len + 1..];
                 offset += len + 1;
                 new_lines |= true;
             } else {
                 if new_lines {
+                    if self.comments.is_some() {
+                        // The rule starts after the comments.
+                        chars = &chars[len..];
+                        offset += len;
+                    }
                     state = match self.rule.parse(
                         tokenizer, &state, chars, offset, refs) {
                         Err(err) => {
-                            err_update(Some(err), &mut opt_error);
-                            break;
+                            if tokenizer.recovers(&err) {
+                                self.recover(tokenizer, &state, err,
+                                    &mut chars, &mut offset)
+                            } else {
+                                err_update(Some(err), &mut opt_error);
+                                break;
+                            }
                         }
                         Ok((range, state, err)) => {
                             // Find whether a new line occured at the end.
                             // If it did, we do not require a new line before
                             // reading the rule again.
                             let end = range.next_offset() - offset;
-                            let last_new_line = chars[..end].iter()
-                                .rev()
-                                .take_while(|&c| *c != '\n' && c.is_whitespace())
-                                .count();
-                            new_lines =
-                                if end < last_new_line + 1
-                                || chars[end - last_new_line - 1] != '\n' { false }
-                                else { true };
+                            new_lines = ends_with_new_line(&chars[..end]);
                             update(range, err, &mut chars, &mut offset, &mut opt_error);
                             state
                         }
@@ -77,19 +95,81 @@ impl Lines {
                 } else {
                     let err = (Range::new(offset, 0),
                         ParseError::ExpectedNewLine(self.debug_id));
-                    return Err(ret_err(err, opt_error));
+                    let err = ret_err(err, opt_error.clone());
+                    if tokenizer.recovers(&err) {
+                        state = self.recover(tokenizer, &state, err,
+                            &mut chars, &mut offset);
+                        new_lines = true;
+                    } else {
+                        return Err(err);
+                    }
                 }
             }
         }
         Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
     }
+
+    /// Returns the number of bytes of whitespace and comments at the start,
+    /// stopping at a new line.
+    /// Returns `Err(i)` if a block comment starting at byte `i` does not end.
+    pub fn space_len(&self, chars: &str) -> Result<usize, usize> {
+        match self.comments {
+            None => Ok(read::line_whitespace_len(chars)),
+            Some(ref comments) => comments.whitespace_len(chars, false),
+        }
+    }
+
+    /// Skips to the end of the line where the error occured,
+    /// marking the skipped text with the error.
+    fn recover<'a>(
+        &self,
+        tokenizer: &mut Tokenizer,
+        state: &TokenizerState,
+        err: (Range, ParseError),
+        chars: &mut &'a str,
+        offset: &mut usize
+    ) -> TokenizerState {
+        let end = line_end(chars, *offset, max(err.0.offset, *offset));
+        tokenizer.examine(end + 1);
+        let state = tokenizer.data(MetaData::Error(err.1), state,
+            Range::new(*offset, end - *offset));
+        *chars = &chars[end - *offset..];
+        *offset = end;
+        state
+    }
+
+    /// Composes rule on separate lines.
+    /// Stops when the rule does not match or uses no meta data.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        mut data: &[(Range, MetaData)],
+        start_offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> Result<Range, ()> {
+        let mut offset = start_offset;
+        loop {
+            let len = out.len();
+            match self.rule.compose(out, data, offset, refs) {
+                Ok(range) if range.length > 0 => {
+                    compose::update(range, &mut data, &mut offset);
+                    out.push('\n');
+                }
+                _ => {
+                    out.truncate(len);
+                    break;
+                }
+            }
+        }
+        Ok(Range::new(start_offset, offset - start_offset))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::*;
     use range::Range;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn fail() {
@@ -103,15 +183,20 @@ mod tests {
 \"error\"
 4
         ";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
         let lines = Lines {
             debug_id: 0,
+            comments: None,
             rule: Rule::Number(Number {
                 debug_id: 1,
                 property: None,
                 allow_underscore: false,
+                kind: NumberKind::F64,
+                allow_radix: false,
+                require_sign: false,
+                allow_special: false,
             }),
         };
         let res = lines.parse(&mut tokenizer, &s, &chars, 0, &[]);
@@ -129,12 +214,13 @@ mod tests {
 
 5
  ";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let val: Rc<String> = Rc::new("val".into());
+        let val: Arc<String> = Arc::new("val".into());
         let lines = Lines {
             debug_id: 0,
+            comments: None,
             rule: Rule::Sequence(Sequence {
                 debug_id: 1,
                 args: vec![
@@ -142,10 +228,15 @@ mod tests {
                         debug_id: 1,
                         property: Some(val.clone()),
                         allow_underscore: false,
+                        kind: NumberKind::F64,
+                        allow_radix: false,
+                        require_sign: false,
+                        allow_special: false,
                     }),
                     Rule::Whitespace(Whitespace {
                         debug_id: 2,
                         optional: true,
+                        comments: None,
                     })
                 ]
             }),
@@ -165,16 +256,21 @@ mod tests {
 
 4
  ";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let val: Rc<String> = Rc::new("val".into());
+        let val: Arc<String> = Arc::new("val".into());
         let lines = Lines {
             debug_id: 0,
+            comments: None,
             rule: Rule::Number(Number {
                 debug_id: 1,
                 property: Some(val.clone()),
                 allow_underscore: false,
+                kind: NumberKind::F64,
+                allow_radix: false,
+                require_sign: false,
+                allow_special: false,
             }),
         };
         let res = lines.parse(&mut tokenizer, &s, &chars, 0, &[]);
@@ -191,21 +287,27 @@ mod tests {
 \"two\"
 \"three\"
         ";
-        let num: Rc<String> = Rc::new("num".into());
-        let tex: Rc<String> = Rc::new("tex".into());
+        let num: Arc<String> = Arc::new("num".into());
+        let tex: Arc<String> = Arc::new("tex".into());
         let rule = Rule::Sequence(Sequence {
             debug_id: 0,
             args: vec![
                 Rule::Lines(Box::new(Lines {
                     debug_id: 1,
+                    comments: None,
                     rule: Rule::Number(Number {
                         debug_id: 2,
                         allow_underscore: true,
+                        kind: NumberKind::F64,
+                        allow_radix: false,
+                        require_sign: false,
+                        allow_special: false,
                         property: Some(num.clone()),
                     })
                 })),
                 Rule::Lines(Box::new(Lines {
                     debug_id: 3,
+                    comments: None,
                     rule: Rule::Text(Text {
                         debug_id: 4,
                         allow_empty: false,
@@ -214,14 +316,14 @@ mod tests {
                 }))
             ]
         });
-        let res = parse(&[(Rc::new("".into()), rule)], text);
+        let res = parse(&[(Arc::new("".into()), rule)], text);
         assert_eq!(res, Ok(vec![
             (Range::new(1, 1), MetaData::F64(num.clone(), 1.0)),
             (Range::new(3, 1), MetaData::F64(num.clone(), 2.0)),
             (Range::new(5, 1), MetaData::F64(num.clone(), 3.0)),
-            (Range::new(7, 5), MetaData::String(tex.clone(), Rc::new("one".into()))),
-            (Range::new(13, 5), MetaData::String(tex.clone(), Rc::new("two".into()))),
-            (Range::new(19, 7), MetaData::String(tex.clone(), Rc::new("three".into())))
+            (Range::new(7, 5), MetaData::String(tex.clone(), Arc::new("one".into()))),
+            (Range::new(13, 5), MetaData::String(tex.clone(), Arc::new("two".into()))),
+            (Range::new(19, 7), MetaData::String(tex.clone(), Arc::new("three".into())))
         ]));
     }
 }
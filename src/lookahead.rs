@@ -0,0 +1,94 @@
+use range::Range;
+use std::sync::Arc;
+
+use {
+    DebugId,
+    ParseError,
+    ParseResult,
+    Rule,
+    Tokenizer,
+    TokenizerState,
+};
+
+/// Stores information about lookahead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lookahead {
+    /// The rule to look for.
+    pub rule: Rule,
+    /// Whether the rule must not match instead.
+    pub negate: bool,
+    /// A debug id to track down the rule generating an error.
+    pub debug_id: DebugId,
+}
+
+impl Lookahead {
+    /// Parses the rule without reading anything.
+    /// Returns the old state, such that the meta data of the rule is thrown away.
+    pub fn parse(
+        &self,
+        tokenizer: &mut Tokenizer,
+        state: &TokenizerState,
+        chars: &str,
+        offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> ParseResult<TokenizerState> {
+        match self.rule.parse(tokenizer, state, chars, offset, refs) {
+            Ok((range, _, _)) => {
+                if self.negate {
+                    Err((range, ParseError::UnexpectedMatch(self.debug_id)))
+                } else {
+                    Ok((Range::empty(offset), state.clone(), None))
+                }
+            }
+            Err(err) => {
+                if self.negate {
+                    Ok((Range::empty(offset), state.clone(), None))
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use range::Range;
+    use std::sync::Arc;
+
+    fn keyword(negate: bool) -> Lookahead {
+        Lookahead {
+            debug_id: 0,
+            negate: negate,
+            rule: Rule::Token(Token {
+                debug_id: 1,
+                text: Arc::new("if".into()),
+                inverted: false,
+                case_insensitive: false,
+                property: Some(Arc::new("if".into())),
+            }),
+        }
+    }
+
+    #[test]
+    fn positive() {
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let res = keyword(false).parse(&mut tokenizer, &s, "if x", 0, &[]);
+        assert_eq!(res, Ok((Range::empty(0), TokenizerState(0), None)));
+        let res = keyword(false).parse(&mut tokenizer, &s, "x", 0, &[]);
+        assert_eq!(res, Err((Range::empty(0),
+            ParseError::ExpectedToken(Arc::new("if".into()), 1))));
+    }
+
+    #[test]
+    fn negative() {
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let res = keyword(true).parse(&mut tokenizer, &s, "if x", 0, &[]);
+        assert_eq!(res, Err((Range::new(0, 2), ParseError::UnexpectedMatch(0))));
+        let res = keyword(true).parse(&mut tokenizer, &s, "x", 0, &[]);
+        assert_eq!(res, Ok((Range::empty(0), TokenizerState(0), None)));
+    }
+}
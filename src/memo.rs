@@ -0,0 +1,229 @@
+use range::Range;
+use std::collections::HashMap;
+use std::mem;
+
+use {
+    MetaData,
+    ParseError,
+};
+
+/// Stores the result of parsing a named rule at some offset.
+#[derive(Clone, Debug)]
+pub enum MemoEntry {
+    /// The rule succeeded with a range, meta data and optional error.
+    Ok(Range, Vec<(Range, MetaData)>, Option<(Range, ParseError)>),
+    /// The rule failed.
+    Err((Range, ParseError)),
+}
+
+/// Caches results of named rules by rule index, offset
+/// and indentation that a block at the offset must exceed,
+/// such that backtracking does not parse the same rule twice.
+///
+/// Each result is stored with the offset after the last examined character,
+/// such that results not affected by changes to the text can be kept.
+#[derive(Clone, Debug)]
+pub struct Memo {
+    entries: HashMap<(usize, usize, usize), (usize, MemoEntry)>,
+    /// The number of results replayed from the cache.
+    pub hits: usize,
+    /// The number of results parsed and stored in the cache.
+    pub misses: usize,
+}
+
+impl Memo {
+    /// Creates a new empty memo table.
+    pub fn new() -> Memo {
+        Memo {
+            entries: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Removes all entries and resets statistics.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Looks up result of rule at offset within a block indentation,
+    /// counting a hit if found.
+    /// Returns the examined offset and the result.
+    pub fn get(&mut self, index: usize, offset: usize, indent: usize)
+    -> Option<(usize, &MemoEntry)> {
+        let entry = self.entries.get(&(index, offset, indent))
+            .map(|&(examined, ref entry)| (examined, entry));
+        if entry.is_some() { self.hits += 1; }
+        entry
+    }
+
+    /// Stores result of rule at offset within a block indentation,
+    /// counting a miss.
+    pub fn insert(
+        &mut self,
+        index: usize,
+        offset: usize,
+        indent: usize,
+        examined: usize,
+        entry: MemoEntry
+    ) {
+        self.misses += 1;
+        self.entries.insert((index, offset, indent), (examined, entry));
+    }
+
+    /// Removes results that examined the character at offset,
+    /// for example when parsing it should give another result.
+    pub fn invalidate(&mut self, offset: usize) {
+        self.entries.retain(|&(_, start, _), &mut (examined, _)| {
+            start > offset || examined <= offset
+        });
+    }
+
+    /// Updates the table when a range of text is replaced
+    /// with new text of some length.
+    ///
+    /// Removes results that examined the replaced text
+    /// and moves results after it.
+    pub fn edit(&mut self, range: Range, length: usize) {
+        let end = range.next_offset();
+        let shift = |r: Range| -> Range {
+            if r.offset >= end {
+                Range::new(r.offset + length - range.length, r.length)
+            } else {
+                r
+            }
+        };
+        let entries = mem::replace(&mut self.entries, HashMap::new());
+        for ((index, offset, indent), (examined, entry)) in entries {
+            if offset >= end {
+                let entry = match entry {
+                    MemoEntry::Ok(r, data, err) => MemoEntry::Ok(
+                        shift(r),
+                        data.into_iter().map(|(r, d)| (shift(r), d)).collect(),
+                        err.map(|err| shift_error(err, &shift))
+                    ),
+                    MemoEntry::Err(err) => MemoEntry::Err(shift_error(err, &shift)),
+                };
+                self.entries.insert((index, offset + length - range.length, indent),
+                    (examined + length - range.length, entry));
+            } else if examined <= range.offset {
+                self.entries.insert((index, offset, indent), (examined, entry));
+            }
+        }
+    }
+}
+
+/// Moves the ranges of an error.
+fn shift_error<F>(err: (Range, ParseError), shift: &F) -> (Range, ParseError)
+    where F: Fn(Range) -> Range
+{
+    use read_token::ParseStringError::*;
+
+    let (range, err) = err;
+    let err = match err {
+        ParseError::ParseStringError(err, debug_id) => {
+            let err = match err {
+                ExpectedFourHexadecimals(r) => ExpectedFourHexadecimals(shift(r)),
+                ExpectedHexadecimal(r) => ExpectedHexadecimal(shift(r)),
+                ExpectedValidUnicode(r) => ExpectedValidUnicode(shift(r)),
+                ExpectedValidEscapeCharacter(r) =>
+                    ExpectedValidEscapeCharacter(shift(r)),
+            };
+            ParseError::ParseStringError(err, debug_id)
+        }
+        x => x
+    };
+    (shift(range), err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use range::Range;
+
+    #[test]
+    fn backtrack() {
+        let rules = r#"
+1 "item" [t!"name" w? ":" w? $"value"]
+2 "document" {
+  [@"item""item" w? ";"]
+  [@"item""item" w? "!"]
+}
+        "#;
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let text = r#""foo": 3!"#;
+        let mut tokenizer = Tokenizer::with_memo();
+        let res = parse_with_tokenizer(&mut tokenizer, &rules, text);
+        assert_eq!(res, parse(&rules, text));
+        assert_eq!(res.unwrap().len(), 4);
+        {
+            let memo = tokenizer.memo.as_ref().unwrap();
+            assert_eq!(memo.hits, 1);
+            assert_eq!(memo.misses, 1);
+            assert_eq!(memo.len(), 1);
+        }
+
+        // Failures are cached too.
+        let text = r#""foo": x!"#;
+        let res = parse_with_tokenizer(&mut tokenizer, &rules, text);
+        assert_eq!(res, parse(&rules, text));
+        let memo = tokenizer.memo.as_ref().unwrap();
+        assert_eq!(memo.hits, 1);
+        assert_eq!(memo.misses, 1);
+    }
+
+    #[test]
+    fn self_syntax() {
+        use std::fs::File;
+        use std::io::Read;
+
+        let mut source = String::new();
+        File::open("assets/self-syntax.txt").unwrap()
+            .read_to_string(&mut source).unwrap();
+        let rules = bootstrap::rules();
+        let mut tokenizer = Tokenizer::with_memo();
+        assert_eq!(parse_with_tokenizer(&mut tokenizer, &rules, &source),
+            parse(&rules, &source));
+        assert!(tokenizer.memo.unwrap().misses > 0);
+    }
+
+    #[test]
+    fn invalidate() {
+        let mut memo = Memo::new();
+        let err = (Range::empty(0), ParseError::ExpectedWhitespace(0));
+        memo.insert(0, 0, 0, 2, MemoEntry::Err(err.clone()));
+        memo.insert(0, 2, 0, 4, MemoEntry::Err(err.clone()));
+        memo.insert(0, 4, 0, 6, MemoEntry::Err(err));
+        memo.invalidate(3);
+        assert_eq!(memo.len(), 2);
+        assert!(memo.get(0, 2, 0).is_none());
+    }
+
+    #[test]
+    fn indent() {
+        let rules = r#"
+1 "a" ["x" ?i(@"a""c")]
+2 "document" {[i(["y" w? @"a""a" "FAIL"])] [w? "y" w? @"a""a"]}
+        "#;
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        // The first line of the block continues on a line that is not indented.
+        let text = "\n  y\nx\n  x";
+        let mut tokenizer = Tokenizer::with_memo();
+        let res = parse_with_tokenizer(&mut tokenizer, &rules, text);
+        assert_eq!(res, parse(&rules, text));
+        assert_eq!(res.unwrap().len(), 4);
+    }
+}
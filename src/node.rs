@@ -1,11 +1,14 @@
 use range::Range;
-use std::rc::Rc;
-use std::cell::Cell;
+use std::sync::Arc;
+use std::cmp::max;
 
 use {
+    compose,
+    err_update,
     ret_err,
     update,
     DebugId,
+    MemoEntry,
     MetaData,
     ParseError,
     ParseResult,
@@ -18,13 +21,13 @@ use {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Node {
     /// Name of rule.
-    pub name: Rc<String>,
+    pub name: Arc<String>,
     /// The property to set.
-    pub property: Option<Rc<String>>,
+    pub property: Option<Arc<String>>,
     /// A debug id to track down the rule generating an error.
     pub debug_id: DebugId,
     /// The index to the rule reference.
-    pub index: Cell<Option<usize>>,
+    pub index: Option<usize>,
 }
 
 impl Node {
@@ -33,12 +36,11 @@ impl Node {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        mut chars: &[char],
-        start_offset: usize,
-        refs: &[(Rc<String>, Rule)]
+        chars: &str,
+        offset: usize,
+        refs: &[(Arc<String>, Rule)]
     ) -> ParseResult<TokenizerState> {
-        let mut offset = start_offset;
-        let index = match self.index.get() {
+        let index = match self.index {
             None => {
                 return Err((
                     Range::empty(offset),
@@ -50,53 +52,204 @@ impl Node {
             }
             Some(i) => i
         };
-        let mut state = if let Some(ref prop) = self.property {
+        parse_node(index, self.debug_id, self.property.as_ref(),
+            tokenizer, state, chars, offset,
+            &mut |tokenizer, state, chars, offset| {
+                refs[index].1.parse(tokenizer, state, chars, offset, refs)
+            })
+    }
+
+    /// Composes node.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        mut data: &[(Range, MetaData)],
+        start_offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> Result<Range, ()> {
+        let mut offset = start_offset;
+        let index = try!(self.index.ok_or(()));
+        if let Some(ref prop) = self.property {
+            let range = try!(compose::start_node(prop, data, offset));
+            compose::update(range, &mut data, &mut offset);
+        }
+        let range = try!(refs[index].1.compose(out, data, offset, refs));
+        compose::update(range, &mut data, &mut offset);
+        if let Some(ref prop) = self.property {
+            let range = try!(compose::end_node(prop, data, offset));
+            compose::update(range, &mut data, &mut offset);
+        }
+        Ok(Range::new(start_offset, offset - start_offset))
+    }
+}
+
+/// Parses a node, using a function to parse the referenced rule.
+/// Sets the property to the meta data of the rule, if any.
+pub fn parse_node<F>(
+    index: usize,
+    debug_id: DebugId,
+    property: Option<&Arc<String>>,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    mut chars: &str,
+    start_offset: usize,
+    f: &mut F
+) -> ParseResult<TokenizerState>
+    where F: FnMut(&mut Tokenizer, &TokenizerState, &str, usize)
+        -> ParseResult<TokenizerState>
+{
+    let mut offset = start_offset;
+    let mut state = if let Some(prop) = property {
+        tokenizer.data(
+            MetaData::StartNode(prop.clone()),
+            state,
+            Range::empty(offset)
+        )
+    } else {
+        state.clone()
+    };
+    let mut opt_error = None;
+    try!(tokenizer.enter_node(debug_id, offset));
+    let res = parse_ref(index, debug_id, tokenizer, &state, chars, offset, f);
+    tokenizer.exit_node();
+    state = match res {
+        Err(err) => { return Err(ret_err(err, opt_error)); }
+        Ok((range, state, err)) => {
+            update(range, err, &mut chars, &mut offset, &mut opt_error);
+            state
+        }
+    };
+    let range = Range::new(start_offset, offset - start_offset);
+    Ok((
+        range,
+        if let Some(prop) = property {
             tokenizer.data(
-                MetaData::StartNode(prop.clone()),
-                state,
-                Range::empty(offset)
+                MetaData::EndNode(prop.clone()),
+                &state,
+                range
             )
         } else {
             state.clone()
-        };
-        let mut opt_error = None;
-        state = match refs[index].1.parse(
-            tokenizer, &state, chars, offset, refs
-        ) {
-            Err(err) => { return Err(ret_err(err, opt_error)); }
-            Ok((range, state, err)) => {
-                update(range, err, &mut chars, &mut offset, &mut opt_error);
-                state
+        },
+        opt_error
+    ))
+}
+
+/// Parses the referenced rule.
+/// Replays the result from the memo table, if enabled and found.
+/// Grows the result if the rule is left recursive.
+fn parse_ref<F>(
+    index: usize,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    f: &mut F
+) -> ParseResult<TokenizerState>
+    where F: FnMut(&mut Tokenizer, &TokenizerState, &str, usize)
+        -> ParseResult<TokenizerState>
+{
+    let indent = if tokenizer.memo.is_some() { tokenizer.block_indent(offset) } else { 0 };
+    let entry = match tokenizer.memo {
+        None => None,
+        Some(ref mut memo) => memo.get(index, offset, indent)
+            .map(|(examined, entry)| (examined, entry.clone()))
+    };
+    if let Some((examined, entry)) = entry {
+        tokenizer.examine(examined);
+        return replay(entry, tokenizer, state);
+    }
+    match tokenizer.left_recursion.enter(index, offset) {
+        Ok(()) => {}
+        Err(None) => {
+            return Err((Range::empty(offset),
+                ParseError::LeftRecursion(debug_id)));
+        }
+        Err(Some(seed)) => {
+            return replay(seed, tokenizer, state);
+        }
+    }
+    // Track how far this rule examines the text separately.
+    let outer_examined = tokenizer.examined;
+    tokenizer.examined = offset;
+    let mut res = f(tokenizer, state, chars, offset);
+    if tokenizer.left_recursion.exit(index, offset) {
+        loop {
+            let seed = match res {
+                Ok((range, ref s, ref err)) => MemoEntry::Ok(range,
+                    tokenizer.tokens[state.0..s.0].to_vec(), err.clone()),
+                Err(_) => break
+            };
+            tokenizer.left_recursion.grow(index, offset, seed.clone());
+            let next = f(tokenizer, state, chars, offset);
+            tokenizer.left_recursion.exit(index, offset);
+            match (&next, &seed) {
+                (&Ok((range, _, _)), &MemoEntry::Ok(seed_range, _, _))
+                if range.next_offset() > seed_range.next_offset() => {}
+                _ => {
+                    // Stop when the rule does not read further,
+                    // but keep the error of the last attempt.
+                    let err = match next {
+                        Ok((_, _, err)) => err,
+                        Err(err) => Some(err),
+                    };
+                    res = replay(seed, tokenizer, state)
+                        .map(|(range, state, mut opt_error)| {
+                            err_update(err, &mut opt_error);
+                            (range, state, opt_error)
+                        });
+                    break;
+                }
             }
+            res = next;
+        }
+        tokenizer.left_recursion.finish();
+    }
+    let examined = tokenizer.examined;
+    if tokenizer.memo.is_some() && !tokenizer.left_recursion.is_growing() {
+        let entry = match res {
+            Ok((range, ref s, ref err)) => MemoEntry::Ok(range,
+                tokenizer.tokens[state.0..s.0].to_vec(), err.clone()),
+            Err(ref err) => MemoEntry::Err(err.clone()),
         };
-        let range = Range::new(start_offset, offset - start_offset);
-        Ok((
-            range,
-            if let Some(ref prop) = self.property {
-                tokenizer.data(
-                    MetaData::EndNode(prop.clone()),
-                    &state,
-                    range
-                )
-            } else {
-                state.clone()
-            },
-            opt_error
-        ))
+        if let Some(ref mut memo) = tokenizer.memo {
+            memo.insert(index, offset, indent, examined, entry);
+        }
+    }
+    tokenizer.examined = max(outer_examined, examined);
+    res
+}
+
+/// Replays a parse result.
+fn replay(
+    entry: MemoEntry,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState
+) -> ParseResult<TokenizerState> {
+    match entry {
+        MemoEntry::Ok(range, data, err) => {
+            let mut state = state.clone();
+            for (r, d) in data {
+                state = tokenizer.data(d, &state, r);
+            }
+            Ok((range, state, err))
+        }
+        MemoEntry::Err(err) => Err(err),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::*;
-    use std::rc::Rc;
-    use std::cell::Cell;
+    use range::Range;
+    use std::sync::Arc;
 
     #[test]
     fn node_ref() {
         // Create a node rule the refers to itself.
-        let foo: Rc<String> = Rc::new("foo".into());
-        let num: Rc<String> = Rc::new("num".into());
+        let foo: Arc<String> = Arc::new("foo".into());
+        let num: Arc<String> = Arc::new("num".into());
         let node = Rule::Sequence(Sequence {
             debug_id: 1,
             args: vec![
@@ -104,6 +257,10 @@ mod tests {
                     debug_id: 2,
                     property: Some(num.clone()),
                     allow_underscore: false,
+                    kind: NumberKind::F64,
+                    allow_radix: false,
+                    require_sign: false,
+                    allow_special: false,
                 }),
                 Rule::Optional(Box::new(Optional {
                     debug_id: 3,
@@ -112,13 +269,14 @@ mod tests {
                         args: vec![
                             Rule::Whitespace(Whitespace {
                                 debug_id: 3,
-                                optional: false
+                                optional: false,
+                                comments: None,
                             }),
                             Rule::Node(Node {
                                 name: foo.clone(),
                                 property: Some(foo.clone()),
                                 debug_id: 3,
-                                index: Cell::new(None),
+                                index: None,
                             }),
                         ]
                     }),
@@ -131,13 +289,13 @@ mod tests {
             name: foo.clone(),
             property: Some(foo.clone()),
             debug_id: 0,
-            index: Cell::new(None),
+            index: None,
         });
-        let rules = vec![
+        let mut rules = vec![
             (foo.clone(), node),
-            (Rc::new("".into()), rule)
+            (Arc::new("".into()), rule)
         ];
-        update_refs(&rules);
+        update_refs(&mut rules);
 
         let text = "1 2 3";
         let data = parse(&rules, text).unwrap();
@@ -152,4 +310,79 @@ mod tests {
         assert_eq!(&data[7].1, &MetaData::EndNode(foo.clone()));
         assert_eq!(&data[8].1, &MetaData::EndNode(foo.clone()));
     }
+
+    #[test]
+    fn left_recursion() {
+        let rules = r#"
+1 "term" $"val"
+2 "expr" {[@"expr""left" w? "+" w? @"term""right"] @"term""term"}
+3 "document" [w? @"expr""expr" w?]
+        "#;
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let expr: Arc<String> = Arc::new("expr".into());
+        let left: Arc<String> = Arc::new("left".into());
+        let right: Arc<String> = Arc::new("right".into());
+        let term: Arc<String> = Arc::new("term".into());
+        let val: Arc<String> = Arc::new("val".into());
+        let text = "1 + 2 + 3";
+        let data = parse(&rules, text).unwrap();
+        let data: Vec<MetaData> = data.into_iter().map(|x| x.1).collect();
+        assert_eq!(data, vec![
+            MetaData::StartNode(expr.clone()),
+            MetaData::StartNode(left.clone()),
+            MetaData::StartNode(left.clone()),
+            MetaData::StartNode(term.clone()),
+            MetaData::F64(val.clone(), 1.0),
+            MetaData::EndNode(term.clone()),
+            MetaData::EndNode(left.clone()),
+            MetaData::StartNode(right.clone()),
+            MetaData::F64(val.clone(), 2.0),
+            MetaData::EndNode(right.clone()),
+            MetaData::EndNode(left.clone()),
+            MetaData::StartNode(right.clone()),
+            MetaData::F64(val.clone(), 3.0),
+            MetaData::EndNode(right.clone()),
+            MetaData::EndNode(expr.clone()),
+        ]);
+
+        // Memoization gives the same result.
+        let mut tokenizer = Tokenizer::with_memo();
+        assert_eq!(parse_with_tokenizer(&mut tokenizer, &rules, text),
+            parse(&rules, text));
+
+        assert_eq!(parse(&rules, "1 + "), Err((Range::new(4, 0),
+            ParseError::ExpectedNumber(1001))));
+    }
+
+    #[test]
+    fn indirect_left_recursion() {
+        let rules = r#"
+1 "a" {[@"b""b" "x"] "y"}
+2 "b" @"a""a"
+3 "document" @"a""a"
+        "#;
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let data = parse(&rules, "yxx").unwrap();
+        assert_eq!(data.len(), 10);
+        assert_eq!(data[9].0, Range::new(0, 3));
+    }
+
+    #[test]
+    fn no_progress() {
+        let rules = r#"
+1 "a" @"a"
+        "#;
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        assert_eq!(parse(&rules, ""), Err((Range::empty(0),
+            ParseError::LeftRecursion(1001))));
+    }
 }
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use {
+    DebugId,
+    Rule,
+};
+
+/// Returns the debug id of the first repetition of a rule that can succeed
+/// without reading anything, which would loop forever.
+///
+/// For `SeparatedBy` this requires both the rule and the separator
+/// to read nothing. Repetitions with a maximum count always stop.
+/// The references must be updated.
+pub fn infinite_loop(rules: &[(Arc<String>, Rule)]) -> Option<DebugId> {
+    // Finds the rules that read nothing, until no more are found.
+    let mut nullables = vec![false; rules.len()];
+    loop {
+        let mut changed = false;
+        for (i, r) in rules.iter().enumerate() {
+            if !nullables[i] && nullable(&r.1, &nullables) {
+                nullables[i] = true;
+                changed = true;
+            }
+        }
+        if !changed { break; }
+    }
+    rules.iter().filter_map(|r| find_loop(&r.1, &nullables)).next()
+}
+
+/// Returns `true` if a rule can succeed without reading anything.
+fn nullable(rule: &Rule, nullables: &[bool]) -> bool {
+    match rule {
+        &Rule::Whitespace(ref w) => w.optional,
+        &Rule::Token(ref t) => t.text.len() == 0,
+        &Rule::UntilAny(ref u) => u.optional,
+        &Rule::UntilAnyOrWhitespace(ref u) => u.optional,
+        &Rule::Text(_) => false,
+        &Rule::Number(_) => false,
+        &Rule::CharClass(ref c) => c.min == 0,
+        &Rule::Select(ref s) => s.args.iter().any(|r| nullable(r, nullables)),
+        &Rule::Sequence(ref s) => s.args.iter().all(|r| nullable(r, nullables)),
+        &Rule::SeparatedBy(ref s) => s.optional || nullable(&s.rule, nullables),
+        &Rule::Repeat(ref r) => r.optional || nullable(&r.rule, nullables),
+        &Rule::Lines(_) => true,
+        &Rule::Indent(_) => false,
+        &Rule::Node(ref n) => n.index.map(|i| nullables[i]).unwrap_or(false),
+        &Rule::Optional(_) => true,
+        &Rule::Lookahead(_) => true,
+    }
+}
+
+/// Returns the debug id of the first repetition of a nullable rule.
+fn find_loop(rule: &Rule, nullables: &[bool]) -> Option<DebugId> {
+    match rule {
+        &Rule::Whitespace(_) => None,
+        &Rule::Token(_) => None,
+        &Rule::UntilAny(_) => None,
+        &Rule::UntilAnyOrWhitespace(_) => None,
+        &Rule::Text(_) => None,
+        &Rule::Number(_) => None,
+        &Rule::CharClass(_) => None,
+        &Rule::Select(ref s) => {
+            s.args.iter().filter_map(|r| find_loop(r, nullables)).next()
+        }
+        &Rule::Sequence(ref s) => {
+            s.args.iter().filter_map(|r| find_loop(r, nullables)).next()
+        }
+        &Rule::SeparatedBy(ref s) => {
+            let bounded = s.count.and_then(|c| c.1).is_some();
+            if !bounded && nullable(&s.rule, nullables) && nullable(&s.by, nullables) {
+                Some(s.debug_id)
+            } else {
+                find_loop(&s.rule, nullables).or_else(|| find_loop(&s.by, nullables))
+            }
+        }
+        &Rule::Repeat(ref r) => {
+            let bounded = r.count.and_then(|c| c.1).is_some();
+            if !bounded && nullable(&r.rule, nullables) { Some(r.debug_id) }
+            else { find_loop(&r.rule, nullables) }
+        }
+        &Rule::Lines(ref l) => find_loop(&l.rule, nullables),
+        &Rule::Indent(ref i) => find_loop(&i.rule, nullables),
+        &Rule::Node(_) => None,
+        &Rule::Optional(ref o) => find_loop(&o.rule, nullables),
+        &Rule::Lookahead(ref l) => find_loop(&l.rule, nullables),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::infinite_loop;
+
+    fn convert(rules: &str) -> Result<Vec<(Arc<String>, Rule)>, ()> {
+        bootstrap::convert(&parse(&bootstrap::rules(), rules).unwrap(), &mut vec![])
+    }
+
+    #[test]
+    fn repeat() {
+        assert!(convert(r#"1 "rule" r?(w?)"#).is_err());
+        assert!(convert(r#"1 "rule" r?(?"x")"#).is_err());
+        assert!(convert(r#"1 "rule" r?([w? "x"])"#).is_ok());
+
+        let rules = convert(r#"
+1 "item" {"x" w?}
+2 "rule" [r?(@"item") "y"]
+        "#);
+        assert!(rules.is_err());
+    }
+
+    #[test]
+    fn separated_by() {
+        assert!(convert(r#"1 "rule" s?(w?){..","?}"#).is_err());
+        assert!(convert(r#"1 "rule" s?(w?){..","!}"#).is_ok());
+        assert!(convert(r#"1 "rule" s?(",") {..","?}"#).is_ok());
+    }
+
+    #[test]
+    fn bounded() {
+        // A maximum count stops the repetition.
+        let rules = convert(r#"1 "rule" [r{3}(?"x""x") s{0,2}(w?){..","?"y"}]"#).unwrap();
+        assert_eq!(infinite_loop(&rules), None);
+        let y: Arc<String> = Arc::new("y".into());
+        let res = parse(&rules, "x").unwrap();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[2], (Range::empty(1), MetaData::String(y, Arc::new("".into()))));
+        assert_eq!(parse(&rules, "").unwrap().len(), 2);
+        assert!(convert(r#"1 "rule" r{3,}(?"x")"#).is_err());
+        assert!(convert(r#"1 "rule" s{2,}(w?){..","?}"#).is_err());
+    }
+
+    #[test]
+    fn convert_with_error() {
+        use range::Range;
+
+        let data = parse(&bootstrap::rules(), r#"1 "rule" [r?(w?) "x"]"#).unwrap();
+        assert_eq!(bootstrap::convert_with_error(&data, &mut vec![]),
+            Err((Range::empty(0), ParseError::InfiniteLoop(1002))));
+        let data = vec![(Range::new(0, 1), MetaData::Bool(Arc::new("x".into()), true))];
+        assert_eq!(bootstrap::convert_with_error(&data, &mut vec![]),
+            Err((Range::new(0, 1), ParseError::InvalidMetaData)));
+    }
+
+    #[test]
+    fn syntax() {
+        let mut rules = convert(r#"1 "rule" r?("x")"#).unwrap();
+        assert_eq!(infinite_loop(&rules), None);
+        if let Rule::Repeat(ref mut r) = rules[0].1 {
+            r.rule = Rule::Optional(Box::new(Optional {
+                rule: r.rule.clone(),
+                debug_id: 5,
+            }));
+        }
+        let debug_id = rules[0].1.debug_id();
+        assert_eq!(Grammar::new(rules), Err(ParseError::InfiniteLoop(debug_id)));
+        assert_eq!(infinite_loop(&bootstrap::rules()), None);
+    }
+}
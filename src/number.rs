@@ -1,8 +1,9 @@
 use range::Range;
-use read_token;
-use std::rc::Rc;
+use read;
+use std::sync::Arc;
 
 use {
+    compose,
     DebugId,
     MetaData,
     ParseError,
@@ -15,11 +16,31 @@ use {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Number {
     /// The property to set.
-    pub property: Option<Rc<String>>,
+    pub property: Option<Arc<String>>,
     /// A debug id to track down the rule generating an error.
     pub debug_id: DebugId,
     /// Whether underscore is allowed as visible separator.
     pub allow_underscore: bool,
+    /// The kind of number to read.
+    pub kind: NumberKind,
+    /// Whether the prefixes `0x`, `0o` and `0b` are allowed
+    /// for hexadecimal, octal and binary numbers.
+    pub allow_radix: bool,
+    /// Whether the number must start with `+` or `-`.
+    pub require_sign: bool,
+    /// Whether `inf`, `infinity` and `NaN` are allowed for floats.
+    pub allow_special: bool,
+}
+
+/// The kind of number to read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberKind {
+    /// A floating point number, stored as `MetaData::F64`.
+    F64,
+    /// A signed integer, stored as `MetaData::I64`.
+    I64,
+    /// An unsigned integer, stored as `MetaData::U64`.
+    U64,
 }
 
 impl Number {
@@ -28,58 +49,192 @@ impl Number {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        chars: &[char],
+        chars: &str,
         offset: usize
     ) -> ParseResult<TokenizerState> {
-        let res = if self.allow_underscore {
-                read_token::underscore_number(chars, offset)
-            } else {
-                read_token::number(chars, offset)
-            };
-        if let Some(range) = res {
-            let mut text = String::with_capacity(range.length);
-            for c in chars.iter()
-                .take(range.length)
-                .filter(|&c| *c != '_')
-            {
-                text.push(*c);
-            }
-            match text.parse::<f64>() {
-                Err(err) => Err((range,
-                    ParseError::ParseFloatError(err, self.debug_id))),
-                Ok(val) => {
-                    if let Some(ref property) = self.property {
-                        Ok((range, tokenizer.data(
-                            MetaData::F64(property.clone(), val),
-                            state,
-                            range
-                        ), None))
-                    } else {
-                        Ok((range, state.clone(), None))
+        parse(self.kind, self.allow_underscore, self.allow_radix,
+            self.require_sign, self.allow_special, self.property.as_ref(),
+            self.debug_id, tokenizer, state, chars, offset)
+    }
+
+    /// Composes number.
+    /// Fails if the number can not be read back, for example `NaN`
+    /// when special floats are not allowed.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        data: &[(Range, MetaData)],
+        offset: usize
+    ) -> Result<Range, ()> {
+        let (range, mut text) = match self.property {
+            Some(ref property) => {
+                match self.kind {
+                    NumberKind::F64 => {
+                        let (range, val) = try!(compose::meta_f64(
+                            property, data, offset));
+                        if !self.allow_special && !val.is_finite() {
+                            return Err(());
+                        }
+                        (range, format!("{}", val))
+                    }
+                    NumberKind::I64 => {
+                        let (range, val) = try!(compose::meta_i64(
+                            property, data, offset));
+                        (range, format!("{}", val))
+                    }
+                    NumberKind::U64 => {
+                        let (range, val) = try!(compose::meta_u64(
+                            property, data, offset));
+                        (range, format!("{}", val))
                     }
                 }
             }
+            None => (Range::empty(offset), "0".into())
+        };
+        if self.require_sign && !text.starts_with('-') {
+            text.insert(0, '+');
+        }
+        out.push_str(&text);
+        Ok(range)
+    }
+}
+
+/// Parses number, like `Number`.
+/// This is used by generated code to avoid creating the rule each time.
+pub fn parse(
+    kind: NumberKind,
+    allow_underscore: bool,
+    allow_radix: bool,
+    require_sign: bool,
+    allow_special: bool,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    let sign = match chars.as_bytes().first() {
+        Some(&b'+') | Some(&b'-') => 1,
+        _ => 0
+    };
+    if require_sign && sign == 0 {
+        return Err((Range::new(offset, 0),
+            ParseError::ExpectedSign(debug_id)));
+    }
+    let radix = if allow_radix {
+            read::radix_prefix(&chars[sign..])
         } else {
-            return Err((Range::new(offset, 0),
-                ParseError::ExpectedNumber(self.debug_id)))
+            None
+        };
+    let special = if allow_special && kind == NumberKind::F64 {
+            read::special_float(&chars[sign..])
+        } else {
+            None
+        };
+    let (range, text, radix) = if let Some(radix) = radix {
+        let start = sign + 2;
+        let len = read::radix_digits(&chars[start..], radix,
+            allow_underscore);
+        let end = start + len;
+        // A letter or digit after the digits is not valid in the radix.
+        let next = chars[end..].chars().next()
+            .filter(|c| c.is_alphanumeric());
+        if len == 0 || next.is_some() {
+            let n = next.map(|c| c.len_utf8()).unwrap_or(0);
+            return Err((Range::new(offset + end, n),
+                ParseError::ExpectedRadixDigit(radix, debug_id)));
         }
+        let text: String = chars[..sign].chars()
+            .chain(chars[start..end].chars().filter(|&c| c != '_'))
+            .collect();
+        (Range::new(offset, end), text, radix)
+    } else if let Some(len) = special {
+        (Range::new(offset, sign + len), chars[..sign + len].to_owned(), 10)
+    } else {
+        let res = if allow_underscore {
+                read::underscore_number(chars, offset)
+            } else {
+                read::number(chars, offset)
+            };
+        let range = match res {
+            None => return Err((Range::new(offset, 0),
+                ParseError::ExpectedNumber(debug_id))),
+            Some(range) => range
+        };
+        let text: String = chars[..range.length].chars()
+            .filter(|&c| c != '_')
+            .collect();
+        if kind != NumberKind::F64 &&
+           text.contains(|c| c == '.' || c == 'e' || c == 'E') {
+            return Err((range, ParseError::ExpectedInteger(debug_id)));
+        }
+        (range, text, 10)
+    };
+    match value(kind, property, debug_id, &text, radix) {
+        Err(err) => Err((range, err)),
+        Ok(Some(data)) => Ok((range, tokenizer.data(data, state, range), None)),
+        Ok(None) => Ok((range, state.clone(), None)),
     }
 }
 
+/// Parses the text of a number without underscores or radix prefix.
+/// Returns the meta data to store, if there is a property.
+fn value(
+    kind: NumberKind,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    text: &str,
+    radix: u32
+) -> Result<Option<MetaData>, ParseError> {
+    Ok(match kind {
+        NumberKind::F64 => {
+            let val = if radix == 10 {
+                try!(text.parse::<f64>()
+                    .map_err(|err| ParseError::ParseFloatError(err, debug_id)))
+            } else {
+                let (negative, digits) = if text.starts_with('-') {
+                        (true, &text[1..])
+                    } else {
+                        (false, text)
+                    };
+                let val = try!(u64::from_str_radix(digits, radix)
+                    .map_err(|err| ParseError::ParseIntError(err, debug_id))) as f64;
+                if negative { -val } else { val }
+            };
+            property.map(|p| MetaData::F64(p.clone(), val))
+        }
+        NumberKind::I64 => {
+            let val = try!(i64::from_str_radix(text, radix)
+                .map_err(|err| ParseError::ParseIntError(err, debug_id)));
+            property.map(|p| MetaData::I64(p.clone(), val))
+        }
+        NumberKind::U64 => {
+            let val = try!(u64::from_str_radix(text, radix)
+                .map_err(|err| ParseError::ParseIntError(err, debug_id)));
+            property.map(|p| MetaData::U64(p.clone(), val))
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::*;
     use range::Range;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn expected_number() {
         let text = "foo";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let number = Number {
             debug_id: 0,
             property: None,
             allow_underscore: false,
+            kind: NumberKind::F64,
+            allow_radix: false,
+            require_sign: false,
+            allow_special: false,
         };
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
@@ -90,11 +245,15 @@ mod tests {
     #[test]
     fn successful() {
         let text = "foo 1 1.1 10e1 10.0E1 10_000";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let number = Number {
             debug_id: 0,
             property: None,
             allow_underscore: true,
+            kind: NumberKind::F64,
+            allow_radix: false,
+            require_sign: false,
+            allow_special: false,
         };
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
@@ -107,15 +266,142 @@ mod tests {
         let res = number.parse(&mut tokenizer, &s, &chars[22..], 22);
         assert_eq!(res, Ok((Range::new(22, 6), s, None)));
 
-        let val: Rc<String> = Rc::new("val".into());
+        let val: Arc<String> = Arc::new("val".into());
         let number = Number {
             debug_id: 0,
             property: Some(val.clone()),
             allow_underscore: false,
+            kind: NumberKind::F64,
+            allow_radix: false,
+            require_sign: false,
+            allow_special: false,
         };
         let res = number.parse(&mut tokenizer, &s, &chars[15..], 15);
         assert_eq!(res, Ok((Range::new(15, 6), TokenizerState(1), None)));
         assert_eq!(tokenizer.tokens.len(), 1);
         assert_eq!(&tokenizer.tokens[0].1, &MetaData::F64(val.clone(), 10.0e1));
     }
+
+    #[test]
+    fn integer() {
+        let text = "12 -3 1.5 2e3 99999999999999999999";
+        let val: Arc<String> = Arc::new("val".into());
+        let number = Number {
+            debug_id: 0,
+            property: Some(val.clone()),
+            allow_underscore: false,
+            kind: NumberKind::I64,
+            allow_radix: false,
+            require_sign: false,
+            allow_special: false,
+        };
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let res = number.parse(&mut tokenizer, &s, &text[3..], 3);
+        assert_eq!(res, Ok((Range::new(3, 2), TokenizerState(1), None)));
+        assert_eq!(&tokenizer.tokens[0].1, &MetaData::I64(val.clone(), -3));
+        let res = number.parse(&mut tokenizer, &s, &text[6..], 6);
+        assert_eq!(res, Err((Range::new(6, 3), ParseError::ExpectedInteger(0))));
+        let res = number.parse(&mut tokenizer, &s, &text[10..], 10);
+        assert_eq!(res, Err((Range::new(10, 3), ParseError::ExpectedInteger(0))));
+        let res = number.parse(&mut tokenizer, &s, &text[14..], 14);
+        match res {
+            Err((range, ParseError::ParseIntError(_, 0))) =>
+                assert_eq!(range, Range::new(14, 20)),
+            x => panic!("Expected overflow, got {:?}", x),
+        }
+
+        let number = Number { kind: NumberKind::U64, ..number };
+        let res = number.parse(&mut tokenizer, &s, text, 0);
+        assert_eq!(res, Ok((Range::new(0, 2), TokenizerState(1), None)));
+        assert_eq!(&tokenizer.tokens[0].1, &MetaData::U64(val.clone(), 12));
+        let res = number.parse(&mut tokenizer, &s, &text[3..], 3);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn radix() {
+        let text = "0xFF -0b1010 0o7_55 0x 0b102 0x1_0000_0000_0000_0000";
+        let val: Arc<String> = Arc::new("val".into());
+        let number = Number {
+            debug_id: 0,
+            property: Some(val.clone()),
+            allow_underscore: true,
+            kind: NumberKind::I64,
+            allow_radix: true,
+            require_sign: false,
+            allow_special: false,
+        };
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let res = number.parse(&mut tokenizer, &s, text, 0);
+        assert_eq!(res, Ok((Range::new(0, 4), TokenizerState(1), None)));
+        assert_eq!(&tokenizer.tokens[0].1, &MetaData::I64(val.clone(), 255));
+        number.parse(&mut tokenizer, &s, &text[5..], 5).unwrap();
+        assert_eq!(&tokenizer.tokens[0].1, &MetaData::I64(val.clone(), -10));
+        let res = number.parse(&mut tokenizer, &s, &text[13..], 13);
+        assert_eq!(res, Ok((Range::new(13, 6), TokenizerState(1), None)));
+        assert_eq!(&tokenizer.tokens[0].1, &MetaData::I64(val.clone(), 0o755));
+        let res = number.parse(&mut tokenizer, &s, &text[20..], 20);
+        assert_eq!(res, Err((Range::new(22, 0), ParseError::ExpectedRadixDigit(16, 0))));
+        let res = number.parse(&mut tokenizer, &s, &text[23..], 23);
+        assert_eq!(res, Err((Range::new(27, 1), ParseError::ExpectedRadixDigit(2, 0))));
+        match number.parse(&mut tokenizer, &s, &text[29..], 29) {
+            Err((range, ParseError::ParseIntError(_, 0))) =>
+                assert_eq!(range, Range::new(29, 23)),
+            x => panic!("Expected overflow, got {:?}", x),
+        }
+
+        let number = Number { kind: NumberKind::F64, ..number };
+        number.parse(&mut tokenizer, &s, &text[5..], 5).unwrap();
+        assert_eq!(&tokenizer.tokens[0].1, &MetaData::F64(val.clone(), -10.0));
+    }
+
+    #[test]
+    fn sign_and_special() {
+        let text = "+3 4 -inf NaN info nanny Infinity";
+        let val: Arc<String> = Arc::new("val".into());
+        let number = Number {
+            debug_id: 0,
+            property: Some(val.clone()),
+            allow_underscore: false,
+            kind: NumberKind::F64,
+            allow_radix: false,
+            require_sign: true,
+            allow_special: true,
+        };
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let res = number.parse(&mut tokenizer, &s, text, 0);
+        assert_eq!(res, Ok((Range::new(0, 2), TokenizerState(1), None)));
+        assert_eq!(&tokenizer.tokens[0].1, &MetaData::F64(val.clone(), 3.0));
+        let res = number.parse(&mut tokenizer, &s, &text[3..], 3);
+        assert_eq!(res, Err((Range::new(3, 0), ParseError::ExpectedSign(0))));
+        let res = number.parse(&mut tokenizer, &s, &text[5..], 5);
+        assert_eq!(res, Ok((Range::new(5, 4), TokenizerState(1), None)));
+        assert_eq!(&tokenizer.tokens[0].1,
+            &MetaData::F64(val.clone(), ::std::f64::NEG_INFINITY));
+
+        let number = Number { require_sign: false, ..number };
+        let res = number.parse(&mut tokenizer, &s, &text[10..], 10);
+        assert_eq!(res, Ok((Range::new(10, 3), TokenizerState(1), None)));
+        match tokenizer.tokens[0].1 {
+            MetaData::F64(_, x) => assert!(x.is_nan()),
+            ref x => panic!("Expected NaN, got {:?}", x),
+        }
+        let res = number.parse(&mut tokenizer, &s, &text[14..], 14);
+        assert_eq!(res, Err((Range::new(14, 0), ParseError::ExpectedNumber(0))));
+        let res = number.parse(&mut tokenizer, &s, &text[19..], 19);
+        assert_eq!(res, Err((Range::new(19, 0), ParseError::ExpectedNumber(0))));
+        let res = number.parse(&mut tokenizer, &s, &text[25..], 25);
+        assert_eq!(res, Ok((Range::new(25, 8), TokenizerState(1), None)));
+
+        let data = vec![(Range::empty(0), MetaData::F64(val.clone(), 2.0))];
+        let mut out = String::new();
+        Number { require_sign: true, ..number.clone() }.compose(&mut out, &data, 0).unwrap();
+        assert_eq!(out, "+2");
+        let data = vec![(Range::empty(0), MetaData::F64(val.clone(), ::std::f64::INFINITY))];
+        assert!(number.compose(&mut out, &data, 0).is_ok());
+        assert!(Number { allow_special: false, ..number }.compose(&mut out, &data, 0).is_err());
+    }
 }
@@ -1,10 +1,11 @@
 use range::Range;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use {
     ret_err,
     update,
     DebugId,
+    MetaData,
     ParseError,
     Rule,
     Tokenizer,
@@ -27,9 +28,9 @@ impl Optional {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        mut chars: &[char],
+        mut chars: &str,
         mut offset: usize,
-        refs: &[(Rc<String>, Rule)]
+        refs: &[(Arc<String>, Rule)]
     ) -> (Range, TokenizerState, Option<(Range, ParseError)>) {
         let start_offset = offset;
         let mut success_state = state.clone();
@@ -49,21 +50,40 @@ impl Optional {
         (Range::new(start_offset, offset - start_offset), success_state,
             opt_error)
     }
+
+    /// Composes optional.
+    /// Writes nothing if the sub rule does not match the meta data.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        data: &[(Range, MetaData)],
+        offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> Range {
+        let len = out.len();
+        match self.rule.compose(out, data, offset, refs) {
+            Ok(range) => range,
+            Err(()) => {
+                out.truncate(len);
+                Range::empty(offset)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::*;
     use range::Range;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn fail_but_continue() {
         let text = "2";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let num: Rc<String> = Rc::new("num".into());
+        let num: Arc<String> = Arc::new("num".into());
         // Will fail because text is expected first.
         let optional = Optional {
             debug_id: 0,
@@ -79,6 +99,10 @@ mod tests {
                         debug_id: 3,
                         property: Some(num.clone()),
                         allow_underscore: false,
+                        kind: NumberKind::F64,
+                        allow_radix: false,
+                        require_sign: false,
+                        allow_special: false,
                     })
                 ]
             }),
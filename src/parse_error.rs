@@ -1,24 +1,48 @@
 use std::fmt::{ Display, Formatter };
 use std::fmt::Error as FormatError;
-use std::num::ParseFloatError;
+use std::num::{ ParseFloatError, ParseIntError };
 use read_token::ParseStringError;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use DebugId;
 
 /// Errors reporting expected values.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ParseError {
     /// Whitespace is required.
     ExpectedWhitespace(DebugId),
+    /// Expected the end of a block comment.
+    ExpectedCommentEnd(DebugId),
     /// New line is required.
     ExpectedNewLine(DebugId),
+    /// Expected line indented deeper than the current block.
+    ExpectedIndent(DebugId),
+    /// Expected line at the indentation of the block.
+    UnexpectedIndent(DebugId),
+    /// Expected line to return to the indentation of the block,
+    /// or to the indentation of a block outside it.
+    InconsistentDedent(DebugId),
+    /// Expected indentation with only tabs or only spaces.
+    MixedIndentation(DebugId),
     /// Something is required.
     ExpectedSomething(DebugId),
     /// Expected number.
     ExpectedNumber(DebugId),
     /// Error when parsing float.
     ParseFloatError(ParseFloatError, DebugId),
+    /// Expected integer, found fraction or exponent.
+    ExpectedInteger(DebugId),
+    /// Error when parsing integer, for example when it overflows.
+    ParseIntError(ParseIntError, DebugId),
+    /// Expected `+` or `-` before number.
+    ExpectedSign(DebugId),
+    /// Expected digit in the radix after a prefix like `0x`.
+    ExpectedRadixDigit(u32, DebugId),
+    /// Expected a number of items within a count, but found fewer.
+    /// Stores the minimum, the maximum if any, and the number found.
+    ExpectedCount(usize, Option<usize>, usize, DebugId),
+    /// Expected character in class.
+    ExpectedCharClass(DebugId),
     /// Expected text.
     ExpectedText(DebugId),
     /// Empty text not allowed.
@@ -26,11 +50,29 @@ pub enum ParseError {
     /// Invalid string format.
     ParseStringError(ParseStringError, DebugId),
     /// Expected token.
-    ExpectedToken(Rc<String>, DebugId),
+    ExpectedToken(Arc<String>, DebugId),
     /// An invalid rule.
     InvalidRule(&'static str, DebugId),
+    /// A rule matched where it is not allowed by a negative lookahead.
+    UnexpectedMatch(DebugId),
+    /// A left recursive rule was reached again before reading anything.
+    LeftRecursion(DebugId),
+    /// A repeated rule succeeded without reading anything,
+    /// which would repeat forever.
+    InfiniteLoop(DebugId),
+    /// Nodes were nested deeper than the maximum depth of the tokenizer.
+    DepthLimit(DebugId),
+    /// More rules were parsed than the fuel of the tokenizer allows.
+    OutOfFuel(DebugId),
     /// No rules are specified.
     NoRules,
+    /// There is no rule with the name.
+    UnknownRule(Arc<String>),
+    /// The meta data does not describe rules.
+    InvalidMetaData,
+    /// The offset to start parsing at is outside the text
+    /// or not at a character boundary.
+    InvalidOffset(usize),
     /// Expected to reach the end.
     ExpectedEnd,
 }
@@ -41,9 +83,24 @@ impl Display for ParseError {
             &ParseError::ExpectedWhitespace(debug_id) =>
                 try!(write!(fmt, "#{}, Expected whitespace",
                     debug_id)),
+            &ParseError::ExpectedCommentEnd(debug_id) =>
+                try!(write!(fmt, "#{}, Expected end of comment",
+                    debug_id)),
             &ParseError::ExpectedNewLine(debug_id) =>
                 try!(write!(fmt, "#{}, Expected new line",
                     debug_id)),
+            &ParseError::ExpectedIndent(debug_id) =>
+                try!(write!(fmt, "#{}, Expected indented line",
+                    debug_id)),
+            &ParseError::UnexpectedIndent(debug_id) =>
+                try!(write!(fmt, "#{}, Expected line at the indentation of the block",
+                    debug_id)),
+            &ParseError::InconsistentDedent(debug_id) =>
+                try!(write!(fmt, "#{}, Expected dedent to the indentation of an outer block",
+                    debug_id)),
+            &ParseError::MixedIndentation(debug_id) =>
+                try!(write!(fmt, "#{}, Expected indentation with either tabs or spaces",
+                    debug_id)),
             &ParseError::ExpectedSomething(debug_id) =>
                 try!(write!(fmt, "#{}, Expected something",
                     debug_id)),
@@ -52,8 +109,30 @@ impl Display for ParseError {
             &ParseError::ParseFloatError(ref err, debug_id) =>
                 try!(write!(fmt, "#{}, Invalid number format: {}",
                     debug_id, err)),
+            &ParseError::ExpectedInteger(debug_id) =>
+                try!(write!(fmt, "#{}, Expected integer", debug_id)),
+            &ParseError::ParseIntError(ref err, debug_id) =>
+                try!(write!(fmt, "#{}, Invalid integer format: {}",
+                    debug_id, err)),
+            &ParseError::ExpectedSign(debug_id) =>
+                try!(write!(fmt, "#{}, Expected `+` or `-`", debug_id)),
+            &ParseError::ExpectedRadixDigit(radix, debug_id) =>
+                try!(write!(fmt, "#{}, Expected digit in base {}", debug_id, radix)),
+            &ParseError::ExpectedCount(min, max, n, debug_id) => match max {
+                Some(max) if max == min =>
+                    try!(write!(fmt, "#{}, Expected {} items, found {}",
+                        debug_id, min, n)),
+                Some(max) =>
+                    try!(write!(fmt, "#{}, Expected {} to {} items, found {}",
+                        debug_id, min, max, n)),
+                None =>
+                    try!(write!(fmt, "#{}, Expected at least {} items, found {}",
+                        debug_id, min, n)),
+            },
             &ParseError::ExpectedToken(ref token, debug_id) =>
                 try!(write!(fmt, "#{}, Expected: `{}`", debug_id, token)),
+            &ParseError::ExpectedCharClass(debug_id) =>
+                try!(write!(fmt, "#{}, Expected character in class", debug_id)),
             &ParseError::ExpectedText(debug_id) =>
                 try!(write!(fmt, "#{}, Expected text", debug_id)),
             &ParseError::EmptyTextNotAllowed(debug_id) =>
@@ -63,8 +142,27 @@ impl Display for ParseError {
                     debug_id, err)),
             &ParseError::InvalidRule(msg, debug_id) =>
                 try!(write!(fmt, "#{}, Invalid rule: {}", debug_id, msg)),
+            &ParseError::UnexpectedMatch(debug_id) =>
+                try!(write!(fmt, "#{}, Unexpected match", debug_id)),
+            &ParseError::LeftRecursion(debug_id) =>
+                try!(write!(fmt, "#{}, Left recursion", debug_id)),
+            &ParseError::InfiniteLoop(debug_id) =>
+                try!(write!(fmt, "#{}, Infinite loop, the repeated rule reads nothing",
+                    debug_id)),
+            &ParseError::DepthLimit(debug_id) =>
+                try!(write!(fmt, "#{}, Nodes are nested too deeply", debug_id)),
+            &ParseError::OutOfFuel(debug_id) =>
+                try!(write!(fmt, "#{}, Out of fuel, too many rules were parsed",
+                    debug_id)),
             &ParseError::NoRules =>
                 try!(write!(fmt, "No rules are specified")),
+            &ParseError::UnknownRule(ref name) =>
+                try!(write!(fmt, "Could not find rule `{}`", name)),
+            &ParseError::InvalidMetaData =>
+                try!(write!(fmt, "Meta data does not describe rules")),
+            &ParseError::InvalidOffset(offset) =>
+                try!(write!(fmt, "Offset {} is not at a character boundary of the text",
+                    offset)),
             &ParseError::ExpectedEnd =>
                 try!(write!(fmt, "Expected end")),
         }
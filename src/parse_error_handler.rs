@@ -1,6 +1,9 @@
 use range::Range;
 
-use ParseError;
+use {
+    ParseError,
+    SourceMap,
+};
 
 /// Implemented by error handlers.
 pub trait ParseErrorHandler {
@@ -10,23 +13,14 @@ pub trait ParseErrorHandler {
 
 /// Reports error to standard error output.
 pub struct ParseStdErr<'a> {
-    lines: Vec<(Range, &'a str)>,
+    source_map: SourceMap<'a>,
 }
 
 impl<'a> ParseStdErr<'a> {
     /// Creates a new error handler for standard error output.
     pub fn new(text: &'a str) -> ParseStdErr<'a> {
-        let mut start = 0;
-        let mut lines = vec![];
-        for line in text.split('\n') {
-            let length = line.len();
-            lines.push((Range::new(start, length), line));
-            // Lines are separated by '\n'.
-            start += length + 1;
-        }
-
         ParseStdErr {
-            lines: lines,
+            source_map: SourceMap::new(text),
         }
     }
 }
@@ -41,7 +35,8 @@ impl<'b> ParseErrorHandler for ParseStdErr<'b> {
             range: Range
         ) -> Option<(usize, Range)> {
             let mut first_line = None;
-            for (i, &(r, _)) in err_handler.lines.iter().enumerate() {
+            let lines = err_handler.source_map.lines();
+            for (i, &(r, _)) in lines.iter().enumerate() {
                 if let Some(intersect) = range.ends_intersect(&r) {
                     first_line = Some((i, intersect));
                     break;
@@ -50,6 +45,7 @@ impl<'b> ParseErrorHandler for ParseStdErr<'b> {
             first_line
         }
 
+        let lines = self.source_map.lines();
         let mut stderr = stderr();
         writeln!(&mut stderr, "Error {}", error).unwrap();
         if let &ParseError::ExpectedToken(_, _) = &error {
@@ -58,24 +54,26 @@ impl<'b> ParseErrorHandler for ParseStdErr<'b> {
             if let Some(first_line) = first_line(self, range) {
                 let mut prev_line = 0;
                 for (i, &(_, text)) in
-                    self.lines[..first_line.0].iter().enumerate().rev() {
+                    lines[..first_line.0].iter().enumerate().rev() {
                     prev_line = i;
                     if !text.chars()
                         .all(|c| { c.is_whitespace() }) { break; }
                 }
                 for (i, &(_, text)) in
-                    self.lines[prev_line .. first_line.0].iter().enumerate() {
+                    lines[prev_line .. first_line.0].iter().enumerate() {
                     writeln!(&mut stderr, "{}: {}",
                         i + prev_line + 1, text).unwrap();
                 }
             }
         }
-        for (i, &(r, text)) in self.lines.iter().enumerate() {
+        for (i, &(r, text)) in lines.iter().enumerate() {
             if let Some(intersect) = range.ends_intersect(&r) {
                 writeln!(&mut stderr, "{}: {}", i + 1, text).unwrap();
                 if intersect.offset >= r.offset {
                     write!(&mut stderr, "{}: ", i + 1).unwrap();
+                    // Ranges are in bytes, but the marker is in characters.
                     let i = intersect.offset - r.offset;
+                    let i = text.get(..i).map(|s| s.chars().count()).unwrap_or(i);
                     for _ in 0 .. i {
                         write!(&mut stderr, " ").unwrap();
                     }
@@ -0,0 +1,254 @@
+use range::Range;
+use std::collections::HashMap;
+use std::io::{ self, Write };
+use std::sync::Arc;
+use std::time::{ Duration, Instant };
+
+use tracer::kind;
+use {
+    DebugId,
+    ParseError,
+    Rule,
+    Tracer,
+};
+
+/// Statistics of a rule collected by a `Profiler`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProfileStats {
+    /// The number of times the rule was parsed.
+    pub calls: usize,
+    /// The number of times the rule succeeded.
+    pub successes: usize,
+    /// The number of times the rule failed.
+    pub failures: usize,
+    /// The number of bytes read when succeeding.
+    pub consumed: usize,
+    /// The number of bytes read before failing as an alternative
+    /// in a `Select` or `Optional`, which must be read again.
+    pub rescanned: usize,
+    /// The time spent parsing the rule, including its sub rules.
+    pub time: Duration,
+}
+
+impl ProfileStats {
+    fn add(&mut self, other: &ProfileStats) {
+        self.calls += other.calls;
+        self.successes += other.successes;
+        self.failures += other.failures;
+        self.consumed += other.consumed;
+        self.rescanned += other.rescanned;
+        self.time += other.time;
+    }
+}
+
+/// A rule that is being parsed.
+struct Frame {
+    debug_id: DebugId,
+    name: Option<Arc<String>>,
+    backtracks: bool,
+    offset: usize,
+    start: Instant,
+}
+
+/// Collects statistics per rule name and per debug id.
+///
+/// Pass it to `parse_with_tracer` and call `write` with the same rules
+/// to print a table.
+/// A rule name is counted each time a node refers to it.
+pub struct Profiler {
+    stack: Vec<Frame>,
+    names: HashMap<Arc<String>, ProfileStats>,
+    debug_ids: HashMap<DebugId, (&'static str, ProfileStats)>,
+}
+
+impl Profiler {
+    /// Creates a new profiler.
+    pub fn new() -> Profiler {
+        Profiler {
+            stack: vec![],
+            names: HashMap::new(),
+            debug_ids: HashMap::new(),
+        }
+    }
+
+    /// Returns the statistics of a rule name.
+    pub fn name(&self, name: &str) -> Option<&ProfileStats> {
+        self.names.iter().find(|&(n, _)| &***n == name).map(|(_, s)| s)
+    }
+
+    /// Returns the statistics of a debug id.
+    pub fn debug_id(&self, debug_id: DebugId) -> Option<&ProfileStats> {
+        self.debug_ids.get(&debug_id).map(|&(_, ref s)| s)
+    }
+
+    /// Writes a table of the rule names, followed by a table of the debug ids.
+    /// The rows are sorted by time, the slowest first.
+    ///
+    /// The rules are used to name rules that no node refers to,
+    /// such as the last rule, and to tell which rule a debug id belongs to.
+    pub fn write<W: Write>(
+        &self,
+        rules: &[(Arc<String>, Rule)],
+        out: &mut W
+    ) -> io::Result<()> {
+        let mut names: Vec<_> = self.names.iter()
+            .map(|(n, s)| (format!("{:?}", n), s)).collect();
+        for r in rules {
+            if self.names.contains_key(&r.0) { continue; }
+            if let Some(&(_, ref s)) = self.debug_ids.get(&r.1.debug_id()) {
+                names.push((format!("{:?}", r.0), s));
+            }
+        }
+        names.sort_by(|a, b| b.1.time.cmp(&a.1.time).then(a.0.cmp(&b.0)));
+        try!(write_table(out, "rule", &names));
+        try!(writeln!(out, ""));
+        let mut ids: Vec<_> = self.debug_ids.iter()
+            .map(|(&id, &(kind, ref s))| {
+                match rules.iter().find(|r| contains(&r.1, id)) {
+                    Some(r) => (format!("{} {} in {:?}", id, kind, r.0), s),
+                    None => (format!("{} {}", id, kind), s),
+                }
+            }).collect();
+        ids.sort_by(|a, b| b.1.time.cmp(&a.1.time).then(a.0.cmp(&b.0)));
+        write_table(out, "debug id", &ids)
+    }
+
+    fn exit(&mut self, mut stats: ProfileStats) {
+        let frame = self.stack.pop().expect("Expected `enter` before exit");
+        stats.calls = 1;
+        stats.time = frame.start.elapsed();
+        if let Some(name) = frame.name {
+            self.names.entry(name).or_insert_with(ProfileStats::default).add(&stats);
+        }
+        self.debug_ids.get_mut(&frame.debug_id).unwrap().1.add(&stats);
+    }
+}
+
+impl Tracer for Profiler {
+    fn enter(&mut self, rule: &Rule, debug_id: DebugId, offset: usize) {
+        let backtracks = match self.stack.last() {
+            Some(parent) => parent.backtracks,
+            None => false,
+        };
+        self.stack.push(Frame {
+            debug_id: debug_id,
+            name: if let &Rule::Node(ref n) = rule { Some(n.name.clone()) } else { None },
+            backtracks: backtracks,
+            offset: offset,
+            start: Instant::now(),
+        });
+        // Sub rules of a `Select` or `Optional` are read again when failing.
+        match rule {
+            &Rule::Select(_) | &Rule::Optional(_) => {
+                self.stack.last_mut().unwrap().backtracks = true;
+            }
+            _ => {}
+        }
+        self.debug_ids.entry(debug_id)
+            .or_insert_with(|| (kind(rule), ProfileStats::default()));
+    }
+
+    fn success(&mut self, range: Range) {
+        self.exit(ProfileStats {
+            successes: 1,
+            consumed: range.length,
+            ..ProfileStats::default()
+        });
+    }
+
+    fn fail(&mut self, range: Range, _error: &ParseError) {
+        let rescanned = {
+            let frame = self.stack.last().expect("Expected `enter` before exit");
+            let parent_backtracks = self.stack.len() > 1 &&
+                self.stack[self.stack.len() - 2].backtracks;
+            if parent_backtracks && range.offset > frame.offset {
+                range.offset - frame.offset
+            } else {
+                0
+            }
+        };
+        self.exit(ProfileStats {
+            failures: 1,
+            rescanned: rescanned,
+            ..ProfileStats::default()
+        });
+    }
+}
+
+/// Returns `true` if a rule or one of its sub rules has the debug id.
+fn contains(rule: &Rule, debug_id: DebugId) -> bool {
+    if rule.debug_id() == debug_id { return true; }
+    match rule {
+        &Rule::Select(ref s) => s.args.iter().any(|r| contains(r, debug_id)),
+        &Rule::Sequence(ref s) => s.args.iter().any(|r| contains(r, debug_id)),
+        &Rule::SeparatedBy(ref s) => {
+            contains(&s.rule, debug_id) || contains(&s.by, debug_id)
+        }
+        &Rule::Repeat(ref r) => contains(&r.rule, debug_id),
+        &Rule::Lines(ref l) => contains(&l.rule, debug_id),
+        &Rule::Indent(ref i) => contains(&i.rule, debug_id),
+        &Rule::Optional(ref o) => contains(&o.rule, debug_id),
+        &Rule::Lookahead(ref l) => contains(&l.rule, debug_id),
+        _ => false
+    }
+}
+
+/// Writes a table of statistics.
+fn write_table<W: Write>(
+    out: &mut W,
+    title: &str,
+    rows: &[(String, &ProfileStats)]
+) -> io::Result<()> {
+    let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0).max(title.len());
+    try!(writeln!(out, "{:<w$} {:>8} {:>8} {:>8} {:>10} {:>10} {:>10}",
+        title, "calls", "ok", "fail", "consumed", "rescanned", "time (us)", w = width));
+    for &(ref name, stats) in rows {
+        let micros = stats.time.as_secs() * 1_000_000 +
+            stats.time.subsec_nanos() as u64 / 1_000;
+        try!(writeln!(out, "{:<w$} {:>8} {:>8} {:>8} {:>10} {:>10} {:>10}",
+            name, stats.calls, stats.successes, stats.failures,
+            stats.consumed, stats.rescanned, micros, w = width));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    #[test]
+    fn backtracking() {
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), r#"
+1 "ab" ["a" "b"]
+2 "ac" ["a" "c"]
+3 "rule" r!({@"ab" @"ac"})
+            "#).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let mut profiler = Profiler::new();
+        assert!(parse_with_tracer(&rules, "acac", &mut profiler).is_ok());
+
+        let ab = profiler.name("ab").unwrap();
+        assert_eq!((ab.calls, ab.successes, ab.failures), (3, 0, 3));
+        // The last time, "a" is not found at the end.
+        assert_eq!(ab.rescanned, 2);
+        let ac = profiler.name("ac").unwrap();
+        assert_eq!((ac.calls, ac.successes, ac.failures), (3, 2, 1));
+        assert_eq!((ac.consumed, ac.rescanned), (4, 0));
+        assert!(profiler.name("rule").is_none());
+
+        // The sequence of "ab".
+        let seq = profiler.debug_id(rules[0].1.debug_id()).unwrap();
+        assert_eq!((seq.calls, seq.failures, seq.rescanned), (3, 3, 2));
+
+        let mut out = vec![];
+        profiler.write(&rules, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("rule "));
+        assert!(out.contains("\n\"ac\" "));
+        // The last rule is not referred to by a node.
+        assert!(out.contains("\n\"rule\" "));
+        assert!(out.contains(&format!("\n{} sequence in \"ab\" ", rules[0].1.debug_id())));
+    }
+}
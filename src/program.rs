@@ -0,0 +1,695 @@
+use range::Range;
+use read;
+use std::mem;
+use std::sync::Arc;
+
+use {
+    ends_with_new_line,
+    parse_with_tokenizer,
+    CharClass,
+    Comments,
+    DebugId,
+    MetaData,
+    Number,
+    ParseError,
+    Rule,
+    Text,
+    Token,
+    Tokenizer,
+    TokenizerState,
+    UntilAny,
+    UntilAnyOrWhitespace,
+    Whitespace,
+};
+
+/// An instruction of a compiled program.
+///
+/// Addresses are indices into the instructions of the program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instruction {
+    /// Counts a rule with the debug id, using fuel of the tokenizer.
+    Step(DebugId),
+    /// Read whitespace.
+    Whitespace(Whitespace),
+    /// Match against a token.
+    Token(Token),
+    /// Reads until any character.
+    UntilAny(UntilAny),
+    /// Read until any character or whitespace.
+    UntilAnyOrWhitespace(UntilAnyOrWhitespace),
+    /// Read text.
+    Text(Text),
+    /// Read number.
+    Number(Number),
+    /// Read characters in a class.
+    CharClass(CharClass),
+    /// Saves the current position,
+    /// such that the next failure continues from the address.
+    Choice(usize),
+    /// Removes the last saved position and jumps to the address.
+    Commit(usize),
+    /// Jumps to the address.
+    Jump(usize),
+    /// Fails, continuing from the last saved position.
+    Fail,
+    /// Calls the rule with the index, entering a node with the debug id.
+    Call(usize, DebugId),
+    /// Returns from a rule, exiting the node.
+    Return,
+    /// Stops successfully.
+    End,
+    /// Starts a node and remembers the offset.
+    StartNode(Arc<String>),
+    /// Ends the node started at the last remembered offset.
+    EndNode(Arc<String>),
+    /// Remembers the offset.
+    Mark,
+    /// Pushes a flag of a repetition.
+    /// For `Repeat` and `SeparatedBy` it tells whether it is the first item.
+    /// For `Lines` it tells whether a new line was read.
+    Push(bool),
+    /// Sets the last flag.
+    Set(bool),
+    /// Fails if the last flag has the value.
+    FailIf(bool),
+    /// Removes the last flag.
+    Pop,
+    /// Removes the last remembered offset.
+    /// Stops the program if nothing was read since,
+    /// because a repetition would loop forever.
+    Progress,
+    /// Skips whitespace and comments on the line.
+    /// Jumps to the address at the end of the text.
+    /// Reads the next line when it reaches a new line, setting the last flag.
+    LineSpace(usize, Option<Arc<Comments>>),
+    /// Removes the last saved position and the last remembered offset,
+    /// sets the last flag to whether the text read ends with a new line,
+    /// and jumps to the address.
+    LinesCommit(usize),
+    /// Parses with a rule instead of instructions.
+    /// Used for indented blocks, since the indentation is kept by the tokenizer,
+    /// and for repetitions with a count.
+    Rule(Rule),
+}
+
+/// Stores rules compiled into a flat list of instructions.
+///
+/// The program is run in a loop instead of calling rules recursively,
+/// and gives the same meta data as parsing with the rules.
+/// Errors are reported by parsing again with the rules,
+/// since the program does not keep track of the deepest error.
+/// This is also done for left recursive rules,
+/// and when the fuel or maximum depth of the tokenizer is reached.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+    /// The instructions.
+    pub instructions: Vec<Instruction>,
+    /// The address of each rule.
+    pub entries: Vec<usize>,
+    rules: Vec<(Arc<String>, Rule)>,
+}
+
+/// A saved position to continue from when failing.
+struct Backtrack {
+    address: usize,
+    offset: usize,
+    tokens: usize,
+    calls: usize,
+    marks: usize,
+}
+
+/// A rule in progress.
+struct Call {
+    ret: usize,
+    index: usize,
+    offset: usize,
+}
+
+impl Program {
+    /// Compiles rules into a program.
+    /// The references must be updated.
+    pub fn new(rules: &[(Arc<String>, Rule)]) -> Program {
+        let mut instructions = vec![Instruction::End];
+        let mut entries = vec![];
+        for r in rules {
+            entries.push(instructions.len());
+            compile(&r.1, &mut instructions);
+            instructions.push(Instruction::Return);
+        }
+        Program {
+            instructions: instructions,
+            entries: entries,
+            rules: rules.to_vec(),
+        }
+    }
+
+    /// Parses text with the last rule.
+    pub fn parse(&self, text: &str)
+    -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
+        self.parse_with_tokenizer(&mut Tokenizer::new(), text)
+    }
+
+    /// Parses text with the last rule, using the limits of a tokenizer.
+    ///
+    /// Rules are counted and nodes are entered like when parsing with the rules,
+    /// such that the program stops within the same fuel and maximum depth.
+    /// When the tokenizer has a tracer, parses with the rules,
+    /// since the program does not notify it.
+    pub fn parse_with_tokenizer(&self, tokenizer: &mut Tokenizer, text: &str)
+    -> Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
+        let n = match self.entries.len() {
+            0 => { return Err((Range::empty(0), ParseError::NoRules)); }
+            x => x
+        };
+        if tokenizer.tracer.is_none() {
+            if let Some(ref mut memo) = tokenizer.memo { memo.clear(); }
+            tokenizer.tokens.clear();
+            tokenizer.examined = 0;
+            tokenizer.depth = 0;
+            tokenizer.steps = 0;
+            tokenizer.limit = None;
+            tokenizer.indent = 0;
+            tokenizer.read_lines(text);
+            let res = self.run(tokenizer, n - 1, text);
+            if tokenizer.limit.is_none() && res == Some(text.len()) {
+                return Ok(mem::replace(&mut tokenizer.tokens, vec![]));
+            }
+        }
+        // Report the same error as the rules.
+        parse_with_tokenizer(tokenizer, &self.rules, text)
+    }
+
+    /// Runs the program from a rule.
+    /// Returns the offset where it stopped reading,
+    /// or `None` if it failed, found left recursion or reached a limit.
+    fn run(&self, tokenizer: &mut Tokenizer, index: usize, text: &str)
+    -> Option<usize> {
+        let mut address = self.entries[index];
+        let mut offset = 0;
+        let mut backtrack: Vec<Backtrack> = vec![];
+        let mut calls = vec![Call { ret: 0, index: index, offset: 0 }];
+        let mut marks: Vec<usize> = vec![];
+        loop {
+            let chars = &text[offset..];
+            let state = TokenizerState(tokenizer.tokens.len());
+            let res = match &self.instructions[address] {
+                &Instruction::Step(debug_id) => {
+                    if tokenizer.step(debug_id, offset).is_err() { return None; }
+                    Some(offset)
+                }
+                &Instruction::Whitespace(ref w) => {
+                    w.parse(chars, offset).map(|range| range.next_offset()).ok()
+                }
+                &Instruction::Token(ref t) => {
+                    t.read(chars, offset).map(|range| {
+                        if let Some(ref name) = t.property {
+                            tokenizer.tokens.push((range,
+                                MetaData::Bool(name.clone(), !t.inverted)));
+                        }
+                        range.next_offset()
+                    })
+                }
+                &Instruction::UntilAny(ref u) => {
+                    u.parse(tokenizer, &state, chars, offset)
+                        .map(|(range, _, _)| range.next_offset()).ok()
+                }
+                &Instruction::UntilAnyOrWhitespace(ref u) => {
+                    u.parse(tokenizer, &state, chars, offset)
+                        .map(|(range, _, _)| range.next_offset()).ok()
+                }
+                &Instruction::Text(ref t) => {
+                    t.parse(tokenizer, &state, chars, offset)
+                        .map(|(range, _, _)| range.next_offset()).ok()
+                }
+                &Instruction::Number(ref n) => {
+                    n.parse(tokenizer, &state, chars, offset)
+                        .map(|(range, _, _)| range.next_offset()).ok()
+                }
+                &Instruction::CharClass(ref c) => {
+                    c.parse(tokenizer, &state, chars, offset)
+                        .map(|(range, _, _)| range.next_offset()).ok()
+                }
+                &Instruction::Choice(next) => {
+                    backtrack.push(Backtrack {
+                        address: next,
+                        offset: offset,
+                        tokens: tokenizer.tokens.len(),
+                        calls: calls.len(),
+                        marks: marks.len(),
+                    });
+                    Some(offset)
+                }
+                &Instruction::Commit(next) => {
+                    backtrack.pop();
+                    address = next;
+                    continue;
+                }
+                &Instruction::Jump(next) => {
+                    address = next;
+                    continue;
+                }
+                &Instruction::Fail => None,
+                &Instruction::Call(i, debug_id) => {
+                    if tokenizer.enter_node(debug_id, offset).is_err() { return None; }
+                    // Rules in progress at the same offset are on top.
+                    for call in calls.iter().rev() {
+                        if call.offset != offset { break; }
+                        if call.index == i { return None; }
+                    }
+                    calls.push(Call { ret: address + 1, index: i, offset: offset });
+                    address = self.entries[i];
+                    continue;
+                }
+                &Instruction::Return => {
+                    // The first call did not enter a node.
+                    if calls.len() > 1 { tokenizer.exit_node(); }
+                    address = calls.pop().unwrap().ret;
+                    continue;
+                }
+                &Instruction::End => { return Some(offset); }
+                &Instruction::StartNode(ref name) => {
+                    tokenizer.tokens.push((Range::empty(offset),
+                        MetaData::StartNode(name.clone())));
+                    marks.push(offset);
+                    Some(offset)
+                }
+                &Instruction::EndNode(ref name) => {
+                    let start = marks.pop().unwrap();
+                    tokenizer.tokens.push((Range::new(start, offset - start),
+                        MetaData::EndNode(name.clone())));
+                    Some(offset)
+                }
+                &Instruction::Mark => {
+                    marks.push(offset);
+                    Some(offset)
+                }
+                &Instruction::Push(flag) => {
+                    marks.push(flag as usize);
+                    Some(offset)
+                }
+                &Instruction::Set(flag) => {
+                    *marks.last_mut().unwrap() = flag as usize;
+                    Some(offset)
+                }
+                &Instruction::FailIf(flag) => {
+                    if *marks.last().unwrap() == flag as usize { None }
+                    else { Some(offset) }
+                }
+                &Instruction::Pop => {
+                    marks.pop();
+                    Some(offset)
+                }
+                &Instruction::Progress => {
+                    if marks.pop().unwrap() == offset { return None; }
+                    Some(offset)
+                }
+                &Instruction::LineSpace(next, ref comments) => {
+                    let len = match comments {
+                        &None => read::line_whitespace_len(chars),
+                        &Some(ref comments) => match comments.whitespace_len(chars, false) {
+                            Ok(len) => len,
+                            Err(_) => return None,
+                        }
+                    };
+                    if len == chars.len() {
+                        offset += len;
+                        address = next;
+                        continue;
+                    } else if chars.as_bytes()[len] == b'\n' {
+                        // Read the next line with the same instruction.
+                        offset += len + 1;
+                        *marks.last_mut().unwrap() = 1;
+                        continue;
+                    }
+                    // The rule starts after the comments.
+                    if comments.is_some() { Some(offset + len) } else { Some(offset) }
+                }
+                &Instruction::LinesCommit(next) => {
+                    backtrack.pop();
+                    let start = marks.pop().unwrap();
+                    let new_lines = ends_with_new_line(&text[start..offset]);
+                    *marks.last_mut().unwrap() = new_lines as usize;
+                    address = next;
+                    continue;
+                }
+                &Instruction::Rule(ref r) => {
+                    r.parse(tokenizer, &state, chars, offset, &self.rules)
+                        .map(|(range, _, _)| range.next_offset()).ok()
+                }
+            };
+            match res {
+                Some(next_offset) => {
+                    offset = next_offset;
+                    address += 1;
+                }
+                None => {
+                    let b = match backtrack.pop() {
+                        None => { return None; }
+                        Some(b) => b
+                    };
+                    address = b.address;
+                    offset = b.offset;
+                    tokenizer.tokens.truncate(b.tokens);
+                    calls.truncate(b.calls);
+                    // Each call except the first entered a node.
+                    tokenizer.depth = b.calls - 1;
+                    marks.truncate(b.marks);
+                }
+            }
+        }
+    }
+}
+
+/// Sets the address of the instruction to the next address.
+fn patch(out: &mut Vec<Instruction>, at: usize) {
+    let next = out.len();
+    match &mut out[at] {
+        &mut Instruction::Choice(ref mut address) |
+        &mut Instruction::Commit(ref mut address) |
+        &mut Instruction::Jump(ref mut address) |
+        &mut Instruction::LineSpace(ref mut address, _) => *address = next,
+        _ => unreachable!()
+    }
+}
+
+/// Compiles a rule, adding the instructions to the output.
+fn compile(rule: &Rule, out: &mut Vec<Instruction>) {
+    match rule {
+        // The rule is counted when parsed.
+        &Rule::Repeat(ref r) if r.count.is_some() => {}
+        &Rule::SeparatedBy(ref s) if s.count.is_some() => {}
+        &Rule::Indent(_) => {}
+        _ => out.push(Instruction::Step(rule.debug_id())),
+    }
+    match rule {
+        &Rule::Whitespace(ref w) => out.push(Instruction::Whitespace(w.clone())),
+        &Rule::Token(ref t) => out.push(Instruction::Token(t.clone())),
+        &Rule::UntilAny(ref u) => out.push(Instruction::UntilAny(u.clone())),
+        &Rule::UntilAnyOrWhitespace(ref u) => {
+            out.push(Instruction::UntilAnyOrWhitespace(u.clone()))
+        }
+        &Rule::Text(ref t) => out.push(Instruction::Text(t.clone())),
+        &Rule::Number(ref n) => out.push(Instruction::Number(n.clone())),
+        &Rule::CharClass(ref c) => out.push(Instruction::CharClass(c.clone())),
+        &Rule::Select(ref s) => {
+            if s.args.len() == 0 {
+                out.push(Instruction::Fail);
+                return;
+            }
+            let mut commits = vec![];
+            let last = s.args.len() - 1;
+            for sub_rule in &s.args[..last] {
+                let choice = out.len();
+                out.push(Instruction::Choice(0));
+                compile(sub_rule, out);
+                commits.push(out.len());
+                out.push(Instruction::Commit(0));
+                patch(out, choice);
+            }
+            compile(&s.args[last], out);
+            for commit in commits { patch(out, commit); }
+        }
+        &Rule::Sequence(ref s) => {
+            for sub_rule in &s.args { compile(sub_rule, out); }
+        }
+        &Rule::Lookahead(ref l) => {
+            // Fails after the rule matches, which restores the offset
+            // and throws away the meta data.
+            // A positive lookahead is a negative one done twice.
+            let outer = out.len();
+            if !l.negate { out.push(Instruction::Choice(0)); }
+            let choice = out.len();
+            out.push(Instruction::Choice(0));
+            compile(&l.rule, out);
+            let next = out.len() + 1;
+            out.push(Instruction::Commit(next));
+            out.push(Instruction::Fail);
+            patch(out, choice);
+            if !l.negate {
+                let next = out.len() + 1;
+                out.push(Instruction::Commit(next));
+                out.push(Instruction::Fail);
+                patch(out, outer);
+            }
+        }
+        &Rule::Optional(ref o) => {
+            let choice = out.len();
+            out.push(Instruction::Choice(0));
+            compile(&o.rule, out);
+            let next = out.len() + 1;
+            out.push(Instruction::Commit(next));
+            patch(out, choice);
+        }
+        &Rule::Node(ref n) => {
+            let index = match n.index {
+                None => {
+                    out.push(Instruction::Fail);
+                    return;
+                }
+                Some(i) => i
+            };
+            if let Some(ref prop) = n.property {
+                out.push(Instruction::StartNode(prop.clone()));
+            }
+            out.push(Instruction::Call(index, n.debug_id));
+            if let Some(ref prop) = n.property {
+                out.push(Instruction::EndNode(prop.clone()));
+            }
+        }
+        &Rule::Repeat(ref r) if r.count.is_some() => {
+            out.push(Instruction::Rule(rule.clone()))
+        }
+        &Rule::SeparatedBy(ref s) if s.count.is_some() => {
+            out.push(Instruction::Rule(rule.clone()))
+        }
+        &Rule::Repeat(ref r) => {
+            out.push(Instruction::Push(true));
+            let start = out.len();
+            out.push(Instruction::Choice(0));
+            out.push(Instruction::Mark);
+            compile(&r.rule, out);
+            let next = out.len() + 1;
+            out.push(Instruction::Commit(next));
+            out.push(Instruction::Progress);
+            out.push(Instruction::Set(false));
+            out.push(Instruction::Jump(start));
+            patch(out, start);
+            if !r.optional { out.push(Instruction::FailIf(true)); }
+            out.push(Instruction::Pop);
+        }
+        &Rule::SeparatedBy(ref s) => {
+            out.push(Instruction::Push(true));
+            let start = out.len();
+            out.push(Instruction::Choice(0));
+            out.push(Instruction::Mark);
+            compile(&s.rule, out);
+            let commit = out.len();
+            out.push(Instruction::Commit(0));
+            // The rule failed, which is accepted after a separator
+            // only when trailing separators are allowed.
+            patch(out, start);
+            match (s.optional, s.allow_trail) {
+                (false, false) => out.push(Instruction::Fail),
+                (false, true) => out.push(Instruction::FailIf(true)),
+                (true, false) => out.push(Instruction::FailIf(false)),
+                (true, true) => {}
+            }
+            let jump = out.len();
+            out.push(Instruction::Jump(0));
+            patch(out, commit);
+            let choice = out.len();
+            out.push(Instruction::Choice(0));
+            compile(&s.by, out);
+            let next = out.len() + 1;
+            out.push(Instruction::Commit(next));
+            out.push(Instruction::Progress);
+            out.push(Instruction::Set(false));
+            out.push(Instruction::Jump(start));
+            // The separator failed, which leaves the offset of the item.
+            patch(out, choice);
+            out.push(Instruction::Pop);
+            patch(out, jump);
+            out.push(Instruction::Pop);
+        }
+        &Rule::Lines(ref l) => {
+            out.push(Instruction::Push(true));
+            let start = out.len();
+            out.push(Instruction::LineSpace(0, l.comments.clone()));
+            // Requires a new line before the next item.
+            out.push(Instruction::FailIf(false));
+            let choice = out.len();
+            out.push(Instruction::Choice(0));
+            out.push(Instruction::Mark);
+            compile(&l.rule, out);
+            out.push(Instruction::LinesCommit(start));
+            patch(out, start);
+            patch(out, choice);
+            out.push(Instruction::Pop);
+        }
+        &Rule::Indent(_) => out.push(Instruction::Rule(rule.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use std::sync::Arc;
+
+    fn convert(rules: &str) -> Vec<(Arc<String>, Rule)> {
+        bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap()
+    }
+
+    /// Checks that the program gives the same result as the rules.
+    /// Returns `true` if parsing succeeded without using the rules.
+    fn check(program: &Program, rules: &[(Arc<String>, Rule)], text: &str) -> bool {
+        assert_eq!(program.parse(text), parse(rules, text));
+        program.run(&mut Tokenizer::new(), rules.len() - 1, text) == Some(text.len())
+    }
+
+    #[test]
+    fn self_syntax() {
+        use std::fs::File;
+        use std::io::Read;
+
+        let mut text = String::new();
+        File::open("assets/self-syntax.txt").unwrap()
+            .read_to_string(&mut text).unwrap();
+        let rules = bootstrap::rules();
+        let program = Program::new(&rules);
+        assert!(check(&program, &rules, &text));
+
+        // Errors are the same.
+        assert!(!check(&program, &rules, &text[..text.len() / 2]));
+    }
+
+    #[test]
+    fn same_output() {
+        let rules = convert(r#"
+1 "item" {$"num" t!"text" ["x""ex" ?"!"!"not"] c"a-z"{2,}"id"}
+2 "list" ["(" w? s?.(["," w?]){@"item""item"} ")"]
+3 "sep" s!([w? ";" w?]){@"list""list"}
+4 "rep" [r!(["<" ..">"!"a" ">"]) r?([.."<"!"b" w!])]
+5 "line" [?w! $"n" w?]
+6 "document" {
+    [w? "lines:" l(@"line""line")]
+    [w? "sep:" w! @"sep""sep" w?]
+    [w? "rep:" w! @"rep""rep"]
+}
+        "#);
+        let program = Program::new(&rules);
+        for &(text, ok) in &[
+            ("lines:\n1\n2 \n\n  3\n", true),
+            ("lines: 1\n2 3\n", false),
+            ("sep: (1, \"a\", x!,); (2,  3) ; ()", true),
+            ("sep: (ab, xy, z)", false),
+            ("sep: (ab, cde)", true),
+            ("sep: (1 2)", false),
+            ("sep: (1,,)", false),
+            ("rep: <foo><bar>baz qux ", true),
+            ("rep: baz", false),
+            ("rep: <foo> x!", false),
+            ("", false),
+        ] {
+            assert_eq!(check(&program, &rules, text), ok, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let rules = convert(r#"
+1 "item" {$"num" t!"text" ["x""ex" ?"!"!"not"]}
+2 "list" ["(" w? s?.(["," w?]){{@"item""item" @"list""list"}} ")"]
+3 "document" [w? l(@"list""list")]
+        "#);
+        let program = Program::new(&rules);
+        let text = "(1, \"a\")\n((x!), ())\n";
+        let mut tokenizer = Tokenizer::new();
+        assert!(parse_with_tokenizer(&mut tokenizer, &rules, text).is_ok());
+        let (steps, depth) = (tokenizer.steps, 3);
+
+        // The program stops at the same limits as the rules.
+        for fuel in 0..steps + 2 {
+            for max_depth in 0..depth + 2 {
+                let mut a = Tokenizer::new();
+                a.fuel = Some(fuel);
+                a.max_depth = Some(max_depth);
+                let mut b = Tokenizer::new();
+                b.fuel = Some(fuel);
+                b.max_depth = Some(max_depth);
+                assert_eq!(program.parse_with_tokenizer(&mut a, text),
+                    parse_with_tokenizer(&mut b, &rules, text),
+                    "fuel {} max_depth {}", fuel, max_depth);
+            }
+        }
+
+        // The program parses within the limits without the rules.
+        tokenizer.fuel = Some(steps);
+        tokenizer.max_depth = Some(depth);
+        tokenizer.steps = 0;
+        assert_eq!(program.run(&mut tokenizer, rules.len() - 1, text), Some(text.len()));
+        assert_eq!(tokenizer.limit, None);
+        tokenizer.max_depth = Some(depth - 1);
+        tokenizer.steps = 0;
+        assert_eq!(program.run(&mut tokenizer, rules.len() - 1, text), None);
+    }
+
+    #[test]
+    fn left_recursion() {
+        let rules = convert(r#"
+1 "term" $"val"
+2 "expr" {[@"expr""left" w? "+" w? @"term""right"] @"term""term"}
+3 "document" [w? @"expr""expr" w?]
+        "#);
+        let program = Program::new(&rules);
+        let text = "1 + 2 + 3";
+        assert!(program.parse(text).is_ok());
+        // Left recursion is parsed with the rules.
+        assert!(!check(&program, &rules, text));
+    }
+
+    #[test]
+    fn lookahead() {
+        let rules = convert(r#"
+1 "keyword" ["if" !c"a-z"]
+2 "document" s!.(w!) {{@"keyword""if" [&"i" ..","!"name"] [!"i" $"num"]}}
+        "#);
+        let program = Program::new(&rules);
+        for &(text, ok) in &[
+            ("if ifx 2 if", true),
+            ("ifx if", true),
+            ("if2", false),
+            ("x", false),
+        ] {
+            assert_eq!(check(&program, &rules, text), ok, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn infinite_loop() {
+        let rules = vec![(Arc::new("rule".into()), Rule::SeparatedBy(Box::new(SeparatedBy {
+            debug_id: 1,
+            rule: Rule::Whitespace(Whitespace { debug_id: 2, optional: true, comments: None }),
+            by: Rule::Optional(Box::new(Optional {
+                debug_id: 3,
+                rule: Rule::Token(Token {
+                    debug_id: 4,
+                    text: Arc::new(",".into()),
+                    inverted: false,
+                    case_insensitive: false,
+                    property: None,
+                }),
+            })),
+            optional: false,
+            allow_trail: false,
+            count: None,
+        })))];
+        let program = Program::new(&rules);
+        // The rules report the error.
+        assert!(!check(&program, &rules, " , ,x"));
+        assert_eq!(program.parse(" , ,x"),
+            Err((Range::empty(4), ParseError::InfiniteLoop(1))));
+    }
+}
@@ -0,0 +1,292 @@
+//! Reads tokens from UTF-8 text, using byte offsets.
+//!
+//! The functions take the text starting at `offset`,
+//! and return ranges relative to the start of the document.
+
+use range::Range;
+use read_token::ParseStringError;
+use std::char;
+
+/// Reads an expected token, returns `None` if it does not match.
+pub fn token(token: &str, chars: &str, offset: usize) -> Option<Range> {
+    if chars.starts_with(token) {
+        Some(Range::new(offset, token.len()))
+    } else {
+        None
+    }
+}
+
+/// Compares an expected token ignoring case with the start of the text.
+/// Characters are compared by their lower case, such that `"SELECT"` matches `"select"`.
+/// Returns the length of the text read if it matches,
+/// or else the number of bytes compared, counting the end of the text as one.
+///
+/// The length of the text can differ from the token,
+/// since some characters have a lower case of another length.
+pub fn compare_ignore_case(token: &str, chars: &str) -> Result<usize, usize> {
+    let mut expected = token.chars().flat_map(|c| c.to_lowercase()).peekable();
+    let mut len = 0;
+    for c in chars.chars() {
+        if expected.peek().is_none() { break; }
+        for lower in c.to_lowercase() {
+            if expected.next() != Some(lower) { return Err(len + c.len_utf8()); }
+        }
+        len += c.len_utf8();
+    }
+    if expected.peek().is_some() { return Err(len + 1); }
+    Ok(len)
+}
+
+/// Reads a token until any character in string or whitespace.
+/// Returns `(range, None)` if stopping at whitespace or end of text.
+/// Returns `(range, Some(x))` if stopping at a character.
+pub fn until_any_or_whitespace(
+    any: &str,
+    chars: &str,
+    offset: usize
+) -> (Range, Option<usize>) {
+    for (i, c) in chars.char_indices() {
+        if c.is_whitespace() {
+            return (Range::new(offset, i), None)
+        }
+        if let Some(j) = any.chars().position(|b| b == c) {
+            return (Range::new(offset, i), Some(j))
+        }
+    }
+    (Range::new(offset, chars.len()), None)
+}
+
+/// Reads token until any character in string.
+/// Returns `(range, None)` if stopping at end of text.
+/// Returns `(range, Some(x))` if stopping at a character.
+pub fn until_any(
+    any: &str,
+    chars: &str,
+    offset: usize
+) -> (Range, Option<usize>) {
+    for (i, c) in chars.char_indices() {
+        if let Some(j) = any.chars().position(|b| b == c) {
+            return (Range::new(offset, i), Some(j))
+        }
+    }
+    (Range::new(offset, chars.len()), None)
+}
+
+/// Returns the number of bytes of whitespace at the start.
+pub fn whitespace_len(chars: &str) -> usize {
+    chars.len() - chars.trim_start().len()
+}
+
+/// Returns the number of bytes of whitespace at the start,
+/// stopping at a new line.
+pub fn line_whitespace_len(chars: &str) -> usize {
+    chars.len() - chars.trim_start_matches(|c: char| c != '\n' && c.is_whitespace())
+        .len()
+}
+
+/// Reads string with character escapes.
+pub fn string(chars: &str, offset: usize) -> Option<Range> {
+    let bytes = chars.as_bytes();
+    if bytes.first() != Some(&b'"') { return None; }
+    let mut escape = false;
+    // The delimiters are ASCII, so multi-byte characters can be skipped.
+    for (i, &b) in bytes.iter().enumerate().skip(1) {
+        if escape { escape = false; continue; }
+        if b == b'\\' { escape = true; continue; }
+        if b == b'"' { return Some(Range::new(offset, i + 1)); }
+    }
+    None
+}
+
+/// Parses four unicode characters in hexadecimal format.
+pub fn parse_unicode(
+    chars: &str,
+    offset: usize
+) -> Result<char, ParseStringError> {
+    if chars.chars().take(4).count() < 4 {
+        return Err(ParseStringError::ExpectedFourHexadecimals(
+            Range::new(offset, chars.len())
+        ));
+    }
+    let mut code = 0;
+    for (i, c) in chars.char_indices().take(4) {
+        match c.to_digit(16) {
+            Some(x) => code = (code << 4) | x,
+            None => {
+                return Err(ParseStringError::ExpectedHexadecimal(
+                    Range::new(offset + i, c.len_utf8())
+                ))
+            }
+        }
+    }
+    match char::from_u32(code) {
+        Some(x) => Ok(x),
+        None => Err(ParseStringError::ExpectedValidUnicode(
+            Range::new(offset, 4)
+        ))
+    }
+}
+
+/// Parses string into a real string according to the JSON standard.
+///
+/// Assumes the string starts and ends with double-quotes,
+/// where `range` is the range returned by `string`.
+pub fn parse_string(
+    chars: &str,
+    range: Range
+) -> Result<String, ParseStringError> {
+    let offset = range.offset;
+    let inner = &chars[1..range.length - 1];
+    let mut txt = String::with_capacity(inner.len());
+    let mut iter = inner.char_indices();
+    while let Some((_, c)) = iter.next() {
+        if c != '\\' {
+            txt.push(c);
+            continue;
+        }
+        let (i, c) = match iter.next() {
+            Some(x) => x,
+            None => break
+        };
+        txt.push(match c {
+            '\"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\u{0008}',
+            'f' => '\u{000c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let start = i + 1;
+                let x = try!(parse_unicode(&inner[start..], offset + 1 + start));
+                // Skip the hexadecimals.
+                for _ in 0..4 { iter.next(); }
+                x
+            }
+            _ => {
+                return Err(ParseStringError::ExpectedValidEscapeCharacter(
+                    Range::new(offset + 1 + i, c.len_utf8())
+                ));
+            }
+        })
+    }
+    Ok(txt)
+}
+
+/// Reads number.
+pub fn number(chars: &str, offset: usize) -> Option<Range> {
+    read_number(chars, offset, false)
+}
+
+/// Reads a number that can have underscore as visual separator.
+pub fn underscore_number(chars: &str, offset: usize) -> Option<Range> {
+    read_number(chars, offset, true)
+}
+
+fn read_number(chars: &str, offset: usize, allow_underscore: bool)
+-> Option<Range> {
+    let mut has_sign = false;
+    let mut has_decimal_separator = false;
+    let mut has_scientific = false;
+    let mut has_exponent_sign = false;
+    let mut has_digit = false;
+    // All characters of a number are ASCII.
+    for (i, &c) in chars.as_bytes().iter().enumerate() {
+        if !has_sign {
+            has_sign = true;
+            if c == b'+' || c == b'-' { continue; }
+        }
+        if c.is_ascii_digit() { has_digit = true; continue; }
+        if allow_underscore && has_digit && c == b'_' { continue; }
+        if !has_decimal_separator && c == b'.' {
+            has_decimal_separator = true;
+            continue;
+        }
+        if !has_scientific && (c == b'e' || c == b'E') {
+            has_scientific = true;
+            continue;
+        }
+        if has_scientific && !has_exponent_sign {
+            has_exponent_sign = true;
+            if c == b'+' || c == b'-' { continue; }
+        }
+        if i > 0 { return Some(Range::new(offset, i)) }
+        else { return None }
+    }
+    if !chars.is_empty() { Some(Range::new(offset, chars.len())) }
+    else { None }
+}
+
+/// Reads a prefix `0x`, `0o` or `0b` and returns the radix.
+pub fn radix_prefix(chars: &str) -> Option<u32> {
+    let bytes = chars.as_bytes();
+    if bytes.len() < 2 || bytes[0] != b'0' { return None; }
+    match bytes[1] {
+        b'x' | b'X' => Some(16),
+        b'o' | b'O' => Some(8),
+        b'b' | b'B' => Some(2),
+        _ => None
+    }
+}
+
+/// Returns the number of bytes of digits in a radix at the start.
+/// An underscore is allowed as visual separator after the first digit.
+pub fn radix_digits(chars: &str, radix: u32, allow_underscore: bool) -> usize {
+    let mut has_digit = false;
+    // All digits are ASCII.
+    for (i, &c) in chars.as_bytes().iter().enumerate() {
+        if (c as char).is_digit(radix) { has_digit = true; continue; }
+        if allow_underscore && has_digit && c == b'_' { continue; }
+        return i;
+    }
+    chars.len()
+}
+
+/// Returns the number of bytes of `infinity`, `inf` or `nan` at the start,
+/// ignoring case.
+/// A letter or digit must not follow, such that `info` is not read.
+pub fn special_float(chars: &str) -> Option<usize> {
+    let bytes = chars.as_bytes();
+    ["infinity", "inf", "nan"].iter()
+        .find(|name| bytes.len() >= name.len() &&
+            bytes[..name.len()].eq_ignore_ascii_case(name.as_bytes()) &&
+            !chars[name.len()..].chars().next().map_or(false, |c| c.is_alphanumeric()))
+        .map(|name| name.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use range::Range;
+    use read_token::ParseStringError;
+
+    #[test]
+    fn test_token() {
+        let text = "\u{e6}n day";
+        assert_eq!(token("\u{e6}n", text, 0), Some(Range::new(0, 3)));
+        assert_eq!(token("two", text, 0), None);
+    }
+
+    #[test]
+    fn test_until_any() {
+        let text = "\u{e6}ne day, a nice day";
+        assert_eq!(until_any(",", text, 0), (Range::new(0, 8), Some(0)));
+        assert_eq!(until_any_or_whitespace(",", text, 0), (Range::new(0, 4), None));
+        assert_eq!(until_any(",", &text[9..], 9), (Range::new(9, 11), None));
+    }
+
+    #[test]
+    fn test_string() {
+        let text = "\"\\u00e6\\\"\\n\u{1f600}\" x";
+        let range = string(text, 0).unwrap();
+        assert_eq!(range, Range::new(0, 16));
+        assert_eq!(parse_string(text, range), Ok("\u{e6}\"\n\u{1f600}".into()));
+        assert_eq!(string("\"", 0), None);
+        assert_eq!(string("\"\\\"", 0), None);
+        assert_eq!(parse_string("\"\\x\"", Range::new(5, 4)),
+            Err(ParseStringError::ExpectedValidEscapeCharacter(Range::new(7, 1))));
+        assert_eq!(parse_string("\"\\u12\"", Range::new(0, 6)),
+            Err(ParseStringError::ExpectedFourHexadecimals(Range::new(3, 2))));
+    }
+}
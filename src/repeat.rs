@@ -1,11 +1,17 @@
 use range::Range;
-use std::rc::Rc;
+use std::cmp::{ max, min };
+use std::sync::Arc;
 
 use {
+    check_count,
+    compose,
+    line_end,
     ret_err,
     err_update,
     update,
     DebugId,
+    MetaData,
+    ParseError,
     ParseResult,
     Rule,
     Tokenizer,
@@ -19,29 +25,48 @@ pub struct Repeat {
     pub rule: Rule,
     /// Whether the rule must occur at least once.
     pub optional: bool,
+    /// The minimum and maximum number of times the rule must occur, if any.
+    /// When the minimum is zero, `optional` is `true`.
+    pub count: Option<(usize, Option<usize>)>,
+    /// The token to skip past when recovering from an error.
+    /// If there is none, the rest of the line is skipped.
+    pub sync: Option<Arc<String>>,
     /// A debug id to track down the rule generating an error.
     pub debug_id: DebugId,
 }
 
 impl Repeat {
     /// Parses rule repeatedly.
+    /// Stops at the maximum count, if any.
     pub fn parse(
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        mut chars: &[char],
+        mut chars: &str,
         start_offset: usize,
-        refs: &[(Rc<String>, Rule)]
+        refs: &[(Arc<String>, Rule)]
     ) -> ParseResult<TokenizerState> {
         let mut offset = start_offset;
         let mut state = state.clone();
         let mut opt_error = None;
         let mut first = true;
+        let mut n = 0;
+        let max_count = self.count.and_then(|c| c.1);
         loop {
+            if max_count == Some(n) { break; }
             state = match self.rule.parse(
                 tokenizer, &state, chars, offset, refs) {
                 Err(err) => {
-                    if first && !self.optional {
+                    let end = self.sync_end(tokenizer, &err, chars, offset);
+                    if end > offset {
+                        // Skip the text and continue.
+                        let range = Range::new(offset, end - offset);
+                        let state = tokenizer.data(MetaData::Error(err.1),
+                            &state, range);
+                        update(range, None, &mut chars, &mut offset,
+                            &mut opt_error);
+                        state
+                    } else if first && !self.optional {
                         return Err(ret_err(err, opt_error));
                     } else {
                         err_update(Some(err), &mut opt_error);
@@ -49,36 +74,107 @@ impl Repeat {
                     }
                 }
                 Ok((range, state, err)) => {
+                    // A maximum count stops the loop.
+                    if range.length == 0 && max_count.is_none() {
+                        return Err((Range::empty(offset),
+                            ParseError::InfiniteLoop(self.debug_id)));
+                    }
                     update(range, err, &mut chars, &mut offset, &mut opt_error);
                     state
                 }
             };
             first = false;
+            n += 1;
+        }
+        let range = Range::new(start_offset, offset - start_offset);
+        if let Err(err) = check_count(self.count, n, range, self.debug_id) {
+            return Err(ret_err(err, opt_error));
         }
-        Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+        Ok((range, state, opt_error))
+    }
+
+    /// Returns the offset to continue from when recovering from an error,
+    /// or the same offset if the error should not be recovered from.
+    fn sync_end(
+        &self,
+        tokenizer: &mut Tokenizer,
+        err: &(Range, ParseError),
+        chars: &str,
+        offset: usize
+    ) -> usize {
+        if !tokenizer.recovers(err) { return offset; }
+        let from = max(err.0.offset, offset);
+        let end = match self.sync {
+            Some(ref sync) => chars[from - offset..].find(&**sync)
+                .map(|i| from + i + sync.len())
+                .unwrap_or(offset + chars.len()),
+            None => min(line_end(chars, offset, from) + 1, offset + chars.len()),
+        };
+        tokenizer.examine(end + 1);
+        end
+    }
+
+    /// Composes rule repeatedly.
+    /// Stops when the rule does not match or uses no meta data.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        mut data: &[(Range, MetaData)],
+        start_offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> Result<Range, ()> {
+        let mut offset = start_offset;
+        let mut first = true;
+        let mut n = 0;
+        loop {
+            if self.count.and_then(|c| c.1) == Some(n) { break; }
+            let len = out.len();
+            match self.rule.compose(out, data, offset, refs) {
+                Err(()) => {
+                    out.truncate(len);
+                    if first && !self.optional { return Err(()); }
+                    break;
+                }
+                Ok(range) => {
+                    if range.length == 0 {
+                        // Keep the text only when it is required.
+                        if !first || self.optional { out.truncate(len); }
+                        break;
+                    }
+                    compose::update(range, &mut data, &mut offset);
+                }
+            }
+            first = false;
+            n += 1;
+        }
+        if n < self.count.map(|c| c.0).unwrap_or(0) { return Err(()); }
+        Ok(Range::new(start_offset, offset - start_offset))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::*;
-    use std::rc::Rc;
+    use std::sync::Arc;
     use range::Range;
 
     #[test]
     fn fail() {
         let text = "[a][a][a]";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let token: Rc<String> = Rc::new("(a)".into());
+        let token: Arc<String> = Arc::new("(a)".into());
         let rule = Repeat {
             debug_id: 0,
             optional: false,
+            count: None,
+            sync: None,
             rule: Rule::Token(Token {
                 debug_id: 1,
                 text: token.clone(),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             })
         };
@@ -90,17 +186,20 @@ mod tests {
     #[test]
     fn success() {
         let text = "(a)(a)(a)";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let token: Rc<String> = Rc::new("(a)".into());
+        let token: Arc<String> = Arc::new("(a)".into());
         let rule = Repeat {
             debug_id: 0,
             optional: false,
+            count: None,
+            sync: None,
             rule: Rule::Token(Token {
                 debug_id: 1,
                 text: token.clone(),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             })
         };
@@ -108,4 +207,53 @@ mod tests {
         assert_eq!(res, Ok((Range::new(0, 9), TokenizerState(0),
             Some((Range::new(9, 0), ParseError::ExpectedToken(token.clone(), 1))))))
     }
+
+    #[test]
+    fn infinite_loop() {
+        let text = "a";
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let rule = Repeat {
+            debug_id: 0,
+            optional: true,
+            count: None,
+            sync: None,
+            rule: Rule::Whitespace(Whitespace {
+                debug_id: 1,
+                optional: true,
+                comments: None,
+            })
+        };
+        let res = rule.parse(&mut tokenizer, &s, text, 0, &[]);
+        assert_eq!(res, Err((Range::new(0, 0), ParseError::InfiniteLoop(0))));
+    }
+
+    #[test]
+    fn count() {
+        let text = "(a)(a)(a)";
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let token: Arc<String> = Arc::new("(a)".into());
+        let rule = |count| Repeat {
+            debug_id: 0,
+            optional: false,
+            count: Some(count),
+            sync: None,
+            rule: Rule::Token(Token {
+                debug_id: 1,
+                text: token.clone(),
+                inverted: false,
+                case_insensitive: false,
+                property: None,
+            })
+        };
+        let res = rule((3, Some(3))).parse(&mut tokenizer, &s, text, 0, &[]);
+        assert_eq!(res.map(|r| r.0), Ok(Range::new(0, 9)));
+        let res = rule((4, None)).parse(&mut tokenizer, &s, text, 0, &[]);
+        assert_eq!(res, Err((Range::new(0, 9),
+            ParseError::ExpectedCount(4, None, 3, 0))));
+        // Stops at the maximum count, like `CharClass`.
+        let res = rule((1, Some(2))).parse(&mut tokenizer, &s, text, 0, &[]);
+        assert_eq!(res.map(|r| r.0), Ok(Range::new(0, 6)));
+    }
 }
@@ -1,20 +1,28 @@
-use std::rc::Rc;
+use read;
+use range::Range;
+use std::sync::Arc;
 
 use {
+    Comments,
+    DebugId,
+    MetaData,
     Whitespace,
     Token,
     UntilAny,
     UntilAnyOrWhitespace,
     Text,
     Number,
+    CharClass,
     Node,
     ParseResult,
     Select,
     SeparatedBy,
     Repeat,
     Lines,
+    Indent,
     Sequence,
     Optional,
+    Lookahead,
     Tokenizer,
     TokenizerState,
 };
@@ -34,6 +42,8 @@ pub enum Rule {
     Text(Text),
     /// Read number.
     Number(Number),
+    /// Read characters in a class.
+    CharClass(CharClass),
     /// Select one of the sub rules.
     /// If the first one does not succeed, try another and so on.
     /// If all sub rules fail, then the rule fails.
@@ -47,10 +57,14 @@ pub enum Rule {
     Repeat(Box<Repeat>),
     /// Repeat rule separated by one or more lines.
     Lines(Box<Lines>),
+    /// Repeat rule on lines indented deeper than the current block.
+    Indent(Box<Indent>),
     /// Read node.
     Node(Node),
     /// Read optional.
     Optional(Box<Optional>),
+    /// Look for a rule without reading anything.
+    Lookahead(Box<Lookahead>),
 }
 
 impl Rule {
@@ -59,28 +73,81 @@ impl Rule {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        chars: &[char],
+        chars: &str,
         offset: usize,
-        refs: &[(Rc<String>, Rule)]
+        refs: &[(Arc<String>, Rule)]
     ) -> ParseResult<TokenizerState> {
+        try!(tokenizer.step(self.debug_id(), offset));
+        if tokenizer.tracer.is_none() {
+            return self.parse_rule(tokenizer, state, chars, offset, refs);
+        }
+        if let Some(ref mut tracer) = tokenizer.tracer {
+            tracer.enter(self, self.debug_id(), offset);
+        }
+        let res = self.parse_rule(tokenizer, state, chars, offset, refs);
+        if let Some(ref mut tracer) = tokenizer.tracer {
+            match res {
+                Ok((range, _, _)) => tracer.success(range),
+                Err((range, ref err)) => tracer.fail(range, err),
+            }
+        }
+        res
+    }
+
+    /// Parses rule without notifying the tracer.
+    fn parse_rule(
+        &self,
+        tokenizer: &mut Tokenizer,
+        state: &TokenizerState,
+        chars: &str,
+        offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> ParseResult<TokenizerState> {
+        // Leaf rules look at the character where they stop reading.
         match self {
             &Rule::Whitespace(ref w) => {
-                w.parse(chars, offset).map(|r| (r, state.clone(), None))
+                let res = w.parse(chars, offset)
+                    .map(|r| (r, state.clone(), None));
+                tokenizer.examine(next_offset(&res) + 1);
+                res
             }
             &Rule::Token(ref t) => {
+                // Marks the text compared as examined.
                 t.parse(tokenizer, state, chars, offset)
             }
             &Rule::UntilAny(ref u) => {
-                u.parse(tokenizer, state, chars, offset)
+                let res = u.parse(tokenizer, state, chars, offset);
+                tokenizer.examine(next_offset(&res) + 1);
+                res
             }
             &Rule::UntilAnyOrWhitespace(ref u) => {
-                u.parse(tokenizer, state, chars, offset)
+                let res = u.parse(tokenizer, state, chars, offset);
+                tokenizer.examine(next_offset(&res) + 1);
+                res
             }
             &Rule::Text(ref t) => {
-                t.parse(tokenizer, state, chars, offset)
+                let res = t.parse(tokenizer, state, chars, offset);
+                // Looks for the end of the string when failing.
+                tokenizer.examine(match res {
+                    Ok((range, _, _)) => range.next_offset(),
+                    Err(_) => match read::string(chars, offset) {
+                        Some(range) => range.next_offset(),
+                        None if chars.starts_with('"') =>
+                            offset + chars.len() + 1,
+                        None => offset + 1,
+                    }
+                });
+                res
             }
             &Rule::Number(ref n) => {
-                n.parse(tokenizer, state, chars, offset)
+                let res = n.parse(tokenizer, state, chars, offset);
+                tokenizer.examine(next_offset(&res) + 1);
+                res
+            }
+            &Rule::CharClass(ref c) => {
+                let res = c.parse(tokenizer, state, chars, offset);
+                tokenizer.examine(next_offset(&res) + 1);
+                res
             }
             &Rule::Select(ref s) => {
                 s.parse(tokenizer, state, chars, offset, refs)
@@ -97,75 +164,209 @@ impl Rule {
             &Rule::Lines(ref l) => {
                 l.parse(tokenizer, state, chars, offset, refs)
             }
+            &Rule::Indent(ref i) => {
+                i.parse(tokenizer, state, chars, offset, refs)
+            }
             &Rule::Node(ref p) => {
                 p.parse(tokenizer, state, chars, offset, refs)
             }
             &Rule::Optional(ref o) => {
                 Ok(o.parse(tokenizer, state, chars, offset, refs))
             }
+            &Rule::Lookahead(ref l) => {
+                l.parse(tokenizer, state, chars, offset, refs)
+            }
         }
     }
 
-    /// Updates replacing names with the references.
-    ///
-    /// The references contains the name,
-    /// but this can not be borrowed as when the same reference is updated.
-    pub fn update_refs(&self, refs: &[(Rc<String>, Rule)]) {
+    /// Returns the debug id of the rule.
+    pub fn debug_id(&self) -> DebugId {
         match self {
-            &Rule::Node(ref p) => {
-                match p.index.get() {
-                    None => {
-                        // Look through references and update if correct name
-                        // is found.
-                        let mut found: Option<usize> = None;
-                        for (i, r) in refs.iter().enumerate() {
-                            if &**p.name == &*r.0 {
-                                found = Some(i);
-                                break;
-                            }
-                        }
-                        match found {
-                            None => { return; }
-                            Some(i) => {
-                                p.index.set(Some(i));
-                                return;
-                            }
-                        }
-                    }
-                    Some(_) => {
-                        return;
-                    }
-                };
-            }
-            &Rule::Whitespace(_) => {}
-            &Rule::Token(_) => {}
-            &Rule::UntilAny(_) => {}
-            &Rule::UntilAnyOrWhitespace(_) => {}
-            &Rule::Text(_) => {}
-            &Rule::Number(_) => {}
+            &Rule::Whitespace(ref w) => w.debug_id,
+            &Rule::Token(ref t) => t.debug_id,
+            &Rule::UntilAny(ref u) => u.debug_id,
+            &Rule::UntilAnyOrWhitespace(ref u) => u.debug_id,
+            &Rule::Text(ref t) => t.debug_id,
+            &Rule::Number(ref n) => n.debug_id,
+            &Rule::CharClass(ref c) => c.debug_id,
+            &Rule::Select(ref s) => s.debug_id,
+            &Rule::Sequence(ref s) => s.debug_id,
+            &Rule::SeparatedBy(ref s) => s.debug_id,
+            &Rule::Repeat(ref r) => r.debug_id,
+            &Rule::Lines(ref l) => l.debug_id,
+            &Rule::Indent(ref i) => i.debug_id,
+            &Rule::Node(ref n) => n.debug_id,
+            &Rule::Optional(ref o) => o.debug_id,
+            &Rule::Lookahead(ref l) => l.debug_id,
+        }
+    }
+
+    /// Composes rule.
+    /// Writes text to output and returns the range of meta data used.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        data: &[(Range, MetaData)],
+        offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> Result<Range, ()> {
+        match self {
+            &Rule::Whitespace(ref w) => {
+                Ok(w.compose(out, offset))
+            }
+            &Rule::Token(ref t) => {
+                t.compose(out, data, offset)
+            }
+            &Rule::UntilAny(ref u) => {
+                u.compose(out, data, offset)
+            }
+            &Rule::UntilAnyOrWhitespace(ref u) => {
+                u.compose(out, data, offset)
+            }
+            &Rule::Text(ref t) => {
+                t.compose(out, data, offset)
+            }
+            &Rule::Number(ref n) => {
+                n.compose(out, data, offset)
+            }
+            &Rule::CharClass(ref c) => {
+                c.compose(out, data, offset)
+            }
             &Rule::Select(ref s) => {
-                for sub_rule in &s.args {
-                    sub_rule.update_refs(refs);
-                }
+                s.compose(out, data, offset, refs)
             }
             &Rule::Sequence(ref s) => {
-                for sub_rule in &s.args {
-                    sub_rule.update_refs(refs);
-                }
+                s.compose(out, data, offset, refs)
             }
             &Rule::SeparatedBy(ref s) => {
-                s.rule.update_refs(refs);
-                s.by.update_refs(refs);
+                s.compose(out, data, offset, refs)
             }
             &Rule::Repeat(ref r) => {
-                r.rule.update_refs(refs);
+                r.compose(out, data, offset, refs)
             }
             &Rule::Lines(ref l) => {
-                l.rule.update_refs(refs);
+                l.compose(out, data, offset, refs)
+            }
+            &Rule::Indent(ref i) => {
+                i.compose(out, data, offset, refs)
+            }
+            &Rule::Node(ref p) => {
+                p.compose(out, data, offset, refs)
             }
             &Rule::Optional(ref o) => {
-                o.rule.update_refs(refs);
+                Ok(o.compose(out, data, offset, refs))
+            }
+            &Rule::Lookahead(_) => {
+                // Writes nothing, since lookahead reads nothing.
+                Ok(Range::empty(offset))
             }
         }
     }
+
+    /// Updates replacing names with the references.
+    ///
+    /// Takes the names of the rules, such that the rules can be updated
+    /// while the names are borrowed.
+    pub fn update_refs(&mut self, names: &[Arc<String>]) {
+        match self {
+            &mut Rule::Node(ref mut p) => {
+                if p.index.is_none() {
+                    // Look through references and update if correct name
+                    // is found.
+                    p.index = names.iter().position(|name| **name == *p.name);
+                }
+            }
+            &mut Rule::Whitespace(_) => {}
+            &mut Rule::Token(_) => {}
+            &mut Rule::UntilAny(_) => {}
+            &mut Rule::UntilAnyOrWhitespace(_) => {}
+            &mut Rule::Text(_) => {}
+            &mut Rule::Number(_) => {}
+            &mut Rule::CharClass(_) => {}
+            &mut Rule::Select(ref mut s) => {
+                for sub_rule in &mut s.args {
+                    sub_rule.update_refs(names);
+                }
+            }
+            &mut Rule::Sequence(ref mut s) => {
+                for sub_rule in &mut s.args {
+                    sub_rule.update_refs(names);
+                }
+            }
+            &mut Rule::SeparatedBy(ref mut s) => {
+                s.rule.update_refs(names);
+                s.by.update_refs(names);
+            }
+            &mut Rule::Repeat(ref mut r) => {
+                r.rule.update_refs(names);
+            }
+            &mut Rule::Lines(ref mut l) => {
+                l.rule.update_refs(names);
+            }
+            &mut Rule::Indent(ref mut i) => {
+                i.rule.update_refs(names);
+            }
+            &mut Rule::Optional(ref mut o) => {
+                o.rule.update_refs(names);
+            }
+            &mut Rule::Lookahead(ref mut l) => {
+                l.rule.update_refs(names);
+            }
+        }
+    }
+
+    /// Sets the comments to skip in whitespace and between lines.
+    pub fn set_comments(&mut self, comments: &Arc<Comments>) {
+        match self {
+            &mut Rule::Whitespace(ref mut w) => {
+                w.comments = Some(comments.clone());
+            }
+            &mut Rule::Lines(ref mut l) => {
+                l.comments = Some(comments.clone());
+                l.rule.set_comments(comments);
+            }
+            &mut Rule::Indent(ref mut i) => {
+                i.comments = Some(comments.clone());
+                i.rule.set_comments(comments);
+            }
+            &mut Rule::Token(_) => {}
+            &mut Rule::UntilAny(_) => {}
+            &mut Rule::UntilAnyOrWhitespace(_) => {}
+            &mut Rule::Text(_) => {}
+            &mut Rule::Number(_) => {}
+            &mut Rule::CharClass(_) => {}
+            &mut Rule::Node(_) => {}
+            &mut Rule::Select(ref mut s) => {
+                for sub_rule in &mut s.args {
+                    sub_rule.set_comments(comments);
+                }
+            }
+            &mut Rule::Sequence(ref mut s) => {
+                for sub_rule in &mut s.args {
+                    sub_rule.set_comments(comments);
+                }
+            }
+            &mut Rule::SeparatedBy(ref mut s) => {
+                s.rule.set_comments(comments);
+                s.by.set_comments(comments);
+            }
+            &mut Rule::Repeat(ref mut r) => {
+                r.rule.set_comments(comments);
+            }
+            &mut Rule::Optional(ref mut o) => {
+                o.rule.set_comments(comments);
+            }
+            &mut Rule::Lookahead(ref mut l) => {
+                l.rule.set_comments(comments);
+            }
+        }
+    }
+}
+
+/// Returns the offset where a rule stopped reading.
+fn next_offset<S>(res: &ParseResult<S>) -> usize {
+    match res {
+        &Ok((range, _, _)) => range.next_offset(),
+        &Err((range, _)) => range.next_offset(),
+    }
 }
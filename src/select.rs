@@ -1,9 +1,10 @@
 use range::Range;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use {
     err_update,
     DebugId,
+    MetaData,
     ParseError,
     ParseResult,
     Rule,
@@ -26,9 +27,9 @@ impl Select {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        chars: &[char],
+        chars: &str,
         offset: usize,
-        refs: &[(Rc<String>, Rule)]
+        refs: &[(Arc<String>, Rule)]
     ) -> ParseResult<TokenizerState> {
         let mut opt_error: Option<(Range, ParseError)> = None;
         for sub_rule in &self.args {
@@ -49,13 +50,46 @@ impl Select {
             Some(err) => Err(err),
         }
     }
+
+    /// Composes select.
+    /// Picks the first sub rule that uses the next meta data,
+    /// or else the first sub rule that matches without using meta data.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        data: &[(Range, MetaData)],
+        offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> Result<Range, ()> {
+        let len = out.len();
+        let mut empty: Option<(String, Range)> = None;
+        for sub_rule in &self.args {
+            match sub_rule.compose(out, data, offset, refs) {
+                Ok(range) if range.length > 0 => {
+                    return Ok(range);
+                }
+                Ok(range) => {
+                    if empty.is_none() { empty = Some((out[len..].to_string(), range)); }
+                    out.truncate(len);
+                }
+                Err(()) => { out.truncate(len); }
+            }
+        }
+        match empty {
+            None => Err(()),
+            Some((text, range)) => {
+                out.push_str(&text);
+                Ok(range)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::*;
     use range::Range;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn invalid_rule() {
@@ -64,7 +98,7 @@ mod tests {
             debug_id: 0,
             args: vec![]
         });
-        let res = parse(&[(Rc::new("".into()), select)], &text);
+        let res = parse(&[(Arc::new("".into()), select)], &text);
         let invalid_rule = match &res {
             &Err((_, ParseError::InvalidRule(_, _))) => true,
             _ => false
@@ -75,7 +109,7 @@ mod tests {
     #[test]
     fn fail_first() {
         let text = "2";
-        let num: Rc<String> = Rc::new("num".into());
+        let num: Arc<String> = Arc::new("num".into());
         let select = Rule::Select(Select {
             debug_id: 0,
             args: vec![
@@ -88,10 +122,14 @@ mod tests {
                     debug_id: 2,
                     property: Some(num.clone()),
                     allow_underscore: false,
+                    kind: NumberKind::F64,
+                    allow_radix: false,
+                    require_sign: false,
+                    allow_special: false,
                 })
             ]
         });
-        let res = parse(&[(Rc::new("".into()), select)], &text);
+        let res = parse(&[(Arc::new("".into()), select)], &text);
         assert_eq!(res, Ok(vec![
             (Range::new(0, 1), MetaData::F64(num.clone(), 2.0))
         ]));
@@ -1,11 +1,17 @@
 use range::Range;
-use std::rc::Rc;
+use std::cmp::max;
+use std::sync::Arc;
 
 use {
+    check_count,
+    compose,
+    find_on_line,
     ret_err,
     err_update,
     update,
     DebugId,
+    MetaData,
+    ParseError,
     ParseResult,
     Rule,
     Tokenizer,
@@ -23,38 +29,64 @@ pub struct SeparatedBy {
     pub optional: bool,
     /// Whether the rule can end with separator.
     pub allow_trail: bool,
+    /// The minimum and maximum number of times the rule must occur, if any.
+    /// When the minimum is zero, `optional` is `true`.
+    pub count: Option<(usize, Option<usize>)>,
     /// A debug id to track down the rule generating an error.
     pub debug_id: DebugId,
 }
 
 impl SeparatedBy {
     /// Parses rule repeatedly separated by another rule.
+    /// Stops at the maximum count, if any,
+    /// reading a trailing separator only when it is allowed.
     pub fn parse(
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        mut chars: &[char],
+        mut chars: &str,
         start_offset: usize,
-        refs: &[(Rc<String>, Rule)]
+        refs: &[(Arc<String>, Rule)]
     ) -> ParseResult<TokenizerState> {
         let mut offset = start_offset;
         let mut state = state.clone();
         let mut first = true;
+        let mut n = 0;
+        let max_count = self.count.and_then(|c| c.1);
         let mut opt_error = None;
         loop {
+            if max_count == Some(n) { break; }
+            let item_offset = offset;
             state = match self.rule.parse(
                 tokenizer, &state, chars, offset, refs
             ) {
                 Err(err) => {
-                    match (first, self.optional, self.allow_trail) {
-                          (true, false, _)
-                        | (false, _, false) => {
-                            return Err(ret_err(err, opt_error));
+                    let found = if tokenizer.recovers(&err) {
+                        find_on_line(&self.by, tokenizer, &state, chars, offset,
+                            max(err.0.offset, offset), refs)
+                    } else {
+                        None
+                    };
+                    match found {
+                        Some(next) => {
+                            // Skip to the next separator.
+                            let range = Range::new(offset, next - offset);
+                            let state = tokenizer.data(MetaData::Error(err.1),
+                                &state, range);
+                            update(range, None, &mut chars, &mut offset,
+                                &mut opt_error);
+                            state
                         }
-                          (true, true, _)
-                        | (false, _, true) => {
-                            err_update(Some(err), &mut opt_error);
-                            break;
+                        None => match (first, self.optional, self.allow_trail) {
+                              (true, false, _)
+                            | (false, _, false) => {
+                                return Err(ret_err(err, opt_error));
+                            }
+                              (true, true, _)
+                            | (false, _, true) => {
+                                err_update(Some(err), &mut opt_error);
+                                break;
+                            }
                         }
                     }
                 }
@@ -63,6 +95,8 @@ impl SeparatedBy {
                     state
                 }
             };
+            n += 1;
+            if max_count == Some(n) && !self.allow_trail { break; }
             state = match self.by.parse(
                 tokenizer, &state, chars, offset, refs
             ) {
@@ -75,40 +109,107 @@ impl SeparatedBy {
                     state
                 }
             };
+            // A maximum count stops the loop.
+            if offset == item_offset && max_count.is_none() {
+                return Err((Range::empty(offset),
+                    ParseError::InfiniteLoop(self.debug_id)));
+            }
             first = false;
         }
-        Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
+        let range = Range::new(start_offset, offset - start_offset);
+        if let Err(err) = check_count(self.count, n, range, self.debug_id) {
+            return Err(ret_err(err, opt_error));
+        }
+        Ok((range, state, opt_error))
+    }
+
+    /// Composes rule repeatedly separated by another rule.
+    /// Stops when the rule does not match or uses no meta data.
+    /// Never writes a trailing separator.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        mut data: &[(Range, MetaData)],
+        start_offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> Result<Range, ()> {
+        let mut offset = start_offset;
+        let mut first = true;
+        let mut n = 0;
+        loop {
+            if self.count.and_then(|c| c.1) == Some(n) { break; }
+            let len = out.len();
+            let mut item_data = data;
+            let mut item_offset = offset;
+            if !first {
+                match self.by.compose(out, item_data, item_offset, refs) {
+                    Err(()) => {
+                        out.truncate(len);
+                        break;
+                    }
+                    Ok(range) => {
+                        compose::update(range, &mut item_data, &mut item_offset);
+                    }
+                }
+            }
+            match self.rule.compose(out, item_data, item_offset, refs) {
+                Err(()) => {
+                    out.truncate(len);
+                    if first && !self.optional { return Err(()); }
+                    break;
+                }
+                Ok(range) => {
+                    if range.next_offset() == offset {
+                        // Keep the text only when it is required.
+                        if !first || self.optional { out.truncate(len); }
+                        break;
+                    }
+                    compose::update(range, &mut item_data, &mut item_offset);
+                    if let Rule::Whitespace(ref w) = self.by {
+                        if !first && w.optional { compose::separate(out, len); }
+                    }
+                }
+            }
+            data = item_data;
+            offset = item_offset;
+            first = false;
+            n += 1;
+        }
+        if n < self.count.map(|c| c.0).unwrap_or(0) { return Err(()); }
+        Ok(Range::new(start_offset, offset - start_offset))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::*;
-    use std::rc::Rc;
+    use std::sync::Arc;
     use range::Range;
 
     #[test]
     fn required() {
         let text = "foo()";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
         let sep = SeparatedBy {
             debug_id: 0,
             rule: Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {
                 debug_id: 1,
-                any_characters: Rc::new(",)".into()),
+                any_characters: Arc::new(",)".into()),
                 optional: false,
                 property: None,
             }),
             by: Rule::Token(Token {
                 debug_id: 2,
-                text: Rc::new(",".into()),
+                text: Arc::new(",".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             optional: false,
             allow_trail: false,
+            count: None,
         };
         let res = sep.parse(&mut tokenizer, &s, &chars[4..], 4, &[]);
         assert_eq!(res, Err((Range::new(4, 0),
@@ -118,25 +219,27 @@ mod tests {
     #[test]
     fn optional() {
         let text = "foo()";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
         let sep = SeparatedBy {
             debug_id: 0,
             rule: Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {
                 debug_id: 1,
-                any_characters: Rc::new(",)".into()),
+                any_characters: Arc::new(",)".into()),
                 optional: false,
                 property: None,
             }),
             by: Rule::Token(Token {
                 debug_id: 2,
-                text: Rc::new(",".into()),
+                text: Arc::new(",".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             optional: true,
             allow_trail: false,
+            count: None,
         };
         let res = sep.parse(&mut tokenizer, &s, &chars[4..], 4, &[]);
         assert_eq!(res, Ok((Range::new(4, 0), s,
@@ -146,26 +249,28 @@ mod tests {
     #[test]
     fn disallow_trail() {
         let text = "foo(a,b,c,)";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let arg: Rc<String> = Rc::new("arg".into());
+        let arg: Arc<String> = Arc::new("arg".into());
         let sep = SeparatedBy {
             debug_id: 0,
             rule: Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {
                 debug_id: 1,
-                any_characters: Rc::new(",)".into()),
+                any_characters: Arc::new(",)".into()),
                 optional: false,
                 property: Some(arg.clone()),
             }),
             by: Rule::Token(Token {
                 debug_id: 2,
-                text: Rc::new(",".into()),
+                text: Arc::new(",".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             optional: true,
             allow_trail: false,
+            count: None,
         };
         let res = sep.parse(&mut tokenizer, &s, &chars[4..], 4, &[]);
         assert_eq!(res, Err((Range::new(10, 0),
@@ -175,124 +280,158 @@ mod tests {
     #[test]
     fn allow_trail() {
         let text = "foo(a,b,c,)";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let arg: Rc<String> = Rc::new("arg".into());
+        let arg: Arc<String> = Arc::new("arg".into());
         let sep = SeparatedBy {
             debug_id: 0,
             rule: Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {
                 debug_id: 1,
-                any_characters: Rc::new(",)".into()),
+                any_characters: Arc::new(",)".into()),
                 optional: false,
                 property: Some(arg.clone()),
             }),
             by: Rule::Token(Token {
                 debug_id: 2,
-                text: Rc::new(",".into()),
+                text: Arc::new(",".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             optional: true,
             allow_trail: true,
+            count: None,
         };
         let res = sep.parse(&mut tokenizer, &s, &chars[4..], 4, &[]);
         assert_eq!(res, Ok((Range::new(4, 6), TokenizerState(3),
             Some((Range::new(10, 0), ParseError::ExpectedSomething(1))))));
         assert_eq!(tokenizer.tokens.len(), 3);
         assert_eq!(&tokenizer.tokens[0].1,
-            &MetaData::String(arg.clone(), Rc::new("a".into())));
+            &MetaData::String(arg.clone(), Arc::new("a".into())));
         assert_eq!(&tokenizer.tokens[1].1,
-            &MetaData::String(arg.clone(), Rc::new("b".into())));
+            &MetaData::String(arg.clone(), Arc::new("b".into())));
         assert_eq!(&tokenizer.tokens[2].1,
-            &MetaData::String(arg.clone(), Rc::new("c".into())));
+            &MetaData::String(arg.clone(), Arc::new("c".into())));
     }
 
     #[test]
     fn successful() {
         let text = "foo(a,b,c)";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let arg: Rc<String> = Rc::new("arg".into());
+        let arg: Arc<String> = Arc::new("arg".into());
         let sep = SeparatedBy {
             debug_id: 0,
             rule: Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {
                 debug_id: 1,
-                any_characters: Rc::new(",)".into()),
+                any_characters: Arc::new(",)".into()),
                 optional: false,
                 property: Some(arg.clone()),
             }),
             by: Rule::Token(Token {
                 debug_id: 2,
-                text: Rc::new(",".into()),
+                text: Arc::new(",".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             optional: true,
             allow_trail: false,
+            count: None,
         };
         let res = sep.parse(&mut tokenizer, &s, &chars[4..], 4, &[]);
         assert_eq!(res, Ok((Range::new(4, 5), TokenizerState(3),
             Some((Range::new(9, 0),
-                ParseError::ExpectedToken(Rc::new(",".into()), 2))))));
+                ParseError::ExpectedToken(Arc::new(",".into()), 2))))));
         assert_eq!(tokenizer.tokens.len(), 3);
         assert_eq!(&tokenizer.tokens[0].1,
-            &MetaData::String(arg.clone(), Rc::new("a".into())));
+            &MetaData::String(arg.clone(), Arc::new("a".into())));
         assert_eq!(&tokenizer.tokens[1].1,
-            &MetaData::String(arg.clone(), Rc::new("b".into())));
+            &MetaData::String(arg.clone(), Arc::new("b".into())));
         assert_eq!(&tokenizer.tokens[2].1,
-            &MetaData::String(arg.clone(), Rc::new("c".into())));
+            &MetaData::String(arg.clone(), Arc::new("c".into())));
     }
 
     #[test]
     fn nested() {
         let text = "a,b,c;d,e,f;";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let arg: Rc<String> = Rc::new("arg".into());
+        let arg: Arc<String> = Arc::new("arg".into());
         let sep = SeparatedBy {
             debug_id: 0,
             rule: Rule::SeparatedBy(Box::new(SeparatedBy {
                 debug_id: 1,
                 rule: Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {
                     debug_id: 2,
-                    any_characters: Rc::new(",;".into()),
+                    any_characters: Arc::new(",;".into()),
                     optional: false,
                     property: Some(arg.clone()),
                 }),
                 by: Rule::Token(Token {
                     debug_id: 3,
-                    text: Rc::new(",".into()),
+                    text: Arc::new(",".into()),
                     inverted: false,
+                    case_insensitive: false,
                     property: None,
                 }),
                 optional: false,
                 allow_trail: true,
+                count: None,
             })),
             by: Rule::Token(Token {
                 debug_id: 4,
-                text: Rc::new(";".into()),
+                text: Arc::new(";".into()),
                 inverted: false,
+                case_insensitive: false,
                 property: None,
             }),
             optional: false,
             allow_trail: true,
+            count: None,
         };
         let res = sep.parse(&mut tokenizer, &s, &chars, 0, &[]);
         assert_eq!(res, Ok((Range::new(0, 12), TokenizerState(6),
             Some((Range::new(12, 0), ParseError::ExpectedSomething(2))))));
         assert_eq!(tokenizer.tokens.len(), 6);
         assert_eq!(&tokenizer.tokens[0].1,
-            &MetaData::String(arg.clone(), Rc::new("a".into())));
+            &MetaData::String(arg.clone(), Arc::new("a".into())));
         assert_eq!(&tokenizer.tokens[1].1,
-            &MetaData::String(arg.clone(), Rc::new("b".into())));
+            &MetaData::String(arg.clone(), Arc::new("b".into())));
         assert_eq!(&tokenizer.tokens[2].1,
-            &MetaData::String(arg.clone(), Rc::new("c".into())));
+            &MetaData::String(arg.clone(), Arc::new("c".into())));
         assert_eq!(&tokenizer.tokens[3].1,
-            &MetaData::String(arg.clone(), Rc::new("d".into())));
+            &MetaData::String(arg.clone(), Arc::new("d".into())));
         assert_eq!(&tokenizer.tokens[4].1,
-            &MetaData::String(arg.clone(), Rc::new("e".into())));
+            &MetaData::String(arg.clone(), Arc::new("e".into())));
+    }
+
+    #[test]
+    fn infinite_loop() {
+        let text = "foo";
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let sep = SeparatedBy {
+            debug_id: 0,
+            rule: Rule::UntilAnyOrWhitespace(UntilAnyOrWhitespace {
+                debug_id: 1,
+                any_characters: Arc::new(",".into()),
+                optional: true,
+                property: None,
+            }),
+            by: Rule::Whitespace(Whitespace {
+                debug_id: 2,
+                optional: true,
+                comments: None,
+            }),
+            optional: false,
+            allow_trail: false,
+            count: None,
+        };
+        let res = sep.parse(&mut tokenizer, &s, text, 0, &[]);
+        assert_eq!(res, Err((Range::new(3, 0), ParseError::InfiniteLoop(0))));
     }
 }
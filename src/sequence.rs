@@ -1,10 +1,12 @@
 use range::Range;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use {
+    compose,
     ret_err,
     update,
     DebugId,
+    MetaData,
     ParseResult,
     Rule,
     Tokenizer,
@@ -27,9 +29,9 @@ impl Sequence {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        mut chars: &[char],
+        mut chars: &str,
         start_offset: usize,
-        refs: &[(Rc<String>, Rule)]
+        refs: &[(Arc<String>, Rule)]
     ) -> ParseResult<TokenizerState> {
         let mut offset = start_offset;
         let mut state = state.clone();
@@ -49,4 +51,31 @@ impl Sequence {
         }
         Ok((Range::new(start_offset, offset - start_offset), state, opt_error))
     }
+
+    /// Composes sequence.
+    /// Fails if any sub rule fails.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        mut data: &[(Range, MetaData)],
+        start_offset: usize,
+        refs: &[(Arc<String>, Rule)]
+    ) -> Result<Range, ()> {
+        let mut offset = start_offset;
+        // Where optional whitespace was composed, until text follows.
+        let mut space = None;
+        for sub_rule in &self.args {
+            let len = out.len();
+            let range = try!(sub_rule.compose(out, data, offset, refs));
+            compose::update(range, &mut data, &mut offset);
+            match sub_rule {
+                &Rule::Whitespace(ref w) if w.optional => space = Some(len),
+                _ if out.len() > len => {
+                    if let Some(at) = space.take() { compose::separate(out, at); }
+                }
+                _ => {}
+            }
+        }
+        Ok(Range::new(start_offset, offset - start_offset))
+    }
 }
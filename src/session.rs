@@ -0,0 +1,132 @@
+use range::Range;
+use std::sync::Arc;
+
+use {
+    parse_with_memo,
+    MetaData,
+    ParseError,
+    Rule,
+    Tokenizer,
+};
+
+/// Keeps a document parsed while it is being edited.
+///
+/// The results of named rules are stored with the part of the text
+/// they examined. When the text is changed, only the results affected by
+/// the change are removed, and the rest are replayed when parsing again.
+/// This gives the same result as parsing the whole document.
+pub struct Session {
+    rules: Vec<(Arc<String>, Rule)>,
+    text: String,
+    tokenizer: Tokenizer<'static>,
+    result: Result<Vec<(Range, MetaData)>, (Range, ParseError)>,
+}
+
+impl Session {
+    /// Creates a new session and parses the text.
+    pub fn new(rules: Vec<(Arc<String>, Rule)>, text: String) -> Session {
+        let mut tokenizer = Tokenizer::with_memo();
+        let result = parse_with_memo(&mut tokenizer, &rules, &text);
+        Session {
+            rules: rules,
+            text: text,
+            tokenizer: tokenizer,
+            result: result,
+        }
+    }
+
+    /// Replaces a byte range of the text and parses again.
+    pub fn edit(
+        &mut self,
+        range: Range,
+        replacement: &str
+    ) -> &Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
+        self.text = format!("{}{}{}", &self.text[..range.offset], replacement,
+            &self.text[range.next_offset()..]);
+        if let Some(ref mut memo) = self.tokenizer.memo {
+            memo.edit(range, replacement.len());
+        }
+        self.result = parse_with_memo(&mut self.tokenizer, &self.rules, &self.text);
+        &self.result
+    }
+
+    /// Returns the result of the last parse.
+    pub fn result(&self) -> &Result<Vec<(Range, MetaData)>, (Range, ParseError)> {
+        &self.result
+    }
+
+    /// Returns the current text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the tokenizer, for example to read the memo statistics.
+    pub fn tokenizer(&self) -> &Tokenizer<'static> {
+        &self.tokenizer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use range::Range;
+
+    #[test]
+    fn edit() {
+        let rules = r#"
+1 "item" [t!"name" w? ":" w? $"value"]
+2 "document" [w? s?.([w? "," w?]) {@"item""item"} w?]
+        "#;
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let text = r#""a": 1, "b": 2, "c": 3"#;
+        let mut session = Session::new(rules.clone(), text.into());
+        assert_eq!(session.result(), &parse(&rules, text));
+
+        // Change the value of the last item.
+        session.edit(Range::new(21, 1), "42");
+        assert_eq!(session.text(), r#""a": 1, "b": 2, "c": 42"#);
+        assert_eq!(session.result(), &parse(&rules, session.text()));
+        assert!(session.tokenizer().memo.as_ref().unwrap().hits > 0);
+
+        // Insert an item in the middle.
+        session.edit(Range::new(8, 0), r#""x": 5, "#);
+        assert_eq!(session.result(), &parse(&rules, session.text()));
+        assert_eq!(session.result().as_ref().unwrap().len(), 16);
+
+        // Break the document and fix it again.
+        session.edit(Range::new(13, 1), "x");
+        assert_eq!(session.text(), r#""a": 1, "x": x, "b": 2, "c": 42"#);
+        assert!(session.result().is_err());
+        assert_eq!(session.result(), &parse(&rules, session.text()));
+        session.edit(Range::new(13, 1), "7");
+        assert_eq!(session.result(), &parse(&rules, session.text()));
+
+        // Join two items by removing the separator.
+        session.edit(Range::new(14, 2), "");
+        assert_eq!(session.result(), &parse(&rules, session.text()));
+    }
+
+    #[test]
+    fn self_syntax() {
+        use std::fs::File;
+        use std::io::Read;
+
+        let mut source = String::new();
+        File::open("assets/self-syntax.txt").unwrap()
+            .read_to_string(&mut source).unwrap();
+        let rules = bootstrap::rules();
+        let mut session = Session::new(rules.clone(), source);
+        let offset = session.text().len();
+        session.edit(Range::new(offset, 0), "\n");
+        assert_eq!(session.result(), &parse(&rules, session.text()));
+        session.edit(Range::new(0, 0), "?");
+        assert!(session.result().is_err());
+        assert_eq!(session.result(), &parse(&rules, session.text()));
+        session.edit(Range::new(0, 1), "");
+        assert_eq!(session.result(), &parse(&rules, session.text()));
+        assert!(session.tokenizer().memo.as_ref().unwrap().hits > 0);
+    }
+}
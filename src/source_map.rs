@@ -0,0 +1,170 @@
+use range::Range;
+use std::cmp::min;
+
+/// Tells how to count columns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// Count unicode characters.
+    Chars,
+    /// Count UTF-16 code units, as used by editors following LSP.
+    Utf16,
+    /// Count UTF-8 bytes.
+    Bytes,
+}
+
+/// A line and column in a text document.
+/// Both start at 1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    /// The line number.
+    pub line: usize,
+    /// The column number.
+    pub column: usize,
+}
+
+/// Stores the lines of a text document,
+/// used to convert offsets to line and column.
+pub struct SourceMap<'a> {
+    lines: Vec<(Range, &'a str)>,
+}
+
+impl<'a> SourceMap<'a> {
+    /// Creates a new source map.
+    pub fn new(text: &'a str) -> SourceMap<'a> {
+        let mut start = 0;
+        let mut lines = vec![];
+        for line in text.split('\n') {
+            let length = line.len();
+            lines.push((Range::new(start, length), line));
+            // Lines are separated by '\n'.
+            start += length + 1;
+        }
+
+        SourceMap {
+            lines: lines,
+        }
+    }
+
+    /// Returns the lines with the range they cover in the document.
+    pub fn lines(&self) -> &[(Range, &'a str)] {
+        &self.lines
+    }
+
+    /// Returns the line index containing offset.
+    /// An offset past the end of the document belongs to the last line.
+    pub fn line_index(&self, offset: usize) -> usize {
+        match self.lines.binary_search_by(|&(r, _)| r.offset.cmp(&offset)) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        }
+    }
+
+    /// Converts offset to line and column.
+    /// An offset past the end of the line is clamped to the end of the line,
+    /// and an offset inside a character to the start of the character.
+    pub fn position(&self, offset: usize, unit: ColumnUnit) -> Position {
+        let i = self.line_index(offset);
+        let (r, text) = self.lines[i];
+        let mut end = min(offset - r.offset, text.len());
+        while !text.is_char_boundary(end) { end -= 1; }
+        let chars = text[..end].chars();
+        let column = match unit {
+            ColumnUnit::Chars => chars.count(),
+            ColumnUnit::Utf16 => chars.map(|c| c.len_utf16()).sum(),
+            ColumnUnit::Bytes => chars.map(|c| c.len_utf8()).sum(),
+        };
+        Position { line: i + 1, column: column + 1 }
+    }
+
+    /// Converts range to start and end position.
+    pub fn range(&self, range: Range, unit: ColumnUnit) -> (Position, Position) {
+        (self.position(range.offset, unit),
+         self.position(range.next_offset(), unit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use range::Range;
+    use std::sync::Arc;
+
+    #[test]
+    fn position() {
+        let text = "ab\nc\u{e6}d\n\u{1f600}e";
+        let source_map = SourceMap::new(text);
+        assert_eq!(source_map.lines().len(), 3);
+        assert_eq!(source_map.position(0, ColumnUnit::Chars),
+            Position { line: 1, column: 1 });
+        assert_eq!(source_map.position(2, ColumnUnit::Chars),
+            Position { line: 1, column: 3 });
+        assert_eq!(source_map.position(3, ColumnUnit::Chars),
+            Position { line: 2, column: 1 });
+        assert_eq!(source_map.position(6, ColumnUnit::Chars),
+            Position { line: 2, column: 3 });
+        assert_eq!(source_map.position(6, ColumnUnit::Bytes),
+            Position { line: 2, column: 4 });
+        assert_eq!(source_map.position(12, ColumnUnit::Chars),
+            Position { line: 3, column: 2 });
+        assert_eq!(source_map.position(12, ColumnUnit::Utf16),
+            Position { line: 3, column: 3 });
+        assert_eq!(source_map.position(12, ColumnUnit::Bytes),
+            Position { line: 3, column: 5 });
+        assert_eq!(source_map.range(Range::new(3, 9), ColumnUnit::Chars),
+            (Position { line: 2, column: 1 }, Position { line: 3, column: 2 }));
+    }
+
+    #[test]
+    fn clamp() {
+        let text = "ab\nc\u{e6}d\n\u{1f600}e";
+        let source_map = SourceMap::new(text);
+        assert_eq!(source_map.position(text.len(), ColumnUnit::Chars),
+            Position { line: 3, column: 3 });
+        assert_eq!(source_map.position(text.len() + 1, ColumnUnit::Chars),
+            Position { line: 3, column: 3 });
+        // Inside the smiley.
+        assert_eq!(source_map.position(10, ColumnUnit::Chars),
+            Position { line: 3, column: 1 });
+        assert_eq!(source_map.position(5, ColumnUnit::Bytes),
+            Position { line: 2, column: 2 });
+    }
+
+    #[test]
+    fn parse_positions() {
+        let num: Arc<String> = Arc::new("num".into());
+        let rule = Rule::Lines(Box::new(Lines {
+            debug_id: 0,
+            comments: None,
+            rule: Rule::Sequence(Sequence {
+                debug_id: 1,
+                args: vec![
+                    Rule::Whitespace(Whitespace {
+                        debug_id: 2,
+                        optional: true,
+                        comments: None,
+                    }),
+                    Rule::Number(Number {
+                        debug_id: 3,
+                        property: Some(num.clone()),
+                        allow_underscore: false,
+                        kind: NumberKind::F64,
+                        allow_radix: false,
+                        require_sign: false,
+                        allow_special: false,
+                    })
+                ]
+            })
+        }));
+        let rules = [(Arc::new("".into()), rule)];
+        let res = parse_with_positions(&rules, "1\n \u{e6}", ColumnUnit::Bytes);
+        assert_eq!(res, Err((Range::new(3, 0), Position { line: 2, column: 2 },
+            ParseError::ExpectedNumber(3))));
+        let res = parse_with_positions(&rules, "1\n  2", ColumnUnit::Chars);
+        assert_eq!(res, Ok(vec![
+            (Range::new(0, 1), Position { line: 1, column: 1 },
+                MetaData::F64(num.clone(), 1.0)),
+            (Range::new(4, 1), Position { line: 2, column: 3 },
+                MetaData::F64(num.clone(), 2.0)),
+        ]));
+    }
+}
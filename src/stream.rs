@@ -0,0 +1,379 @@
+use range::Range;
+use std::cmp::max;
+use std::fmt;
+use std::io::{ self, Read };
+use std::mem;
+use std::sync::Arc;
+use std::str;
+
+use {
+    err_update,
+    parse,
+    ret_err,
+    Lines,
+    MetaData,
+    ParseError,
+    Rule,
+    Tokenizer,
+    TokenizerState,
+};
+
+/// The number of bytes to read at a time.
+const CHUNK_SIZE: usize = 8192;
+
+/// An error when parsing from a reader.
+#[derive(Debug)]
+pub enum StreamError {
+    /// Failed to read, or the input is not valid UTF-8.
+    Io(io::Error),
+    /// Failed to parse.
+    Parse(Range, ParseError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            &StreamError::Io(ref err) => fmt::Display::fmt(err, fmt),
+            &StreamError::Parse(range, ref err) =>
+                write!(fmt, "{} at {}", err, range.offset),
+        }
+    }
+}
+
+/// Parses text from a reader, one line item at a time.
+///
+/// When the last rule is `Lines`, the input is read in chunks and
+/// the meta data of each item is returned as soon as it is complete.
+/// Memory is bounded by the largest item instead of the whole input.
+/// Otherwise, the whole input is read and returned as a single item.
+///
+/// Ranges are byte offsets from the start of the input,
+/// the same as for `parse`.
+pub fn parse_stream<'a, R: Read>(
+    rules: &'a [(Arc<String>, Rule)],
+    reader: R
+) -> Stream<'a, R> {
+    Stream {
+        rules: rules,
+        reader: reader,
+        bytes: vec![],
+        text: String::new(),
+        offset: 0,
+        eof: false,
+        done: false,
+        new_lines: true,
+        opt_error: None,
+        tokenizer: Tokenizer::new(),
+    }
+}
+
+/// Iterates over the meta data of each line item read from a reader.
+pub struct Stream<'a, R> {
+    rules: &'a [(Arc<String>, Rule)],
+    reader: R,
+    /// Bytes of an incomplete character at the end of the last chunk.
+    bytes: Vec<u8>,
+    /// Text read but not yet consumed.
+    text: String,
+    /// The offset of the first buffered character.
+    offset: usize,
+    eof: bool,
+    done: bool,
+    /// Whether a new line occured since the last item.
+    new_lines: bool,
+    opt_error: Option<(Range, ParseError)>,
+    tokenizer: Tokenizer<'static>,
+}
+
+impl<'a, R: Read> Stream<'a, R> {
+    /// Reads more characters into the buffer.
+    /// Reads at least as much as buffered, to avoid parsing items
+    /// many times when they span multiple chunks.
+    fn fill(&mut self) -> Result<(), io::Error> {
+        let start = self.bytes.len();
+        self.bytes.resize(start + max(CHUNK_SIZE, self.text.len()), 0);
+        let n = loop {
+            match self.reader.read(&mut self.bytes[start..]) {
+                Ok(n) => break n,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.bytes.truncate(start);
+                    return Err(err);
+                }
+            }
+        };
+        self.bytes.truncate(start + n);
+        if n == 0 { self.eof = true; }
+        let valid = match str::from_utf8(&self.bytes) {
+            Ok(text) => {
+                self.text.push_str(text);
+                self.bytes.len()
+            }
+            Err(err) => {
+                let valid = err.valid_up_to();
+                if err.error_len().is_some() || self.eof {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData,
+                        "stream did not contain valid UTF-8"));
+                }
+                self.text.push_str(str::from_utf8(&self.bytes[..valid])
+                    .unwrap());
+                valid
+            }
+        };
+        self.bytes.drain(..valid);
+        Ok(())
+    }
+
+    /// Removes characters up to offset from the buffer.
+    fn consume(&mut self, offset: usize) {
+        self.text.drain(..offset - self.offset);
+        self.offset = offset;
+    }
+
+    /// Reads the whole input and parses it with the last rule.
+    fn parse_all(&mut self) -> Result<Vec<(Range, MetaData)>, StreamError> {
+        while !self.eof {
+            try!(self.fill().map_err(StreamError::Io));
+        }
+        let text = mem::replace(&mut self.text, String::new());
+        parse(self.rules, &text).map_err(|(range, err)| {
+            StreamError::Parse(range, err)
+        })
+    }
+
+    /// Reads the next item using the same steps as `Lines::parse`.
+    fn next_item(&mut self, lines: &Lines)
+    -> Result<Option<Vec<(Range, MetaData)>>, StreamError> {
+        loop {
+            let len = match lines.space_len(&self.text) {
+                Ok(len) => len,
+                Err(_) if !self.eof => {
+                    // The comment might end in the next chunk.
+                    try!(self.fill().map_err(StreamError::Io));
+                    continue;
+                }
+                Err(i) => {
+                    let range = Range::new(self.offset + i, self.text.len() - i);
+                    let err = ParseError::ExpectedCommentEnd(lines.debug_id);
+                    return Err(StreamError::Parse(range, err));
+                }
+            };
+            let start_len = lines.comments.as_ref().map(|c| c.start_len()).unwrap_or(0);
+            if !self.eof && self.text.len() - len < start_len {
+                // A comment might start at the end of the chunk.
+                try!(self.fill().map_err(StreamError::Io));
+                continue;
+            }
+            if len == self.text.len() {
+                if self.eof { return Ok(None); }
+                try!(self.fill().map_err(StreamError::Io));
+                continue;
+            } else if self.text.as_bytes()[len] == b'\n' {
+                let offset = self.offset + len + 1;
+                self.consume(offset);
+                self.new_lines = true;
+                continue;
+            } else if !self.new_lines {
+                let err = (Range::new(self.offset, 0),
+                    ParseError::ExpectedNewLine(lines.debug_id));
+                let (range, err) = ret_err(err, self.opt_error.take());
+                return Err(StreamError::Parse(range, err));
+            }
+
+            if lines.comments.is_some() {
+                // The rule starts after the comments.
+                let offset = self.offset + len;
+                self.consume(offset);
+            }
+            let offset = self.offset;
+            self.tokenizer.tokens.clear();
+            self.tokenizer.examined = offset;
+            let res = lines.rule.parse(&mut self.tokenizer,
+                &TokenizerState::new(), &self.text, offset, self.rules);
+            // The item might continue in the next chunk
+            // if the rule looked at the end of the buffer.
+            if !self.eof && self.tokenizer.examined >= offset + self.text.len() {
+                try!(self.fill().map_err(StreamError::Io));
+                continue;
+            }
+            match res {
+                Err(err) => {
+                    err_update(Some(err), &mut self.opt_error);
+                    let err = (Range::empty(offset), ParseError::ExpectedEnd);
+                    let (range, err) = ret_err(err, self.opt_error.take());
+                    return Err(StreamError::Parse(range, err));
+                }
+                Ok((range, state, err)) => {
+                    // Find whether a new line occured at the end.
+                    let end = range.next_offset() - offset;
+                    self.new_lines = self.text[..end]
+                        .trim_end_matches(|c: char| c != '\n' && c.is_whitespace())
+                        .ends_with('\n');
+                    err_update(err, &mut self.opt_error);
+                    self.consume(range.next_offset());
+                    self.tokenizer.tokens.truncate(state.0);
+                    return Ok(Some(self.tokenizer.tokens.drain(..).collect()));
+                }
+            }
+        }
+    }
+}
+
+impl<'a, R: Read> Iterator for Stream<'a, R> {
+    type Item = Result<Vec<(Range, MetaData)>, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None; }
+        let rules = self.rules;
+        let res = match rules.last() {
+            None => Err(StreamError::Parse(Range::empty(0), ParseError::NoRules)),
+            Some(&(_, Rule::Lines(ref lines))) => self.next_item(lines),
+            Some(_) => {
+                self.done = true;
+                self.parse_all().map(Some)
+            }
+        };
+        match res {
+            Ok(Some(data)) => Some(Ok(data)),
+            Ok(None) => { self.done = true; None }
+            Err(err) => { self.done = true; Some(Err(err)) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use std::io::{ self, Read };
+
+    /// Reads a few bytes at a time.
+    struct Chunks<'a> {
+        bytes: &'a [u8],
+        size: usize,
+    }
+
+    impl<'a> Read for Chunks<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.size.min(buf.len()).min(self.bytes.len());
+            buf[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Ok(n)
+        }
+    }
+
+    fn rules() -> Vec<(::std::sync::Arc<String>, Rule)> {
+        let rules = r#"
+1 "item" [t!"name" w? ":" w? {$"value" [t?"text" w? "\n" w? t?"text"]}]
+2 "log" l(@"item""item")
+        "#;
+        bootstrap::convert(
+            &parse(&bootstrap::rules(), rules).unwrap(),
+            &mut vec![]
+        ).unwrap()
+    }
+
+    fn collect(rules: &[(::std::sync::Arc<String>, Rule)], text: &str, size: usize)
+    -> Result<Vec<(range::Range, MetaData)>, (range::Range, ParseError)> {
+        let mut data = vec![];
+        for item in parse_stream(rules, Chunks { bytes: text.as_bytes(), size: size }) {
+            match item {
+                Ok(item) => data.extend(item),
+                Err(StreamError::Parse(range, err)) => return Err((range, err)),
+                Err(StreamError::Io(err)) => panic!("{}", err),
+            }
+        }
+        Ok(data)
+    }
+
+    #[test]
+    fn chunks() {
+        let rules = rules();
+        let texts = [
+            "\"a\": 1\n\n  \"b\": 2.5\n\"c\": \"x\"\n  \"y\"\n",
+            "\"a\": 1\n\"b\": \"one\n\"two\"\n\"c\": 3",
+            "\"a\": 1 \"b\": 2",
+            "\"a\": 1\n\"b\" 2",
+            "\"a\": 1\n\"b\": \"unterminated",
+            "",
+        ];
+        for text in &texts {
+            for &size in &[1, 2, 3, 7, 100] {
+                assert_eq!(collect(&rules, text, size), parse(&rules, text));
+            }
+        }
+    }
+
+    #[test]
+    fn comments() {
+        let rules = bootstrap::convert(&parse(&bootstrap::rules(), r#"
+comments "//" ["/*" "*/"]
+1 "item" [t!"name" w? ":" w? $"value"]
+2 "log" l(@"item""item")
+        "#).unwrap(), &mut vec![]).unwrap();
+        let texts = [
+            "// log\n\"a\": 1 /* one\n */\n/* b */ \"b\": /**/ 2 // two",
+            "\"a\": 1\n/* unterminated\n\"b\": 2",
+        ];
+        for text in &texts {
+            for &size in &[1, 2, 3, 7, 100] {
+                assert_eq!(collect(&rules, text, size), parse(&rules, text));
+            }
+        }
+        assert!(parse(&rules, texts[0]).is_ok());
+    }
+
+    #[test]
+    fn items() {
+        let rules = rules();
+        let text = "\"a\": 1\n\"b\": 2\n";
+        let items: Vec<_> = parse_stream(&rules, text.as_bytes())
+            .map(|item| item.unwrap().len()).collect();
+        assert_eq!(items, vec![4, 4]);
+    }
+
+    #[test]
+    fn bounded() {
+        let rules = rules();
+        let text: String = (0..5000).map(|i| format!("\"n\": {}\n", i)).collect();
+        let mut stream = parse_stream(&rules, text.as_bytes());
+        let mut n = 0;
+        while let Some(item) = stream.next() {
+            item.unwrap();
+            assert!(stream.text.len() <= super::CHUNK_SIZE);
+            n += 1;
+        }
+        assert_eq!(n, 5000);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let rules = rules();
+        let bytes = [b'"', b'a', b'"', b':', b' ', 0xff];
+        let res: Vec<_> = parse_stream(&rules, &bytes[..]).collect();
+        match res[0] {
+            Err(StreamError::Io(ref err)) =>
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData),
+            _ => panic!("expected invalid data")
+        }
+    }
+
+    #[test]
+    fn unicode() {
+        let rules = rules();
+        let text = "\"\u{e6}\u{1f600}\": 1\n\"b\": 2";
+        let data = collect(&rules, text, 1).unwrap();
+        assert_eq!(collect(&rules, text, 1), parse(&rules, text));
+        assert_eq!(data[1].0, range::Range::new(0, 8));
+        assert_eq!(data[2].0, range::Range::new(10, 1));
+        assert_eq!(data[5].0, range::Range::new(12, 3));
+        assert_eq!(data.len(), 8);
+    }
+
+    #[test]
+    fn not_lines() {
+        let rules = rules();
+        let rules = &rules[..1];
+        let text = "\"a\": 1";
+        assert_eq!(collect(rules, text, 2), parse(rules, text));
+    }
+}
@@ -0,0 +1,270 @@
+//! Functions used by code generated with `bootstrap::codegen`.
+
+use range::Range;
+use read;
+use std::sync::Arc;
+
+use {
+    char_class,
+    comments,
+    indent,
+    node,
+    number,
+    text,
+    token,
+    until_any,
+    until_any_or_whitespace,
+    whitespace,
+    DebugId,
+    MetaData,
+    NumberKind,
+    ParseError,
+    ParseResult,
+    Tokenizer,
+    TokenizerState,
+};
+
+/// Parses text with a function that parses the start rule,
+/// using a tokenizer to store the meta data, like `parse_with_tokenizer`.
+pub fn parse_with<F>(tokenizer: &mut Tokenizer, text: &str, mut f: F)
+-> Result<Vec<(Range, MetaData)>, (Range, ParseError)>
+    where F: FnMut(&mut Tokenizer, &TokenizerState, &str, usize)
+        -> ParseResult<TokenizerState>
+{
+    if let Some(ref mut memo) = tokenizer.memo { memo.clear(); }
+    ::parse_fn(tokenizer, text, 0, true, &mut f)
+        .map(|(_, data)| data)
+}
+
+/// Parses a node, using a function to parse the referenced rule.
+/// The index of the rule is used to detect left recursion.
+#[inline(always)]
+pub fn node<F>(
+    index: usize,
+    debug_id: DebugId,
+    property: Option<&Arc<String>>,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    f: &mut F
+) -> ParseResult<TokenizerState>
+    where F: FnMut(&mut Tokenizer, &TokenizerState, &str, usize)
+        -> ParseResult<TokenizerState>
+{
+    try!(tokenizer.step(debug_id, offset));
+    node::parse_node(index, debug_id, property, tokenizer, state, chars, offset, f)
+}
+
+/// Parses a token, like `Token`.
+#[inline(always)]
+pub fn token(
+    text: &Arc<String>,
+    inverted: bool,
+    case_insensitive: bool,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    try!(tokenizer.step(debug_id, offset));
+    token::parse(text, inverted, case_insensitive, property, debug_id,
+        tokenizer, state, chars, offset)
+}
+
+/// Parses until any of the characters, like `UntilAny`.
+#[inline(always)]
+pub fn until_any(
+    any_characters: &str,
+    optional: bool,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    try!(tokenizer.step(debug_id, offset));
+    until_any::parse(any_characters, optional, property, debug_id,
+        tokenizer, state, chars, offset)
+}
+
+/// Parses until whitespace or any of the characters,
+/// like `UntilAnyOrWhitespace`.
+#[inline(always)]
+pub fn until_any_or_whitespace(
+    any_characters: &str,
+    optional: bool,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    try!(tokenizer.step(debug_id, offset));
+    until_any_or_whitespace::parse(any_characters, optional, property, debug_id,
+        tokenizer, state, chars, offset)
+}
+
+/// Parses a string, like `Text`.
+#[inline(always)]
+pub fn text(
+    allow_empty: bool,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    try!(tokenizer.step(debug_id, offset));
+    text::parse(allow_empty, property, debug_id, tokenizer, state, chars, offset)
+}
+
+/// Parses a number, like `Number`.
+#[inline(always)]
+pub fn number(
+    kind: NumberKind,
+    allow_underscore: bool,
+    allow_radix: bool,
+    require_sign: bool,
+    allow_special: bool,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    try!(tokenizer.step(debug_id, offset));
+    number::parse(kind, allow_underscore, allow_radix, require_sign, allow_special,
+        property, debug_id, tokenizer, state, chars, offset)
+}
+
+/// Parses characters in ranges, like `CharClass`.
+#[inline(always)]
+pub fn char_class(
+    ranges: &[(char, char)],
+    negated: bool,
+    min: usize,
+    max: Option<usize>,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    try!(tokenizer.step(debug_id, offset));
+    char_class::parse(ranges, negated, min, max, property, debug_id,
+        tokenizer, state, chars, offset)
+}
+
+/// Parses whitespace and comments, like `Whitespace`.
+#[inline(always)]
+pub fn whitespace(
+    optional: bool,
+    line: &[&str],
+    block: &[(&str, &str)],
+    nested: bool,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    chars: &str,
+    offset: usize
+) -> Result<Range, (Range, ParseError)> {
+    try!(tokenizer.step(debug_id, offset));
+    let len = comments::whitespace_len(line, block, nested, chars, true);
+    whitespace::check(len, optional, debug_id, chars, offset)
+}
+
+/// Returns the number of bytes of whitespace and comments at the start,
+/// stopping at a new line, like `Lines`.
+/// Returns `Err(i)` if a block comment starting at byte `i` does not end.
+#[inline(always)]
+pub fn line_comments_len(
+    line: &[&str],
+    block: &[(&str, &str)],
+    nested: bool,
+    chars: &str
+) -> Result<usize, usize> {
+    comments::whitespace_len(line, block, nested, chars, false)
+}
+
+/// Parses an indented block, using a function to parse each line,
+/// like `Indent`.
+#[inline(always)]
+pub fn indent<F, G>(
+    tabs: bool,
+    debug_id: DebugId,
+    space_len: &G,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize,
+    f: &mut F
+) -> ParseResult<TokenizerState>
+    where F: FnMut(&mut Tokenizer, &TokenizerState, &str, usize)
+        -> ParseResult<TokenizerState>,
+          G: Fn(&str) -> Result<usize, usize>
+{
+    indent::parse_indent(tabs, debug_id, space_len, tokenizer, state, chars, offset, f)
+}
+
+/// Returns an error if fewer items were read than the minimum count,
+/// like `Repeat` and `SeparatedBy`.
+#[inline(always)]
+pub fn check_count(
+    min: usize,
+    max: Option<usize>,
+    n: usize,
+    range: Range,
+    debug_id: DebugId
+) -> Result<(), (Range, ParseError)> {
+    ::check_count(Some((min, max)), n, range, debug_id)
+}
+
+/// Updates the parser state after a sub rule succeeds.
+#[inline(always)]
+pub fn update<'a>(
+    range: Range,
+    err: Option<(Range, ParseError)>,
+    chars: &mut &'a str,
+    offset: &mut usize,
+    opt_error: &mut Option<(Range, ParseError)>
+) {
+    ::update(range, err, chars, offset, opt_error)
+}
+
+/// Picks the deepest error, overwriting with the newest one if they are
+/// equally deep.
+#[inline(always)]
+pub fn err_update(
+    err: Option<(Range, ParseError)>,
+    opt_error: &mut Option<(Range, ParseError)>
+) {
+    ::err_update(err, opt_error)
+}
+
+/// Picks the deepest of an error and an optional error.
+#[inline(always)]
+pub fn ret_err(a: (Range, ParseError), b: Option<(Range, ParseError)>) ->
+    (Range, ParseError) {
+    ::ret_err(a, b)
+}
+
+/// Returns the number of bytes of whitespace at the start,
+/// stopping at a new line.
+#[inline(always)]
+pub fn line_whitespace_len(chars: &str) -> usize {
+    read::line_whitespace_len(chars)
+}
+
+/// Returns `true` if text ends with a new line,
+/// ignoring other whitespace after it.
+#[inline(always)]
+pub fn ends_with_new_line(text: &str) -> bool {
+    ::ends_with_new_line(text)
+}
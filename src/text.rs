@@ -1,8 +1,9 @@
-use read_token;
+use read;
 use range::Range;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use {
+    compose,
     DebugId,
     MetaData,
     ParseError,
@@ -17,7 +18,7 @@ pub struct Text {
     /// Whether to allow empty string.
     pub allow_empty: bool,
     /// Which property to set if text is read.
-    pub property: Option<Rc<String>>,
+    pub property: Option<Arc<String>>,
     /// A debug id to track down the rule generating an error.
     pub debug_id: DebugId,
 }
@@ -28,35 +29,74 @@ impl Text {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        chars: &[char],
+        chars: &str,
         offset: usize
     ) -> ParseResult<TokenizerState> {
-        if let Some(range) = read_token::string(chars, offset) {
-            if !self.allow_empty && range.length == 2 {
-                Err((range, ParseError::EmptyTextNotAllowed(self.debug_id)))
-            } else {
-                match read_token::parse_string(
-                    chars, offset, range.next_offset()) {
-                    // Focus range to invalid string format.
-                    Err(err) => Err((err.range(),
-                        ParseError::ParseStringError(err, self.debug_id))),
-                    Ok(text) => {
-                        if let Some(ref property) = self.property {
-                            Ok((range, tokenizer.data(
-                                MetaData::String(property.clone(), Rc::new(text)),
-                                state,
-                                range
-                            ), None))
-                        } else {
-                            Ok((range, state.clone(), None))
-                        }
+        parse(self.allow_empty, self.property.as_ref(), self.debug_id,
+            tokenizer, state, chars, offset)
+    }
+
+    /// Composes text.
+    /// Writes the string property quoted with character escapes.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        data: &[(Range, MetaData)],
+        offset: usize
+    ) -> Result<Range, ()> {
+        match self.property {
+            Some(ref property) => {
+                let (range, val) = try!(compose::meta_string(
+                    property, data, offset));
+                if !self.allow_empty && val.len() == 0 { return Err(()); }
+                compose::write_string(&val, out);
+                Ok(range)
+            }
+            None => {
+                // There is no data to tell what the text is.
+                if !self.allow_empty { return Err(()); }
+                out.push_str("\"\"");
+                Ok(Range::empty(offset))
+            }
+        }
+    }
+}
+
+/// Parses text, like `Text`.
+/// This is used by generated code to avoid creating the rule each time.
+pub fn parse(
+    allow_empty: bool,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    if let Some(range) = read::string(chars, offset) {
+        if !allow_empty && range.length == 2 {
+            Err((range, ParseError::EmptyTextNotAllowed(debug_id)))
+        } else {
+            match read::parse_string(chars, range) {
+                // Focus range to invalid string format.
+                Err(err) => Err((err.range(),
+                    ParseError::ParseStringError(err, debug_id))),
+                Ok(text) => {
+                    if let Some(property) = property {
+                        Ok((range, tokenizer.data(
+                            MetaData::String(property.clone(), Arc::new(text)),
+                            state,
+                            range
+                        ), None))
+                    } else {
+                        Ok((range, state.clone(), None))
                     }
                 }
             }
-        } else {
-            Err((Range::new(offset, 0),
-                ParseError::ExpectedText(self.debug_id)))
         }
+    } else {
+        Err((Range::new(offset, 0),
+            ParseError::ExpectedText(debug_id)))
     }
 }
 
@@ -64,12 +104,12 @@ impl Text {
 mod tests {
     use super::super::*;
     use range::Range;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn expected_text() {
         let text = "23";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
         let text = Text {
@@ -81,10 +121,26 @@ mod tests {
         assert_eq!(res, Err((Range::new(0, 0), ParseError::ExpectedText(0))));
     }
 
+    #[test]
+    fn unterminated() {
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let text = Text {
+            debug_id: 0,
+            allow_empty: true,
+            property: None
+        };
+        // A single double-quote at the end is not a string.
+        let res = text.parse(&mut tokenizer, &s, "\"", 0);
+        assert_eq!(res, Err((Range::new(0, 0), ParseError::ExpectedText(0))));
+        let res = text.parse(&mut tokenizer, &s, "\"foo", 0);
+        assert_eq!(res, Err((Range::new(0, 0), ParseError::ExpectedText(0))));
+    }
+
     #[test]
     fn empty_string() {
         let text = "\"\"";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
         let text = Text {
@@ -100,10 +156,10 @@ mod tests {
     #[test]
     fn successful() {
         let text = "foo \"hello\"";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let foo: Rc<String> = Rc::new("foo".into());
+        let foo: Arc<String> = Arc::new("foo".into());
         let text = Text {
             debug_id: 0,
             allow_empty: true,
@@ -113,6 +169,6 @@ mod tests {
         assert_eq!(res, Ok((Range::new(4, 7), TokenizerState(1), None)));
         assert_eq!(tokenizer.tokens.len(), 1);
         assert_eq!(&tokenizer.tokens[0].1,
-            &MetaData::String(foo.clone(), Rc::new("hello".into())));
+            &MetaData::String(foo.clone(), Arc::new("hello".into())));
     }
 }
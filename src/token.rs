@@ -1,8 +1,9 @@
 use range::Range;
-use read_token;
-use std::rc::Rc;
+use read;
+use std::sync::Arc;
 
 use {
+    compose,
     DebugId,
     MetaData,
     ParseError,
@@ -15,11 +16,15 @@ use {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Token {
     /// The text to match against.
-    pub text: Rc<String>,
+    pub text: Arc<String>,
     /// Whether to set property to true or false (inverted).
     pub inverted: bool,
+    /// Whether to match text with other upper and lower case letters,
+    /// comparing the Unicode lower case of each character.
+    /// This is not full case folding, for example `"SS"` does not match `"ß"`.
+    pub case_insensitive: bool,
     /// Which property to set if token matches.
-    pub property: Option<Rc<String>>,
+    pub property: Option<Arc<String>>,
     /// A debug id to track down the rule generating an error.
     pub debug_id: DebugId,
 }
@@ -35,44 +40,103 @@ impl Token {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        chars: &[char],
+        chars: &str,
         offset: usize
     ) -> ParseResult<TokenizerState> {
-        if let Some(range) = read_token::token(&self.text, chars, offset) {
-            match &self.property {
-                &Some(ref name) => {
-                    Ok((range, tokenizer.data(
-                        MetaData::Bool(name.clone(), !self.inverted),
-                        &state,
-                        range
-                    ), None))
-                }
-                _ => {
-                    return Ok((range, state.clone(), None))
-                }
+        parse(&self.text, self.inverted, self.case_insensitive,
+            self.property.as_ref(), self.debug_id, tokenizer, state, chars, offset)
+    }
+
+    /// Reads the token, returns `None` if it does not match.
+    /// The range is the length of the text read,
+    /// which can differ from the token when ignoring case.
+    pub fn read(&self, chars: &str, offset: usize) -> Option<Range> {
+        read(&self.text, self.case_insensitive, chars, offset).0
+    }
+
+    /// Composes token.
+    /// If the token is linked to a property,
+    /// the text is only written when the property has the expected value.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        data: &[(Range, MetaData)],
+        offset: usize
+    ) -> Result<Range, ()> {
+        let range = match self.property {
+            Some(ref name) => {
+                let (range, val) = try!(compose::meta_bool(name, data, offset));
+                if val == self.inverted { return Err(()); }
+                range
             }
-        } else {
-            return Err((Range::new(offset, 0),
-                ParseError::ExpectedToken(self.text.clone(),
-                self.debug_id)));
+            None => Range::empty(offset)
+        };
+        out.push_str(&self.text);
+        Ok(range)
+    }
+}
+
+/// Parses token, like `Token`.
+/// This is used by generated code to avoid creating the rule each time.
+pub fn parse(
+    text: &Arc<String>,
+    inverted: bool,
+    case_insensitive: bool,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    let (range, examined) = read(text, case_insensitive, chars, offset);
+    tokenizer.examine(offset + examined);
+    if let Some(range) = range {
+        match property {
+            Some(name) => {
+                Ok((range, tokenizer.data(
+                    MetaData::Bool(name.clone(), !inverted),
+                    &state,
+                    range
+                ), None))
+            }
+            None => Ok((range, state.clone(), None))
         }
+    } else {
+        Err((Range::new(offset, 0),
+            ParseError::ExpectedToken(text.clone(), debug_id)))
+    }
+}
+
+/// Reads token, returns `None` if it does not match.
+/// Also returns the number of bytes of the text that were compared.
+pub fn read(text: &str, case_insensitive: bool, chars: &str, offset: usize)
+-> (Option<Range>, usize) {
+    if case_insensitive {
+        match read::compare_ignore_case(text, chars) {
+            Ok(len) => (Some(Range::new(offset, len)), len),
+            Err(compared) => (None, compared),
+        }
+    } else {
+        (read::token(text, chars, offset), text.len())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::*;
-    use std::rc::Rc;
+    use std::sync::Arc;
     use range::Range;
 
     #[test]
     fn expected_token() {
         let text = ")";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let start_parenthesis = Token {
             debug_id: 0,
-            text: Rc::new("(".into()),
+            text: Arc::new("(".into()),
             inverted: false,
+            case_insensitive: false,
             property: None
         };
         let mut tokenizer = Tokenizer::new();
@@ -80,7 +144,7 @@ mod tests {
         let res = start_parenthesis.parse(&mut tokenizer, &s, &chars, 0);
         assert_eq!(res, Err((
             Range::new(0, 0),
-            ParseError::ExpectedToken(Rc::new("(".into()), 0)
+            ParseError::ExpectedToken(Arc::new("(".into()), 0)
             ))
         );
     }
@@ -88,11 +152,12 @@ mod tests {
     #[test]
     fn successful() {
         let text = "fn foo()";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let fn_ = Token {
             debug_id: 0,
-            text: Rc::new("fn ".into()),
+            text: Arc::new("fn ".into()),
             inverted: false,
+            case_insensitive: false,
             property: None
         };
         let mut tokenizer = Tokenizer::new();
@@ -103,11 +168,12 @@ mod tests {
 
         // Set bool property.
         let mut tokenizer = Tokenizer::new();
-        let has_arguments: Rc<String> = Rc::new("has_arguments".into());
+        let has_arguments: Arc<String> = Arc::new("has_arguments".into());
         let start_parenthesis = Token {
             debug_id: 0,
-            text: Rc::new("(".into()),
+            text: Arc::new("(".into()),
             inverted: false,
+            case_insensitive: false,
             property: Some(has_arguments.clone())
         };
         let s = TokenizerState::new();
@@ -119,11 +185,12 @@ mod tests {
 
         // Set inverted bool property.
         let mut tokenizer = Tokenizer::new();
-        let has_arguments: Rc<String> = Rc::new("has_no_arguments".into());
+        let has_arguments: Arc<String> = Arc::new("has_no_arguments".into());
         let start_parenthesis = Token {
             debug_id: 0,
-            text: Rc::new("(".into()),
+            text: Arc::new("(".into()),
             inverted: true,
+            case_insensitive: false,
             property: Some(has_arguments.clone())
         };
         let s = TokenizerState::new();
@@ -133,4 +200,54 @@ mod tests {
         assert_eq!(&tokenizer.tokens[0].1,
             &MetaData::Bool(has_arguments.clone(), false));
     }
+
+    #[test]
+    fn case_insensitive() {
+        let mut tokenizer = Tokenizer::new();
+        let s = TokenizerState::new();
+        let token = |text: &str| Token {
+            debug_id: 0,
+            text: Arc::new(text.into()),
+            inverted: false,
+            case_insensitive: true,
+            property: None
+        };
+        let res = token("select").parse(&mut tokenizer, &s, "SeLeCt *", 0);
+        assert_eq!(res, Ok((Range::new(0, 6), s, None)));
+        let res = token("größe").parse(&mut tokenizer, &s, "GRÖßE", 0);
+        assert_eq!(res, Ok((Range::new(0, 7), s, None)));
+        // The Kelvin sign is longer than the letter it matches.
+        let res = token("k").parse(&mut tokenizer, &s, "\u{212A}", 0);
+        assert_eq!(res, Ok((Range::new(0, 3), s, None)));
+        let res = token("select").parse(&mut tokenizer, &s, "SELEC", 0);
+        assert_eq!(res, Err((Range::new(0, 0),
+            ParseError::ExpectedToken(Arc::new("select".into()), 0))));
+    }
+
+    #[test]
+    fn examined() {
+        let s = TokenizerState::new();
+        let token = |text: &str| Token {
+            debug_id: 0,
+            text: Arc::new(text.into()),
+            inverted: false,
+            case_insensitive: true,
+            property: None
+        };
+        // The Kelvin sign is compared, which is longer than the token.
+        let mut tokenizer = Tokenizer::new();
+        let res = token("k").parse(&mut tokenizer, &s, "\u{212A}", 0);
+        assert!(res.is_ok());
+        assert_eq!(tokenizer.examined, 3);
+        // The lower case of the dotted capital I is longer than the token.
+        let mut tokenizer = Tokenizer::new();
+        let res = token("i").parse(&mut tokenizer, &s, "\u{130}", 0);
+        assert!(res.is_err());
+        assert_eq!(tokenizer.examined, 2);
+        // The end of the text is examined.
+        let mut tokenizer = Tokenizer::new();
+        let res = token("select").parse(&mut tokenizer, &s, "SELEC", 0);
+        assert!(res.is_err());
+        assert_eq!(tokenizer.examined, 6);
+    }
 }
@@ -1,19 +1,182 @@
 use range::Range;
+use std::cmp::max;
 
 use {
+    DebugId,
+    LeftRecursion,
+    Memo,
     MetaData,
+    ParseError,
+    Tracer,
 };
 
 /// Stores all the meta data sequentially.
-pub struct Tokenizer {
+pub struct Tokenizer<'a> {
     /// The read tokens.
-    pub tokens: Vec<(Range, MetaData)>
+    pub tokens: Vec<(Range, MetaData)>,
+    /// Caches results of named rules when enabled.
+    pub memo: Option<Memo>,
+    /// Keeps track of named rules in progress.
+    pub left_recursion: LeftRecursion,
+    /// The offset after the last character examined by the current rule.
+    /// Used to tell which results are affected by changes to the text.
+    pub examined: usize,
+    /// The errors to recover from, used by `parse_with_recovery`.
+    pub recover: Vec<(Range, ParseError)>,
+    /// The errors that rules could have recovered from, when recording them.
+    /// Used by `parse_with_recovery` to pick the next error to recover from.
+    pub recoverable: Option<Vec<(Range, ParseError)>>,
+    /// Gets notified when rules are parsed, used by `parse_with_tracer`.
+    pub tracer: Option<&'a mut dyn Tracer>,
+    /// The maximum nesting of nodes,
+    /// used to fail before running out of stack on deeply nested text.
+    pub max_depth: Option<usize>,
+    /// The maximum number of rules to parse,
+    /// used to fail on text that takes too long to parse.
+    pub fuel: Option<usize>,
+    /// The current nesting of nodes.
+    pub depth: usize,
+    /// The number of rules parsed.
+    pub steps: usize,
+    /// The error of the limit that was reached.
+    /// When set, all rules fail with this error.
+    pub limit: Option<(Range, ParseError)>,
+    /// The indentation of the current indented block,
+    /// which is zero outside blocks.
+    pub indent: usize,
+    /// The offset and indentation of each line of the text,
+    /// used to find the indentation of the line before a block.
+    pub lines: Vec<(usize, usize)>,
 }
 
-impl Tokenizer {
+impl<'a> Tokenizer<'a> {
     /// Creates a new tokenizer.
-    pub fn new() -> Tokenizer {
-        Tokenizer { tokens: vec![] }
+    pub fn new() -> Tokenizer<'a> {
+        Tokenizer {
+            tokens: vec![],
+            memo: None,
+            left_recursion: LeftRecursion::new(),
+            examined: 0,
+            recover: vec![],
+            recoverable: None,
+            tracer: None,
+            max_depth: None,
+            fuel: None,
+            depth: 0,
+            steps: 0,
+            limit: None,
+            indent: 0,
+            lines: vec![],
+        }
+    }
+
+    /// Creates a new tokenizer that caches results of named rules.
+    ///
+    /// This avoids parsing the same rule at the same offset twice
+    /// when backtracking, at the cost of memory.
+    pub fn with_memo() -> Tokenizer<'a> {
+        Tokenizer {
+            tokens: vec![],
+            memo: Some(Memo::new()),
+            left_recursion: LeftRecursion::new(),
+            examined: 0,
+            recover: vec![],
+            recoverable: None,
+            tracer: None,
+            max_depth: None,
+            fuel: None,
+            depth: 0,
+            steps: 0,
+            limit: None,
+            indent: 0,
+            lines: vec![],
+        }
+    }
+
+    /// Stores the offset and indentation of each line of the text.
+    pub fn read_lines(&mut self, text: &str) {
+        self.lines.clear();
+        let mut start = 0;
+        for line in text.split('\n') {
+            let n = line.bytes().take_while(|&b| b == b' ' || b == b'\t').count();
+            self.lines.push((start, n));
+            start += line.len() + 1;
+        }
+    }
+
+    /// Returns the indentation that a block starting at an offset must exceed,
+    /// which is the deepest of the current block and the line containing offset.
+    /// Uses the current block only when the lines of the text are unknown.
+    pub fn block_indent(&self, offset: usize) -> usize {
+        let line = match self.lines.binary_search_by(|&(start, _)| start.cmp(&offset)) {
+            Ok(i) => self.lines[i].1,
+            Err(0) => 0,
+            Err(i) => self.lines[i - 1].1,
+        };
+        max(self.indent, line)
+    }
+
+    /// Counts a rule parsed at an offset.
+    /// Returns an error if the fuel runs out or a limit was reached before.
+    #[inline(always)]
+    pub fn step(&mut self, debug_id: DebugId, offset: usize)
+    -> Result<(), (Range, ParseError)> {
+        if let Some(ref err) = self.limit { return Err(err.clone()); }
+        self.steps += 1;
+        match self.fuel {
+            Some(fuel) if self.steps > fuel => {
+                Err(self.reach_limit(offset, ParseError::OutOfFuel(debug_id)))
+            }
+            _ => Ok(())
+        }
+    }
+
+    /// Enters a node at an offset.
+    /// Returns an error if the maximum depth is exceeded.
+    /// Call `exit_node` afterwards when this succeeds.
+    #[inline(always)]
+    pub fn enter_node(&mut self, debug_id: DebugId, offset: usize)
+    -> Result<(), (Range, ParseError)> {
+        match self.max_depth {
+            Some(max_depth) if self.depth >= max_depth => {
+                Err(self.reach_limit(offset, ParseError::DepthLimit(debug_id)))
+            }
+            _ => {
+                self.depth += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Exits a node.
+    #[inline(always)]
+    pub fn exit_node(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn reach_limit(&mut self, offset: usize, err: ParseError) -> (Range, ParseError) {
+        let err = (Range::empty(offset), err);
+        self.limit = Some(err.clone());
+        err
+    }
+
+    /// Marks text as examined up to offset.
+    #[inline(always)]
+    pub fn examine(&mut self, offset: usize) {
+        if offset > self.examined { self.examined = offset; }
+    }
+
+    /// Returns `true` if a failed rule should recover from the error,
+    /// which happens when it is one of the errors to recover from.
+    ///
+    /// Otherwise, the error is recorded if recording is enabled,
+    /// such that it can be recovered from when parsing again.
+    pub fn recovers(&mut self, err: &(Range, ParseError)) -> bool {
+        if self.recover.contains(err) { return true; }
+        if let Some(ref mut recoverable) = self.recoverable {
+            if !recoverable.contains(err) { recoverable.push(err.clone()); }
+        }
+        false
     }
 
     /// Reads meta data.
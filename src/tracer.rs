@@ -0,0 +1,133 @@
+use range::Range;
+use std::io::Write;
+
+use {
+    DebugId,
+    ParseError,
+    Rule,
+};
+
+/// Implemented by tracers that get notified when rules are parsed.
+///
+/// Each call to `enter` is followed by a call to either `success` or `fail`,
+/// with the calls of the sub rules in between.
+pub trait Tracer {
+    /// Called before a rule is parsed.
+    fn enter(&mut self, rule: &Rule, debug_id: DebugId, offset: usize);
+    /// Called when the last entered rule succeeds.
+    fn success(&mut self, range: Range);
+    /// Called when the last entered rule fails.
+    fn fail(&mut self, range: Range, error: &ParseError);
+}
+
+/// Writes an indented trace tree.
+///
+/// Each rule is written on a line with its kind, debug id and offset.
+/// Nodes are written with the name of the rule they refer to.
+/// The result is written on a line of its own after the sub rules.
+pub struct TraceWriter<W: Write> {
+    out: W,
+    depth: usize,
+}
+
+impl<W: Write> TraceWriter<W> {
+    /// Creates a new tracer writing to output.
+    pub fn new(out: W) -> TraceWriter<W> {
+        TraceWriter {
+            out: out,
+            depth: 0,
+        }
+    }
+
+    /// Returns the output.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            let _ = write!(self.out, "  ");
+        }
+    }
+}
+
+impl<W: Write> Tracer for TraceWriter<W> {
+    fn enter(&mut self, rule: &Rule, debug_id: DebugId, offset: usize) {
+        self.indent();
+        let _ = match rule {
+            &Rule::Node(ref n) =>
+                writeln!(self.out, "> node {} {:?} at {}", debug_id, n.name, offset),
+            &Rule::Token(ref t) =>
+                writeln!(self.out, "> token {} {:?} at {}", debug_id, t.text, offset),
+            _ => writeln!(self.out, "> {} {} at {}", kind(rule), debug_id, offset),
+        };
+        self.depth += 1;
+    }
+
+    fn success(&mut self, range: Range) {
+        self.depth -= 1;
+        self.indent();
+        let _ = writeln!(self.out, "< ok {}..{}", range.offset, range.next_offset());
+    }
+
+    fn fail(&mut self, range: Range, error: &ParseError) {
+        self.depth -= 1;
+        self.indent();
+        let _ = writeln!(self.out, "< fail at {}: {}", range.offset, error);
+    }
+}
+
+/// Returns the name of the kind of rule.
+pub fn kind(rule: &Rule) -> &'static str {
+    match rule {
+        &Rule::Whitespace(_) => "whitespace",
+        &Rule::Token(_) => "token",
+        &Rule::UntilAny(_) => "until_any",
+        &Rule::UntilAnyOrWhitespace(_) => "until_any_or_whitespace",
+        &Rule::Text(_) => "text",
+        &Rule::Number(_) => "number",
+        &Rule::CharClass(_) => "char_class",
+        &Rule::Select(_) => "select",
+        &Rule::Sequence(_) => "sequence",
+        &Rule::SeparatedBy(_) => "separated_by",
+        &Rule::Repeat(_) => "repeat",
+        &Rule::Lines(_) => "lines",
+        &Rule::Indent(_) => "indent",
+        &Rule::Node(_) => "node",
+        &Rule::Optional(_) => "optional",
+        &Rule::Lookahead(_) => "lookahead",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    #[test]
+    fn select() {
+        let rules = bootstrap::convert(
+            &parse(&bootstrap::rules(), r#"
+1 "a" "a"
+2 "b" "b"
+3 "rule" {@"a" @"b"}
+            "#).unwrap(),
+            &mut vec![]
+        ).unwrap();
+        let mut tracer = TraceWriter::new(vec![]);
+        assert!(parse_with_tracer(&rules, "b", &mut tracer).is_ok());
+        let trace = String::from_utf8(tracer.into_inner()).unwrap();
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(lines, vec![
+            "> select 3003 at 0",
+            "  > node 3001 \"a\" at 0",
+            "    > token 1001 \"a\" at 0",
+            "    < fail at 0: #1001, Expected: `a`",
+            "  < fail at 0: #1001, Expected: `a`",
+            "  > node 3002 \"b\" at 0",
+            "    > token 2001 \"b\" at 0",
+            "    < ok 0..1",
+            "  < ok 0..1",
+            "< ok 0..1",
+        ]);
+    }
+}
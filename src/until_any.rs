@@ -1,7 +1,9 @@
-use read_token;
-use std::rc::Rc;
+use range::Range;
+use read;
+use std::sync::Arc;
 
 use {
+    compose,
     DebugId,
     MetaData,
     ParseError,
@@ -14,11 +16,11 @@ use {
 #[derive(Clone, Debug, PartialEq)]
 pub struct UntilAny {
     /// The characters to stop at.
-    pub any_characters: Rc<String>,
+    pub any_characters: Arc<String>,
     /// Whether empty data is accepted or not.
     pub optional: bool,
     /// The property to store read text.
-    pub property: Option<Rc<String>>,
+    pub property: Option<Arc<String>>,
     /// A debug id to track down the rule generating an error.
     pub debug_id: DebugId,
 }
@@ -29,46 +31,85 @@ impl UntilAny {
         &self,
         tokenizer: &mut Tokenizer,
         state: &TokenizerState,
-        chars: &[char],
+        chars: &str,
         offset: usize
     ) -> ParseResult<TokenizerState> {
-        let (range, _) = read_token::until_any(
-            &self.any_characters, chars, offset);
-        if range.length == 0 && !self.optional {
-            Err((range, ParseError::ExpectedSomething(self.debug_id)))
-        } else {
-            if let Some(ref property) = self.property {
-                let mut text = String::with_capacity(range.length);
-                for c in chars.iter().take(range.length) {
-                    text.push(*c);
+        parse(&self.any_characters, self.optional, self.property.as_ref(),
+            self.debug_id, tokenizer, state, chars, offset)
+    }
+
+    /// Composes the text read until any characters.
+    /// Fails if the text contains any of the characters to stop at.
+    pub fn compose(
+        &self,
+        out: &mut String,
+        data: &[(Range, MetaData)],
+        offset: usize
+    ) -> Result<Range, ()> {
+        match self.property {
+            Some(ref property) => {
+                let (range, val) = try!(compose::meta_string(
+                    property, data, offset));
+                if val.len() == 0 && !self.optional { return Err(()); }
+                if val.chars().any(|c| self.any_characters.contains(c)) {
+                    return Err(());
                 }
-                Ok((range, tokenizer.data(
-                    MetaData::String(property.clone(), Rc::new(text)),
-                    state,
-                    range
-                ), None))
-            } else {
-                Ok((range, state.clone(), None))
+                out.push_str(&val);
+                Ok(range)
+            }
+            None => {
+                // There is no data to tell what the text is.
+                if !self.optional { return Err(()); }
+                Ok(Range::empty(offset))
             }
         }
     }
 }
 
+/// Parses until any of the characters, like `UntilAny`.
+/// This is used by generated code to avoid creating the rule each time.
+pub fn parse(
+    any_characters: &str,
+    optional: bool,
+    property: Option<&Arc<String>>,
+    debug_id: DebugId,
+    tokenizer: &mut Tokenizer,
+    state: &TokenizerState,
+    chars: &str,
+    offset: usize
+) -> ParseResult<TokenizerState> {
+    let (range, _) = read::until_any(any_characters, chars, offset);
+    if range.length == 0 && !optional {
+        Err((range, ParseError::ExpectedSomething(debug_id)))
+    } else {
+        if let Some(property) = property {
+            let text = chars[..range.length].to_owned();
+            Ok((range, tokenizer.data(
+                MetaData::String(property.clone(), Arc::new(text)),
+                state,
+                range
+            ), None))
+        } else {
+            Ok((range, state.clone(), None))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::*;
     use range::Range;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn required() {
         let text = "fn ()";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
         let name = UntilAny {
             debug_id: 0,
-            any_characters: Rc::new("(".into()),
+            any_characters: Arc::new("(".into()),
             optional: false,
             property: None
         };
@@ -80,13 +121,13 @@ mod tests {
     #[test]
     fn successful() {
         let text = "fn foo()";
-        let chars: Vec<char> = text.chars().collect();
+        let chars = text;
         let mut tokenizer = Tokenizer::new();
         let s = TokenizerState::new();
-        let function_name: Rc<String> = Rc::new("function_name".into());
+        let function_name: Arc<String> = Arc::new("function_name".into());
         let name = UntilAny {
             debug_id: 0,
-            any_characters: Rc::new("(".into()),
+            any_characters: Arc::new("(".into()),
             optional: false,
             property: Some(function_name.clone())
         };
@@ -94,6 +135,6 @@ mod tests {
         assert_eq!(res, Ok((Range::new(3, 3), TokenizerState(1), None)));
         assert_eq!(tokenizer.tokens.len(), 1);
         assert_eq!(&tokenizer.tokens[0].1,
-            &MetaData::String(function_name.clone(), Rc::new("foo".into())));
+            &MetaData::String(function_name.clone(), Arc::new("foo".into())));
     }
 }
@@ -1,7 +1,9 @@
-use read_token;
+use read;
 use range::Range;
+use std::sync::Arc;
 
 use {
+    Comments,
     DebugId,
     ParseError,
 };
@@ -13,21 +15,47 @@ pub struct Whitespace {
     pub optional: bool,
     /// A debug id to track down the rule generating an error.
     pub debug_id: DebugId,
+    /// The comments to skip as whitespace, if any.
+    pub comments: Option<Arc<Comments>>,
 }
 
 impl Whitespace {
     /// Parse whitespace.
     /// If whitespace is required and no whitespace is found,
     /// an error will be reported.
-    pub fn parse(&self, chars: &[char], offset: usize) ->
+    pub fn parse(&self, chars: &str, offset: usize) ->
         Result<Range, (Range, ParseError)>
     {
-        let range = read_token::whitespace(chars, offset);
-        if range.length == 0 && !self.optional {
-            Err((range, ParseError::ExpectedWhitespace(self.debug_id)))
-        } else {
-            Ok(range)
-        }
+        let len = match self.comments {
+            None => Ok(read::whitespace_len(chars)),
+            Some(ref comments) => comments.whitespace_len(chars, true),
+        };
+        check(len, self.optional, self.debug_id, chars, offset)
+    }
+
+    /// Composes whitespace.
+    /// Writes a single space if whitespace is required.
+    pub fn compose(&self, out: &mut String, offset: usize) -> Range {
+        if !self.optional { out.push(' '); }
+        Range::empty(offset)
+    }
+}
+
+/// Reports an error if a block comment does not end,
+/// or if whitespace is required and no whitespace is found.
+pub fn check(
+    len: Result<usize, usize>,
+    optional: bool,
+    debug_id: DebugId,
+    chars: &str,
+    offset: usize
+) -> Result<Range, (Range, ParseError)> {
+    match len {
+        Err(i) => Err((Range::new(offset + i, chars.len() - i),
+            ParseError::ExpectedCommentEnd(debug_id))),
+        Ok(0) if !optional => Err((Range::new(offset, 0),
+            ParseError::ExpectedWhitespace(debug_id))),
+        Ok(len) => Ok(Range::new(offset, len)),
     }
 }
 
@@ -39,8 +67,12 @@ mod tests {
     #[test]
     fn optional() {
         let text = "a,b, c";
-        let chars: Vec<char> = text.chars().collect();
-        let optional_whitespace = Whitespace { debug_id: 0, optional: true };
+        let chars = text;
+        let optional_whitespace = Whitespace {
+            debug_id: 0,
+            optional: true,
+            comments: None,
+        };
         assert_eq!(optional_whitespace.parse(&chars, 0),
             Ok(Range::new(0, 0)));
         assert_eq!(optional_whitespace.parse(&chars[4..], 4),
@@ -50,8 +82,12 @@ mod tests {
     #[test]
     fn required() {
         let text = "a,   b,c";
-        let chars: Vec<char> = text.chars().collect();
-        let required_whitespace = Whitespace { debug_id: 0, optional: false };
+        let chars = text;
+        let required_whitespace = Whitespace {
+            debug_id: 0,
+            optional: false,
+            comments: None,
+        };
         assert_eq!(required_whitespace.parse(&chars[2..], 2),
             Ok(Range::new(2, 3)));
         // Prints an error message to standard error output.